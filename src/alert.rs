@@ -0,0 +1,417 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::app::CoinData;
+use crate::indicator::sma;
+use crate::source::{Pair, DEFAULT_QUOTE};
+use crate::ui::format_price;
+
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Above(f64),
+    Below(f64),
+    /// Price moves by at least `percent` in either direction within `window`.
+    PercentMove {
+        percent: f64,
+        window: Duration,
+    },
+    /// Price crosses its simple moving average over `period` candles.
+    CrossSma {
+        period: usize,
+    },
+}
+
+/// What to watch for on which symbol.
+///
+/// Parsed from `BTC>70000`, `BTC<60000`, `ETH%5/1h` or `SOL~sma20`; the
+/// coin may also be a full pair such as `ETH/BTC>0.05`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct AlertRule {
+    pub pair: Pair,
+    pub condition: Condition,
+    /// Whether `pair` came with its own quote or fell back to the default
+    explicit_quote: bool,
+}
+
+impl AlertRule {
+    /// Re-quotes a rule given as a bare coin, so `BTC>60000` follows `--quote`.
+    pub fn with_default_quote(mut self, quote: &str) -> Self {
+        if !self.explicit_quote {
+            self.pair.quote = quote.to_uppercase();
+        }
+        self
+    }
+}
+
+impl FromStr for AlertRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(['>', '<', '%', '~'])
+            .ok_or_else(|| format!("invalid alert '{}': expected one of > < % ~", s))?;
+        let (coin, rest) = s.split_at(split);
+        let pair = Pair::parse_with_quote(coin, DEFAULT_QUOTE)
+            .map_err(|_| format!("invalid alert '{}': bad coin '{}'", s, coin.trim()))?;
+        let (op, value) = rest.split_at(1);
+        let value = value.trim();
+
+        let condition = match op {
+            ">" => Condition::Above(parse_number(s, value)?),
+            "<" => Condition::Below(parse_number(s, value)?),
+            "%" => {
+                let (percent, window) = value
+                    .split_once('/')
+                    .ok_or_else(|| format!("invalid alert '{}': expected PCT/WINDOW", s))?;
+                Condition::PercentMove {
+                    percent: parse_number(s, percent)?,
+                    window: parse_duration(window)
+                        .map_err(|e| format!("invalid alert '{}': {}", s, e))?,
+                }
+            }
+            _ => {
+                let period = value
+                    .to_lowercase()
+                    .strip_prefix("sma")
+                    .and_then(|p| p.parse::<usize>().ok())
+                    .filter(|&p| p > 1)
+                    .ok_or_else(|| format!("invalid alert '{}': expected smaN", s))?;
+                Condition::CrossSma { period }
+            }
+        };
+
+        Ok(Self {
+            explicit_quote: coin.contains('/'),
+            pair,
+            condition,
+        })
+    }
+}
+
+impl TryFrom<String> for AlertRule {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+fn parse_number(alert: &str, value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite() && *v > 0.0)
+        .ok_or_else(|| format!("invalid alert '{}': bad number '{}'", alert, value))
+}
+
+/// Parses `90s`, `15m`, `4h` or `1d`.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s
+        .find(|ch: char| !ch.is_ascii_digit())
+        .ok_or_else(|| format!("missing unit in duration '{}'", s))?;
+    let (amount, unit) = s.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("bad duration '{}'", s))?;
+    let scale = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        _ => return Err(format!("unknown unit '{}' in duration '{}'", unit, s)),
+    };
+    let secs = amount
+        .checked_mul(scale)
+        .ok_or_else(|| format!("duration '{}' is too long", s))?;
+    if secs == 0 {
+        return Err(format!("duration '{}' must be positive", s));
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs.is_multiple_of(86_400) {
+        format!("{}d", secs / 86_400)
+    } else if secs.is_multiple_of(3_600) {
+        format!("{}h", secs / 3_600)
+    } else if secs.is_multiple_of(60) {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// A rule fired at `at_ms` (Unix milliseconds).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertEvent {
+    pub symbol: String,
+    pub message: String,
+    pub at_ms: i64,
+}
+
+struct Alert {
+    rule: AlertRule,
+    cooldown: Duration,
+    last_fired: Option<i64>,
+    samples: VecDeque<(i64, f64)>,
+    above_sma: Option<bool>,
+}
+
+impl Alert {
+    fn check(&mut self, coin: &CoinData, now_ms: i64) -> Option<String> {
+        // Nothing to compare against until the first price arrives
+        let price = coin.price;
+        if price <= 0.0 {
+            return None;
+        }
+        match self.rule.condition {
+            Condition::Above(threshold) if price > threshold => Some(format!(
+                "{} above {}: {}",
+                coin.pair,
+                format_price(threshold, coin),
+                format_price(price, coin)
+            )),
+            Condition::Below(threshold) if price < threshold => Some(format!(
+                "{} below {}: {}",
+                coin.pair,
+                format_price(threshold, coin),
+                format_price(price, coin)
+            )),
+            Condition::Above(_) | Condition::Below(_) => None,
+            Condition::PercentMove { percent, window } => {
+                let window_ms = window.as_millis() as i64;
+                self.samples.push_back((now_ms, price));
+                while let Some(&(ts, _)) = self.samples.front() {
+                    if ts >= now_ms - window_ms {
+                        break;
+                    }
+                    self.samples.pop_front();
+                }
+                let low = self
+                    .samples
+                    .iter()
+                    .map(|s| s.1)
+                    .fold(f64::INFINITY, f64::min);
+                let high = self
+                    .samples
+                    .iter()
+                    .map(|s| s.1)
+                    .fold(f64::NEG_INFINITY, f64::max);
+                let up = (price - low) / low * 100.0;
+                let down = (high - price) / high * 100.0;
+                let (moved, arrow) = if up >= down {
+                    (up, "▲")
+                } else {
+                    (down, "▼")
+                };
+                (moved >= percent).then(|| {
+                    format!(
+                        "{} {} {:.2}% in {}: {}",
+                        coin.pair,
+                        arrow,
+                        moved,
+                        format_duration(window),
+                        format_price(price, coin)
+                    )
+                })
+            }
+            Condition::CrossSma { period } => {
+                let sma = sma(coin.price_history.iter().map(|c| c.close), period)?;
+                let above = price > sma;
+                let crossed = self.above_sma.is_some_and(|prev| prev != above);
+                self.above_sma = Some(above);
+                crossed.then(|| {
+                    format!(
+                        "{} crossed {} SMA{} ({}): {}",
+                        coin.pair,
+                        if above { "above" } else { "below" },
+                        period,
+                        format_price(sma, coin),
+                        format_price(price, coin)
+                    )
+                })
+            }
+        }
+    }
+}
+
+/// Evaluates alert rules against coin updates, rate-limited per alert.
+#[derive(Default)]
+pub struct AlertEngine {
+    alerts: Vec<Alert>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>, cooldown: Duration) -> Self {
+        let alerts = rules
+            .into_iter()
+            .map(|rule| Alert {
+                rule,
+                cooldown,
+                last_fired: None,
+                samples: VecDeque::new(),
+                above_sma: None,
+            })
+            .collect();
+        Self { alerts }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alerts.is_empty()
+    }
+
+    /// Checks every rule for `coin`; rules still cooling down keep tracking
+    /// state but stay quiet.
+    pub fn evaluate(&mut self, coin: &CoinData, now_ms: i64) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+        for alert in self.alerts.iter_mut().filter(|a| a.rule.pair == coin.pair) {
+            let Some(message) = alert.check(coin, now_ms) else {
+                continue;
+            };
+            let cooling = alert
+                .last_fired
+                .is_some_and(|last| now_ms - last < alert.cooldown.as_millis() as i64);
+            if cooling {
+                continue;
+            }
+            alert.last_fired = Some(now_ms);
+            events.push(AlertEvent {
+                symbol: coin.symbol.clone(),
+                message,
+                at_ms: now_ms,
+            });
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::Candle;
+
+    fn coin_at(price: f64) -> CoinData {
+        let mut coin = CoinData::new(Pair::new("BTC", "USDT"));
+        coin.price = price;
+        coin
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!(
+            "btc>70000".parse::<AlertRule>(),
+            Ok(AlertRule {
+                pair: Pair::new("BTC", "USDT"),
+                condition: Condition::Above(70000.0),
+                explicit_quote: false,
+            })
+        );
+        assert_eq!(
+            "eth/btc>0.05".parse::<AlertRule>().map(|r| r.pair),
+            Ok(Pair::new("ETH", "BTC"))
+        );
+        assert_eq!(
+            "ETH < 1500.5".parse::<AlertRule>().map(|r| r.condition),
+            Ok(Condition::Below(1500.5))
+        );
+        assert_eq!(
+            "SOL%5/1h".parse::<AlertRule>().map(|r| r.condition),
+            Ok(Condition::PercentMove {
+                percent: 5.0,
+                window: Duration::from_secs(3600),
+            })
+        );
+        assert_eq!(
+            "DOGE~sma20".parse::<AlertRule>().map(|r| r.condition),
+            Ok(Condition::CrossSma { period: 20 })
+        );
+    }
+
+    #[test]
+    fn test_parse_rules_rejects_garbage() {
+        assert!("BTC".parse::<AlertRule>().is_err());
+        assert!(">100".parse::<AlertRule>().is_err());
+        assert!("BTC>abc".parse::<AlertRule>().is_err());
+        assert!("BTC%5".parse::<AlertRule>().is_err());
+        assert!("BTC%5/1y".parse::<AlertRule>().is_err());
+        assert!("BTC~ema20".parse::<AlertRule>().is_err());
+        assert!("BTC/>1".parse::<AlertRule>().is_err());
+        assert!("BTC%5/999999999999999999d".parse::<AlertRule>().is_err());
+    }
+
+    #[test]
+    fn test_default_quote_only_applies_to_bare_coins() {
+        let bare: AlertRule = "BTC>60000".parse().unwrap();
+        assert_eq!(bare.with_default_quote("eur").pair, Pair::new("BTC", "EUR"));
+
+        let quoted: AlertRule = "ETH/BTC>0.05".parse().unwrap();
+        assert_eq!(
+            quoted.with_default_quote("EUR").pair,
+            Pair::new("ETH", "BTC")
+        );
+    }
+
+    #[test]
+    fn test_threshold_with_cooldown() {
+        let rule = "BTC>100".parse().unwrap();
+        let mut engine = AlertEngine::new(vec![rule], Duration::from_secs(60));
+
+        assert!(engine.evaluate(&coin_at(99.0), 0).is_empty());
+        assert_eq!(engine.evaluate(&coin_at(101.0), 1_000).len(), 1);
+        // Still above, but cooling down
+        assert!(engine.evaluate(&coin_at(102.0), 30_000).is_empty());
+        assert_eq!(engine.evaluate(&coin_at(102.0), 61_000).len(), 1);
+    }
+
+    #[test]
+    fn test_percent_move_window() {
+        let rule = "BTC%5/1m".parse().unwrap();
+        let mut engine = AlertEngine::new(vec![rule], Duration::from_secs(1));
+
+        assert!(engine.evaluate(&coin_at(100.0), 0).is_empty());
+        assert!(engine.evaluate(&coin_at(103.0), 20_000).is_empty());
+        let events = engine.evaluate(&coin_at(106.0), 40_000);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].message.contains("▲ 6.00%"));
+
+        // The 100 sample has left the window; 103 -> 104 is too small
+        assert!(engine.evaluate(&coin_at(104.0), 90_000).is_empty());
+    }
+
+    #[test]
+    fn test_sma_cross() {
+        let rule = "BTC~sma3".parse().unwrap();
+        let mut engine = AlertEngine::new(vec![rule], Duration::from_secs(1));
+        let mut coin = coin_at(9.0);
+        coin.load_history(vec![
+            Candle::flat(0, 10.0),
+            Candle::flat(1, 10.0),
+            Candle::flat(2, 9.0),
+        ]);
+        // First evaluation only establishes the side
+        assert!(engine.evaluate(&coin, 0).is_empty());
+
+        coin.price = 12.0;
+        coin.apply_kline(Candle::flat(3, 12.0));
+        let events = engine.evaluate(&coin, 10_000);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].message.contains("crossed above SMA3"));
+    }
+
+    #[test]
+    fn test_other_symbols_are_ignored() {
+        let rule = "ETH>1".parse().unwrap();
+        let mut engine = AlertEngine::new(vec![rule], DEFAULT_COOLDOWN);
+        assert!(engine.evaluate(&coin_at(100.0), 0).is_empty());
+    }
+}
@@ -1,12 +1,20 @@
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Response, StatusCode};
 use serde::Deserialize;
 
+use crate::metrics::Metrics;
+use crate::source::{
+    Candle, Depth, Level, MarketDataSource, RateLimit, SymbolInfo, Ticker, Timeframe, Trade,
+};
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TickerData {
-    #[allow(dead_code)]
     pub symbol: String,
     #[serde(deserialize_with = "deserialize_f64")]
     pub last_price: f64,
@@ -18,9 +26,131 @@ pub struct TickerData {
     pub low_price: f64,
     #[serde(deserialize_with = "deserialize_f64")]
     pub volume: f64,
+    #[serde(deserialize_with = "deserialize_f64")]
+    pub open_price: f64,
+    #[serde(deserialize_with = "deserialize_f64")]
+    pub quote_volume: f64,
+    #[serde(deserialize_with = "deserialize_f64")]
+    pub weighted_avg_price: f64,
+    pub count: u64,
+}
+
+impl From<TickerData> for Ticker {
+    fn from(data: TickerData) -> Self {
+        Self {
+            symbol: data.symbol,
+            last_price: data.last_price,
+            price_change_percent: data.price_change_percent,
+            high_price: data.high_price,
+            low_price: data.low_price,
+            volume: data.volume,
+            open_price: data.open_price,
+            quote_volume: data.quote_volume,
+            weighted_avg_price: Some(data.weighted_avg_price),
+            trade_count: Some(data.count),
+        }
+    }
+}
+
+/// An aggregate trade from `/api/v3/aggTrades`: fills of one taker order
+/// at one price.
+#[derive(Debug, Deserialize)]
+struct AggTradeData {
+    #[serde(rename = "a")]
+    id: u64,
+    #[serde(rename = "p", deserialize_with = "deserialize_f64")]
+    price: f64,
+    #[serde(rename = "q", deserialize_with = "deserialize_f64")]
+    quantity: f64,
+    #[serde(rename = "T")]
+    time: i64,
+    #[serde(rename = "m")]
+    buyer_maker: bool,
+}
+
+impl From<AggTradeData> for Trade {
+    fn from(data: AggTradeData) -> Self {
+        Self {
+            id: data.id,
+            price: data.price,
+            quantity: data.quantity,
+            time: data.time,
+            buyer_maker: data.buyer_maker,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DepthData {
+    last_update_id: u64,
+    #[serde(deserialize_with = "deserialize_levels")]
+    bids: Vec<Level>,
+    #[serde(deserialize_with = "deserialize_levels")]
+    asks: Vec<Level>,
+}
+
+impl From<DepthData> for Depth {
+    fn from(data: DepthData) -> Self {
+        Self {
+            last_update_id: data.last_update_id,
+            bids: data.bids,
+            asks: data.asks,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ExchangeInfo {
+    symbols: Vec<ExchangeSymbol>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExchangeSymbol {
+    symbol: String,
+    status: String,
+    base_asset: String,
+    quote_asset: String,
+    quote_asset_precision: u32,
+    #[serde(default)]
+    filters: Vec<SymbolFilter>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "filterType")]
+enum SymbolFilter {
+    #[serde(rename = "PRICE_FILTER", rename_all = "camelCase")]
+    Price {
+        #[serde(deserialize_with = "deserialize_f64")]
+        tick_size: f64,
+    },
+    #[serde(other)]
+    Other,
+}
+
+impl From<ExchangeSymbol> for SymbolInfo {
+    fn from(s: ExchangeSymbol) -> Self {
+        let tick_size = s
+            .filters
+            .iter()
+            .find_map(|f| match f {
+                SymbolFilter::Price { tick_size } => Some(*tick_size),
+                SymbolFilter::Other => None,
+            })
+            .unwrap_or(0.0);
+        Self {
+            trading: s.status == "TRADING",
+            symbol: s.symbol,
+            base: s.base_asset,
+            quote: s.quote_asset,
+            tick_size,
+            quote_precision: s.quote_asset_precision,
+        }
+    }
 }
 
-fn deserialize_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
+pub(crate) fn deserialize_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -28,9 +158,102 @@ where
     s.parse().map_err(serde::de::Error::custom)
 }
 
+/// Book levels arrive as `[price, quantity]` string pairs.
+pub(crate) fn deserialize_levels<'de, D>(deserializer: D) -> Result<Vec<Level>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let levels: Vec<[String; 2]> = Deserialize::deserialize(deserializer)?;
+    levels
+        .iter()
+        .map(|[price, quantity]| {
+            Ok(Level {
+                price: price.parse().map_err(serde::de::Error::custom)?,
+                quantity: quantity.parse().map_err(serde::de::Error::custom)?,
+            })
+        })
+        .collect()
+}
+
+/// Klines arrive as positional arrays:
+/// `[open_time, open, high, low, close, volume, close_time, ...]`
+/// with prices and volume encoded as strings.
+fn parse_kline(kline: &[serde_json::Value]) -> Option<Candle> {
+    let field = |i: usize| -> Option<f64> { kline.get(i)?.as_str()?.parse().ok() };
+    Some(Candle {
+        open_time: kline.first()?.as_i64()?,
+        open: field(1)?,
+        high: field(2)?,
+        low: field(3)?,
+        close: field(4)?,
+        volume: field(5)?,
+    })
+}
+
+/// Upper bound on symbols per batched ticker request; Binance charges the
+/// same weight for 21 to 100 symbols.
+const MAX_BATCH_SYMBOLS: usize = 100;
+
+/// Most klines Binance returns per request.
+pub const MAX_KLINES: u32 = 1000;
+
+/// Formats the `symbols` query parameter: a JSON array, `["BTCUSDT","ETHUSDT"]`.
+fn symbols_param(symbols: &[String]) -> String {
+    serde_json::to_string(symbols).unwrap_or_default()
+}
+
+/// Binance's REQUEST_WEIGHT budget per minute for spot market data.
+const WEIGHT_LIMIT: u32 = 6000;
+const WEIGHT_HEADER: &str = "x-mbx-used-weight-1m";
+/// Assumed ban length when a 429/418 arrives without `Retry-After`.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60);
+/// Transient failures are retried this many times before giving up.
+const MAX_RETRIES: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(4);
+
+/// Delay before retry number `attempt` (0-based): exponential, capped, with
+/// `jitter` in [0, 1) spreading it over the upper half of the window.
+fn backoff_delay(attempt: u32, jitter: f64) -> Duration {
+    let ceiling = RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(RETRY_MAX_DELAY);
+    ceiling.mul_f64(0.5 + jitter.clamp(0.0, 1.0) * 0.5)
+}
+
+/// `Retry-After` in seconds, as Binance sends it on 429 and 418.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let secs: u64 = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+fn used_weight(headers: &HeaderMap) -> Option<u32> {
+    headers
+        .get(WEIGHT_HEADER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[derive(Debug, Default)]
+struct LimitState {
+    used_weight: u32,
+    resume_at: Option<Instant>,
+}
+
 pub struct BinanceClient {
     client: reqwest::Client,
     base_url: String,
+    limits: Mutex<LimitState>,
+    metrics: Option<Arc<Metrics>>,
 }
 
 impl BinanceClient {
@@ -41,12 +264,75 @@ impl BinanceClient {
         Ok(Self {
             client,
             base_url: "https://api.binance.com".to_string(),
+            limits: Mutex::new(LimitState::default()),
+            metrics: None,
         })
     }
 
+    /// Records request latency and failures in `metrics`.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Sends a GET, tracking used weight and retrying timeouts, connection
+    /// failures and 5xx responses. While a 429/418 ban is in effect requests
+    /// fail immediately instead of extending it.
+    async fn get(&self, url: &str, query: &[(&str, String)]) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            if let Some(wait) = self.rate_limit().and_then(|l| l.remaining(Instant::now())) {
+                return Err(anyhow!(
+                    "rate limited, resuming in {}s",
+                    wait.as_secs_f64().ceil()
+                ));
+            }
+
+            let started = Instant::now();
+            let result = self.client.get(url).query(query).send().await;
+            if let Some(metrics) = &self.metrics {
+                let endpoint = url.strip_prefix(&self.base_url).unwrap_or(url);
+                match &result {
+                    Ok(resp) => {
+                        metrics.observe_request(endpoint, started.elapsed());
+                        if !resp.status().is_success() {
+                            metrics.count_error(resp.status().as_str());
+                        }
+                    }
+                    Err(e) if e.is_timeout() => metrics.count_error("timeout"),
+                    Err(e) if e.is_connect() => metrics.count_error("connect"),
+                    Err(_) => metrics.count_error("other"),
+                }
+            }
+
+            match result {
+                Ok(resp) => {
+                    let status = resp.status();
+                    let mut limits = self.limits.lock().unwrap();
+                    if let Some(weight) = used_weight(resp.headers()) {
+                        limits.used_weight = weight;
+                    }
+                    if status == StatusCode::TOO_MANY_REQUESTS || status.as_u16() == 418 {
+                        let wait = retry_after(resp.headers()).unwrap_or(DEFAULT_RETRY_AFTER);
+                        limits.resume_at = Some(Instant::now() + wait);
+                        return Err(anyhow!("rate limited, resuming in {}s", wait.as_secs()));
+                    }
+                    if !status.is_server_error() || attempt >= MAX_RETRIES {
+                        return Ok(resp);
+                    }
+                }
+                Err(e) if (e.is_timeout() || e.is_connect()) && attempt < MAX_RETRIES => {}
+                Err(e) => return Err(e.into()),
+            }
+
+            tokio::time::sleep(backoff_delay(attempt, fastrand::f64())).await;
+            attempt += 1;
+        }
+    }
+
     pub async fn get_ticker_24h(&self, symbol: &str) -> Result<TickerData> {
-        let url = format!("{}/api/v3/ticker/24hr?symbol={}", self.base_url, symbol);
-        let resp = self.client.get(&url).send().await?;
+        let url = format!("{}/api/v3/ticker/24hr", self.base_url);
+        let resp = self.get(&url, &[("symbol", symbol.to_string())]).await?;
         if !resp.status().is_success() {
             return Err(anyhow!("API error for {}: {}", symbol, resp.status()));
         }
@@ -54,42 +340,332 @@ impl BinanceClient {
         Ok(data)
     }
 
-    pub async fn get_tickers(&self, symbols: &[String]) -> Vec<Result<TickerData>> {
-        futures::future::join_all(symbols.iter().map(|s| self.get_ticker_24h(s))).await
+    /// 24h tickers for all `symbols`, one request per `MAX_BATCH_SYMBOLS`.
+    /// Binance rejects a whole batch over one unknown symbol, so a rejected
+    /// batch is retried a symbol at a time and the failing symbols left out.
+    /// If no ticker comes back at all, the first failure is returned.
+    pub async fn get_tickers(&self, symbols: &[String]) -> Result<Vec<TickerData>> {
+        let requests = symbols.chunks(MAX_BATCH_SYMBOLS).map(|chunk| async move {
+            let url = format!("{}/api/v3/ticker/24hr", self.base_url);
+            let resp = self.get(&url, &[("symbols", symbols_param(chunk))]).await?;
+            if resp.status().is_client_error() {
+                let singles = chunk.iter().map(|symbol| self.get_ticker_24h(symbol));
+                return Ok(futures::future::join_all(singles).await);
+            }
+            if !resp.status().is_success() {
+                return Err(anyhow!("API error for tickers: {}", resp.status()));
+            }
+            let data: Vec<TickerData> = resp.json().await?;
+            Ok(data.into_iter().map(Ok).collect())
+        });
+        let batches = futures::future::try_join_all(requests).await?;
+        let mut tickers = Vec::with_capacity(symbols.len());
+        let mut first_error = None;
+        for result in batches.into_iter().flatten() {
+            match result {
+                Ok(ticker) => tickers.push(ticker),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            Some(e) if tickers.is_empty() => Err(e),
+            _ => Ok(tickers),
+        }
     }
 
-    pub async fn get_klines_batch(
+    /// Klines oldest first: the latest `limit`, or the first `limit` opening
+    /// at or after `start_time`.
+    pub async fn get_klines(
         &self,
-        symbols: &[String],
+        symbol: &str,
+        timeframe: Timeframe,
+        start_time: Option<i64>,
         limit: u32,
-    ) -> Vec<Result<Vec<(i64, f64)>>> {
-        futures::future::join_all(symbols.iter().map(|s| self.get_klines(s, limit))).await
+    ) -> Result<Vec<Candle>> {
+        let url = format!("{}/api/v3/klines", self.base_url);
+        let mut query = vec![
+            ("symbol", symbol.to_string()),
+            ("interval", timeframe.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        if let Some(start_time) = start_time {
+            query.push(("startTime", start_time.to_string()));
+        }
+        let resp = self.get(&url, &query).await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("API error for {}: {}", symbol, resp.status()));
+        }
+        let data: Vec<Vec<serde_json::Value>> = resp.json().await?;
+
+        Ok(data.iter().filter_map(|kline| parse_kline(kline)).collect())
     }
 
-    pub async fn get_klines(&self, symbol: &str, limit: u32) -> Result<Vec<(i64, f64)>> {
-        let url = format!(
-            "{}/api/v3/klines?symbol={}&interval=15m&limit={}",
-            self.base_url, symbol, limit
-        );
-        let resp = self.client.get(&url).send().await?;
+    /// The last `limit` aggregate trades, oldest first as Binance sends them.
+    pub async fn get_recent_trades(&self, symbol: &str, limit: u32) -> Result<Vec<Trade>> {
+        let url = format!("{}/api/v3/aggTrades", self.base_url);
+        let query = [("symbol", symbol.to_string()), ("limit", limit.to_string())];
+        let resp = self.get(&url, &query).await?;
         if !resp.status().is_success() {
             return Err(anyhow!("API error for {}: {}", symbol, resp.status()));
         }
-        let data: Vec<Vec<serde_json::Value>> = resp.json().await?;
+        let data: Vec<AggTradeData> = resp.json().await?;
 
-        // Extract (open_time, close_price) from each kline
-        let prices: Vec<(i64, f64)> = data
-            .iter()
-            .filter_map(|kline| {
-                let ts = kline.first().and_then(|v| v.as_i64())?;
-                let price = kline
-                    .get(4)
-                    .and_then(|v| v.as_str())
-                    .and_then(|s| s.parse().ok())?;
-                Some((ts, price))
+        Ok(data.into_iter().map(Trade::from).collect())
+    }
+
+    pub async fn get_depth(&self, symbol: &str, limit: u32) -> Result<Depth> {
+        let url = format!("{}/api/v3/depth", self.base_url);
+        let query = [("symbol", symbol.to_string()), ("limit", limit.to_string())];
+        let resp = self.get(&url, &query).await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("API error for {}: {}", symbol, resp.status()));
+        }
+        let data: DepthData = resp.json().await?;
+
+        Ok(data.into())
+    }
+
+    pub async fn get_exchange_info(&self) -> Result<Vec<SymbolInfo>> {
+        let url = format!("{}/api/v3/exchangeInfo", self.base_url);
+        let resp = self.get(&url, &[]).await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("API error for exchangeInfo: {}", resp.status()));
+        }
+        let info: ExchangeInfo = resp.json().await?;
+
+        Ok(info.symbols.into_iter().map(SymbolInfo::from).collect())
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for BinanceClient {
+    async fn ticker(&self, symbol: &str) -> Result<Ticker> {
+        self.get_ticker_24h(symbol).await.map(Ticker::from)
+    }
+
+    async fn tickers(&self, symbols: &[String]) -> Result<Vec<Ticker>> {
+        let data = self.get_tickers(symbols).await?;
+        Ok(data.into_iter().map(Ticker::from).collect())
+    }
+
+    async fn klines(&self, symbol: &str, timeframe: Timeframe, limit: u32) -> Result<Vec<Candle>> {
+        self.get_klines(symbol, timeframe, None, limit).await
+    }
+
+    async fn klines_since(
+        &self,
+        symbol: &str,
+        timeframe: Timeframe,
+        start_ms: i64,
+        limit: u32,
+    ) -> Result<Vec<Candle>> {
+        self.get_klines(symbol, timeframe, Some(start_ms), limit)
+            .await
+    }
+
+    async fn symbols(&self) -> Result<Vec<SymbolInfo>> {
+        self.get_exchange_info().await
+    }
+
+    async fn recent_trades(&self, symbol: &str, limit: u32) -> Result<Vec<Trade>> {
+        let mut trades = self.get_recent_trades(symbol, limit).await?;
+        trades.reverse();
+        Ok(trades)
+    }
+
+    async fn depth(&self, symbol: &str, limit: u32) -> Result<Depth> {
+        self.get_depth(symbol, limit).await
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        let limits = self.limits.lock().unwrap();
+        Some(RateLimit {
+            used_weight: limits.used_weight,
+            weight_limit: WEIGHT_LIMIT,
+            resume_at: limits.resume_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_kline() {
+        let kline: Vec<serde_json::Value> = serde_json::from_str(
+            r#"[1700000000000,"100.0","120.0","90.0","110.0","42.5",1700000899999,"4675.0",12,"20.0","2200.0","0"]"#,
+        )
+        .unwrap();
+        let candle = parse_kline(&kline).unwrap();
+        assert_eq!(candle.open_time, 1700000000000);
+        assert_eq!(candle.open, 100.0);
+        assert_eq!(candle.high, 120.0);
+        assert_eq!(candle.low, 90.0);
+        assert_eq!(candle.close, 110.0);
+        assert_eq!(candle.volume, 42.5);
+    }
+
+    #[test]
+    fn test_parse_kline_rejects_malformed() {
+        let kline: Vec<serde_json::Value> =
+            serde_json::from_str(r#"[1700000000000,"100.0","oops"]"#).unwrap();
+        assert!(parse_kline(&kline).is_none());
+    }
+
+    #[test]
+    fn test_parse_exchange_symbol() {
+        let text = r#"{"symbol":"ETHBTC","status":"TRADING","baseAsset":"ETH","baseAssetPrecision":8,"quoteAsset":"BTC","quotePrecision":8,"quoteAssetPrecision":8,"filters":[{"filterType":"PRICE_FILTER","minPrice":"0.00001000","maxPrice":"922327.00000000","tickSize":"0.00001000"},{"filterType":"LOT_SIZE","minQty":"0.00010000","maxQty":"100000.00000000","stepSize":"0.00010000"}]}"#;
+        let symbol: ExchangeSymbol = serde_json::from_str(text).unwrap();
+        let info = SymbolInfo::from(symbol);
+        assert_eq!(info.symbol, "ETHBTC");
+        assert!(info.trading);
+        assert_eq!(info.tick_size, 0.00001);
+        assert_eq!(info.price_decimals(), 5);
+    }
+
+    #[test]
+    fn test_symbols_param() {
+        let symbols = vec!["BTCUSDT".to_string(), "ETHBTC".to_string()];
+        assert_eq!(symbols_param(&symbols), r#"["BTCUSDT","ETHBTC"]"#);
+    }
+
+    #[test]
+    fn test_parse_batched_tickers() {
+        let text = r#"[{"symbol":"BTCUSDT","priceChange":"-94.99","priceChangePercent":"-0.095","weightedAvgPrice":"99786.83","prevClosePrice":"100000.01","lastPrice":"99905.01","lastQty":"0.001","bidPrice":"99905.00","bidQty":"1.2","askPrice":"99905.01","askQty":"3.4","openPrice":"100000.00","highPrice":"101000.00","lowPrice":"98000.00","volume":"12345.6","quoteVolume":"1231231231.0","openTime":1700000000000,"closeTime":1700086399999,"firstId":1,"lastId":100,"count":100},{"symbol":"ETHBTC","priceChange":"0.0001","priceChangePercent":"0.2","weightedAvgPrice":"0.05","prevClosePrice":"0.05","lastPrice":"0.05010","lastQty":"1","bidPrice":"0.05","bidQty":"1","askPrice":"0.0501","askQty":"1","openPrice":"0.05","highPrice":"0.051","lowPrice":"0.049","volume":"5000","quoteVolume":"250","openTime":1700000000000,"closeTime":1700086399999,"firstId":1,"lastId":2,"count":2}]"#;
+        let tickers: Vec<Ticker> = serde_json::from_str::<Vec<TickerData>>(text)
+            .unwrap()
+            .into_iter()
+            .map(Ticker::from)
+            .collect();
+        assert_eq!(tickers.len(), 2);
+        assert_eq!(tickers[1].symbol, "ETHBTC");
+        assert_eq!(tickers[1].last_price, 0.0501);
+        assert_eq!(tickers[0].price_change_percent, -0.095);
+        assert_eq!(tickers[0].open_price, 100000.0);
+        assert_eq!(tickers[0].weighted_avg_price, Some(99786.83));
+        assert_eq!(tickers[0].trade_count, Some(100));
+        assert_eq!(tickers[1].quote_volume, 250.0);
+    }
+
+    #[tokio::test]
+    async fn test_tickers_skip_unknown_symbols() {
+        use axum::extract::Query;
+        use axum::http::StatusCode;
+        use axum::routing::get;
+        use std::collections::HashMap;
+
+        let handler = |Query(query): Query<HashMap<String, String>>| async move {
+            let symbols: Vec<String> = match (query.get("symbols"), query.get("symbol")) {
+                (Some(batch), _) => serde_json::from_str(batch).unwrap(),
+                (None, Some(symbol)) => vec![symbol.clone()],
+                (None, None) => vec![],
+            };
+            if symbols.iter().any(|s| s == "NOPEUSDT") {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            let tickers: Vec<String> = symbols
+                .iter()
+                .map(|s| {
+                    format!(
+                        r#"{{"symbol":"{}","lastPrice":"1","priceChangePercent":"0","highPrice":"1","lowPrice":"1","volume":"1","openPrice":"1","quoteVolume":"1","weightedAvgPrice":"1","count":1}}"#,
+                        s
+                    )
+                })
+                .collect();
+            Ok(if query.contains_key("symbols") {
+                format!("[{}]", tickers.join(","))
+            } else {
+                tickers.concat()
             })
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = axum::Router::new().route("/api/v3/ticker/24hr", get(handler));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let client = BinanceClient {
+            base_url: format!("http://{}", addr),
+            ..BinanceClient::new().unwrap()
+        };
+        let symbols = ["BTCUSDT", "NOPEUSDT", "ETHUSDT"].map(String::from);
+        let tickers = client.tickers(&symbols).await.unwrap();
+        let mut got: Vec<&str> = tickers.iter().map(|t| t.symbol.as_str()).collect();
+        got.sort();
+        assert_eq!(got, ["BTCUSDT", "ETHUSDT"]);
+
+        // With every symbol rejected the cause comes back, not an empty list
+        let symbols = ["NOPEUSDT", "NOPEUSDT"].map(String::from);
+        let msg = client.tickers(&symbols).await.unwrap_err().to_string();
+        assert_eq!(msg, "API error for NOPEUSDT: 400 Bad Request");
+    }
+
+    #[test]
+    fn test_parse_depth() {
+        let text = r#"{"lastUpdateId":1027024,"bids":[["4.00000000","431.00000000"],["3.99000000","2.5"]],"asks":[["4.00000200","12.00000000"]]}"#;
+        let depth = Depth::from(serde_json::from_str::<DepthData>(text).unwrap());
+        assert_eq!(depth.last_update_id, 1027024);
+        assert_eq!(
+            depth.bids,
+            vec![
+                Level {
+                    price: 4.0,
+                    quantity: 431.0
+                },
+                Level {
+                    price: 3.99,
+                    quantity: 2.5
+                },
+            ]
+        );
+        assert_eq!(depth.asks[0].price, 4.000002);
+        assert!(serde_json::from_str::<DepthData>(
+            r#"{"lastUpdateId":1,"bids":[["x","1"]],"asks":[]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_trades() {
+        let text = r#"[{"a":26129,"p":"0.01633102","q":"4.70443515","f":27781,"l":27781,"T":1498793709153,"m":true,"M":true}]"#;
+        let trades: Vec<Trade> = serde_json::from_str::<Vec<AggTradeData>>(text)
+            .unwrap()
+            .into_iter()
+            .map(Trade::from)
             .collect();
+        assert_eq!(
+            trades,
+            vec![Trade {
+                id: 26129,
+                price: 0.01633102,
+                quantity: 4.70443515,
+                time: 1498793709153,
+                buyer_maker: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(0, 0.0), Duration::from_millis(125));
+        assert_eq!(backoff_delay(0, 1.0), Duration::from_millis(250));
+        assert_eq!(backoff_delay(2, 1.0), Duration::from_millis(1000));
+        // Capped, however many attempts
+        assert_eq!(backoff_delay(30, 1.0), RETRY_MAX_DELAY);
+        assert_eq!(backoff_delay(30, 0.0), RETRY_MAX_DELAY / 2);
+    }
+
+    #[test]
+    fn test_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        assert_eq!(used_weight(&headers), None);
 
-        Ok(prices)
+        headers.insert(RETRY_AFTER, "12".parse().unwrap());
+        headers.insert("X-MBX-USED-WEIGHT-1M", "1187".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(12)));
+        assert_eq!(used_weight(&headers), Some(1187));
     }
 }
@@ -1,51 +1,248 @@
-use std::collections::VecDeque;
-use std::time::Instant;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use chrono::{Local, TimeZone};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use crate::api::{BinanceClient, TickerData};
+use crate::alert::{AlertEngine, AlertEvent};
+use crate::book::OrderBook;
+use crate::config;
+use crate::indicator::{
+    Band, Incremental, Indicator, MacdStep, Oscillator, RsiStep, Series, RSI_PERIOD,
+};
+use crate::keys::Keymap;
+use crate::portfolio::{HoldingEdit, Portfolio};
+use crate::source::{
+    Candle, Depth, MarketDataSource, Pair, RateLimit, Ticker, Timeframe, Trade,
+    DEFAULT_PRICE_DECIMALS, DEFAULT_QUOTE,
+};
+use crate::store::{self, Store};
+use crate::stream::StreamEvent;
+use crate::symbols::SymbolRegistry;
+use crate::ui::Theme;
 
-const MAX_HISTORY: usize = 60;
+/// Most coins the watchlist can hold.
+pub const MAX_COINS: usize = 20;
+/// Candles per chart unless stored history allows more.
+const DEFAULT_HISTORY: usize = 60;
+/// Matches listed under the add-pair prompt.
+pub const MAX_SUGGESTIONS: usize = 8;
+/// Trades fetched to fill a tape.
+const TAPE_TRADES: u32 = 100;
+/// Trades kept per coin as the tape streams in.
+const MAX_TRADES: usize = 200;
+/// Order book levels per side in a snapshot; diffs can move the book past
+/// the levels shown, so fetch well beyond them.
+const DEPTH_LIMIT: u32 = 500;
+const MAX_ALERT_LOG: usize = 200;
+const FLASH_DURATION: Duration = Duration::from_secs(10);
 
 #[derive(Debug, Clone)]
 pub struct CoinData {
+    pub pair: Pair,
+    /// Exchange symbol for `pair`, the key used by sources and streams
     pub symbol: String,
-    pub display_name: String,
+    /// Decimals needed to show a price to the pair's tick size
+    pub price_decimals: usize,
     pub price: f64,
     pub change_24h: f64,
     pub high_24h: f64,
     pub low_24h: f64,
     pub volume_24h: f64,
-    pub price_history: VecDeque<(i64, f64)>, // (timestamp_ms, price)
+    pub open_24h: f64,
+    pub quote_volume_24h: f64,
+    /// Only known once a full REST ticker has been seen
+    pub weighted_avg_24h: Option<f64>,
+    pub trades_24h: Option<u64>,
+    /// Latest trades, newest first; only kept while a tape shows them
+    pub trades: Vec<Trade>,
+    /// Draw the trades tape beside the chart
+    pub show_tape: bool,
+    pub price_history: VecDeque<Candle>,
+    /// Most candles `price_history` keeps
+    pub history_len: usize,
+    pub timeframe: Timeframe,
+    pub show_candles: bool,
+    /// Overlays kept in step with `price_history`
+    pub indicators: Vec<Series>,
+    pub show_indicators: bool,
+    /// Draw the per-candle volume strip under the price plot
+    pub show_volume: bool,
+    pub rsi: Incremental<RsiStep>,
+    pub macd: Incremental<MacdStep>,
+    /// Oscillator drawn under the chart, if any
+    pub oscillator: Option<Oscillator>,
+    pub flash_until: Option<Instant>,
 }
 
 impl CoinData {
-    pub fn new(symbol: &str) -> Self {
-        let display_name = symbol.replace("USDT", "/USDT");
+    /// A USDT pair with a price and made-up 24h stats around it.
+    #[cfg(test)]
+    pub fn quoted(base: &str, price: f64, change: f64) -> Self {
+        let mut coin = Self::new(Pair::new(base, "USDT"));
+        coin.price = price;
+        coin.change_24h = change;
+        coin.high_24h = price * 1.1;
+        coin.low_24h = price * 0.9;
+        coin.volume_24h = 12_345.0;
+        coin.quote_volume_24h = price * 12_345.0;
+        coin
+    }
+
+    pub fn new(pair: Pair) -> Self {
         Self {
-            symbol: symbol.to_string(),
-            display_name,
+            symbol: pair.symbol(),
+            pair,
+            price_decimals: DEFAULT_PRICE_DECIMALS,
             price: 0.0,
             change_24h: 0.0,
             high_24h: 0.0,
             low_24h: 0.0,
             volume_24h: 0.0,
-            price_history: VecDeque::with_capacity(MAX_HISTORY),
+            open_24h: 0.0,
+            quote_volume_24h: 0.0,
+            weighted_avg_24h: None,
+            trades_24h: None,
+            trades: Vec::new(),
+            show_tape: false,
+            price_history: VecDeque::with_capacity(DEFAULT_HISTORY),
+            history_len: DEFAULT_HISTORY,
+            timeframe: Timeframe::default(),
+            show_candles: false,
+            indicators: Vec::new(),
+            show_indicators: false,
+            show_volume: true,
+            rsi: Incremental::new(RsiStep::new(RSI_PERIOD)),
+            macd: Incremental::new(MacdStep::default()),
+            oscillator: None,
+            flash_until: None,
+        }
+    }
+
+    pub fn update(&mut self, ticker: &Ticker) {
+        self.update_stats(ticker);
+
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        self.record_price(now_ms, self.price);
+    }
+
+    /// Folds a polled price into the candle for its timeframe bucket so the
+    /// series stays at a single resolution.
+    pub fn record_price(&mut self, ts_ms: i64, price: f64) {
+        let open_time = self.timeframe.bucket_start(ts_ms);
+        match self.price_history.back_mut() {
+            Some(last) if last.open_time == open_time => {
+                last.close = price;
+                last.high = last.high.max(price);
+                last.low = last.low.min(price);
+                self.update_indicators();
+            }
+            Some(last) if last.open_time > open_time => {}
+            _ => self.push_candle(Candle::flat(open_time, price)),
+        }
+    }
+
+    fn push_candle(&mut self, candle: Candle) {
+        if self.price_history.len() >= self.history_len {
+            self.price_history.pop_front();
+            for series in &mut self.indicators {
+                series.pop_front();
+            }
+            self.rsi.pop_front();
+            self.macd.pop_front();
+        }
+        self.price_history.push_back(candle);
+        for series in &mut self.indicators {
+            series.push(candle.close);
         }
+        self.rsi.push(candle.close);
+        self.macd.push(candle.close);
     }
 
-    pub fn update(&mut self, ticker: &TickerData) {
+    /// Recomputes the newest indicator values after the open candle changed.
+    fn update_indicators(&mut self) {
+        if let Some(last) = self.price_history.back() {
+            for series in &mut self.indicators {
+                series.update_last(last.close);
+            }
+            self.rsi.update_last(last.close);
+            self.macd.update_last(last.close);
+        }
+    }
+
+    /// Replaces the overlays, computing them over the current history.
+    pub fn set_indicators(&mut self, indicators: &[Indicator]) {
+        self.indicators = indicators.iter().map(|&i| Series::new(i)).collect();
+        for series in &mut self.indicators {
+            for candle in &self.price_history {
+                series.push(candle.close);
+            }
+        }
+    }
+
+    /// Switches resolution, dropping history that no longer matches it.
+    pub fn set_timeframe(&mut self, timeframe: Timeframe) {
+        if self.timeframe != timeframe {
+            self.timeframe = timeframe;
+            self.price_history.clear();
+            self.clear_indicators();
+        }
+    }
+
+    fn clear_indicators(&mut self) {
+        for series in &mut self.indicators {
+            series.clear();
+        }
+        self.rsi.clear();
+        self.macd.clear();
+    }
+
+    /// Refreshes the headline numbers without sampling into the history.
+    pub fn update_stats(&mut self, ticker: &Ticker) {
         self.price = ticker.last_price;
         self.change_24h = ticker.price_change_percent;
         self.high_24h = ticker.high_price;
         self.low_24h = ticker.low_price;
         self.volume_24h = ticker.volume;
+        self.open_24h = ticker.open_price;
+        self.quote_volume_24h = ticker.quote_volume;
+        // Streamed tickers leave these out; keep the last full values
+        self.weighted_avg_24h = ticker.weighted_avg_price.or(self.weighted_avg_24h);
+        self.trades_24h = ticker.trade_count.or(self.trades_24h);
+    }
 
-        let now_ms = chrono::Utc::now().timestamp_millis();
-        if self.price_history.len() >= MAX_HISTORY {
-            self.price_history.pop_front();
+    /// Adds a streamed trade to the top of the tape. Trades the tape
+    /// already has are skipped.
+    pub fn push_trade(&mut self, trade: Trade) {
+        if self.trades.first().is_some_and(|t| t.id >= trade.id) {
+            return;
+        }
+        self.trades.insert(0, trade);
+        self.trades.truncate(MAX_TRADES);
+    }
+
+    /// Replaces the tape with a fetched backlog (newest first), keeping
+    /// streamed trades that are newer than it.
+    pub fn merge_trades(&mut self, backlog: Vec<Trade>) {
+        let newest = backlog.first().map(|t| t.id);
+        self.trades
+            .retain(|t| newest.is_none_or(|newest| t.id > newest));
+        self.trades.extend(backlog);
+        self.trades.truncate(MAX_TRADES);
+    }
+
+    /// Folds a streamed kline into the history, replacing the last candle
+    /// while the kline is still open.
+    pub fn apply_kline(&mut self, candle: Candle) {
+        match self.price_history.back_mut() {
+            Some(last) if last.open_time == candle.open_time => {
+                *last = candle;
+                self.update_indicators();
+            }
+            Some(last) if last.open_time > candle.open_time => {}
+            _ => self.push_candle(candle),
         }
-        self.price_history.push_back((now_ms, self.price));
     }
 
     pub fn history_data(&self) -> Vec<(f64, f64)> {
@@ -53,7 +250,7 @@ impl CoinData {
         self.price_history
             .iter()
             .enumerate()
-            .map(|(i, (_, p))| (i as f64, *p))
+            .map(|(i, c)| (i as f64, c.close))
             .collect()
     }
 
@@ -61,15 +258,40 @@ impl CoinData {
         if self.price_history.is_empty() {
             return (0.0, 100.0);
         }
-        let prices: Vec<f64> = self.price_history.iter().map(|(_, p)| *p).collect();
+        let mut prices: Vec<f64> = self.price_history.iter().map(|c| c.close).collect();
+        // Keep visible bands on screen too
+        prices.extend(
+            self.visible_bands()
+                .flat_map(|(_, band)| [band.lower, band.upper]),
+        );
         let min = prices.iter().cloned().fold(f64::INFINITY, f64::min);
         let max = prices.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
         let padding = (max - min) * 0.1;
         (min - padding, max + padding)
     }
 
+    /// `(history index, band)` for every computed overlay value, while the
+    /// overlays are shown.
+    pub fn visible_bands(&self) -> impl Iterator<Item = (usize, Band)> + '_ {
+        self.indicators
+            .iter()
+            .filter(|_| self.show_indicators)
+            .flat_map(|series| {
+                series
+                    .values()
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, v)| v.map(|band| (i, band)))
+            })
+    }
+
     pub fn time_labels(&self) -> Vec<String> {
-        if self.price_history.is_empty() {
+        self.time_labels_from(0)
+    }
+
+    /// Start/mid/end labels for the history after skipping the oldest `skip` points.
+    pub fn time_labels_from(&self, skip: usize) -> Vec<String> {
+        if self.price_history.len() <= skip {
             return vec![
                 "--:--".to_string(),
                 "--:--".to_string(),
@@ -77,104 +299,1081 @@ impl CoinData {
             ];
         }
 
+        let pattern = self.timeframe.label_format();
         let format_time = |ts_ms: i64| -> String {
             match Local.timestamp_millis_opt(ts_ms).single() {
-                Some(dt) => dt.format("%H:%M").to_string(),
+                Some(dt) => dt.format(pattern).to_string(),
                 None => "--:--".to_string(),
             }
         };
 
-        let first = self.price_history.front().map(|(ts, _)| *ts).unwrap_or(0);
-        let last = self.price_history.back().map(|(ts, _)| *ts).unwrap_or(0);
+        let first = self
+            .price_history
+            .get(skip)
+            .map(|c| c.open_time)
+            .unwrap_or(0);
+        let last = self.price_history.back().map(|c| c.open_time).unwrap_or(0);
         let mid = (first + last) / 2;
 
         vec![format_time(first), format_time(mid), format_time(last)]
     }
 
-    pub fn load_history(&mut self, data: Vec<(i64, f64)>) {
+    pub fn is_flashing(&self) -> bool {
+        self.flash_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    pub fn load_history(&mut self, data: Vec<Candle>) {
         self.price_history.clear();
-        for (ts, price) in data {
-            self.price_history.push_back((ts, price));
+        self.clear_indicators();
+        for candle in data {
+            self.push_candle(candle);
+        }
+        if let Some(last) = self.price_history.back() {
+            self.price = last.close;
         }
-        if let Some((_, last_price)) = self.price_history.back() {
-            self.price = *last_price;
+    }
+}
+
+/// How a chart starts out, for coins present at startup and added later.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChartDefaults {
+    pub candles: bool,
+    pub indicators: bool,
+    pub volume: bool,
+    pub oscillator: Option<Oscillator>,
+    pub tape: bool,
+}
+
+impl Default for ChartDefaults {
+    fn default() -> Self {
+        Self {
+            candles: false,
+            indicators: false,
+            volume: true,
+            oscillator: None,
+            tape: false,
         }
     }
 }
 
+impl ChartDefaults {
+    pub fn apply(&self, coin: &mut CoinData) {
+        coin.show_candles = self.candles;
+        coin.show_indicators = self.indicators;
+        coin.show_volume = self.volume;
+        coin.oscillator = self.oscillator;
+        coin.show_tape = self.tape;
+    }
+}
+
 pub struct App {
     pub coins: Vec<CoinData>,
     pub last_update: Option<Instant>,
     pub running: bool,
     pub page_index: usize,
+    pub charts_per_page: usize,
+    pub selected: usize,
+    pub timeframe: Timeframe,
     pub status_message: String,
+    pub streaming: bool,
+    pub alerts: AlertEngine,
+    pub alert_log: VecDeque<AlertEvent>,
+    pub alert_scroll: usize,
+    pub show_alert_log: bool,
+    pub bell_pending: bool,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    /// Request budget from the last REST call
+    pub rate_limit: Option<RateLimit>,
+    pub portfolio: Portfolio,
+    /// Where holding edits are saved; `None` keeps them in memory
+    pub holdings_path: Option<PathBuf>,
+    /// Text being typed into the status bar, which takes every key press
+    pub prompt: Option<Prompt>,
+    pub view: View,
+    pub sort_column: SortColumn,
+    pub sort_descending: bool,
+    /// Exchange symbols, for validating and suggesting added pairs
+    pub registry: SymbolRegistry,
+    /// Quote asset for coins added without one
+    pub quote: String,
+    pub chart_defaults: ChartDefaults,
+    /// Config file that watchlist edits are written back to, if any
+    pub watchlist_path: Option<PathBuf>,
+    /// The config file's watchlist, with this session's edits applied
+    pub saved_watchlist: SavedWatchlist,
+    indicators: Vec<Indicator>,
+    /// Added coins still waiting for their history and ticker
+    pending: Vec<String>,
+    /// Coin whose trades the detail view last loaded
+    detail_symbol: Option<String>,
+    /// Order book of the coin in the detail view
+    pub book: Option<OrderBook>,
+    /// The book missed a diff and needs a fresh snapshot
+    book_resync: bool,
+    /// Notional that makes a trade a large print, by quote asset
+    pub large_trade: HashMap<String, f64>,
+    /// Show only large prints on the tapes
+    pub tape_filter: bool,
+    /// Tapes waiting for a backlog of trades
+    tape_pending: Vec<String>,
+    /// On-disk price history, if enabled
+    pub store: Option<Store>,
+    history_len: usize,
+}
+
+/// What the main area shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum View {
+    /// A page of charts
+    #[default]
+    Charts,
+    /// One row per coin
+    Table,
+    /// The selected coin full screen, with stats and recent trades
+    Detail,
+}
+
+/// Column the table view is ordered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortColumn {
+    /// The order coins were given in
+    #[default]
+    Watchlist,
+    Pair,
+    Price,
+    Change,
+    High,
+    Low,
+    Volume,
 }
 
-const CHARTS_PER_PAGE: usize = 4;
+impl SortColumn {
+    const ALL: [SortColumn; 7] = [
+        SortColumn::Watchlist,
+        SortColumn::Pair,
+        SortColumn::Price,
+        SortColumn::Change,
+        SortColumn::High,
+        SortColumn::Low,
+        SortColumn::Volume,
+    ];
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|c| *c == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Column heading in the table view.
+    pub fn label(self) -> &'static str {
+        match self {
+            SortColumn::Watchlist => "#",
+            SortColumn::Pair => "Pair",
+            SortColumn::Price => "Price",
+            SortColumn::Change => "24h %",
+            SortColumn::High => "High",
+            SortColumn::Low => "Low",
+            SortColumn::Volume => "Volume",
+        }
+    }
+
+    /// Numeric sort key, or `None` for `Pair`, which sorts by name.
+    fn key(self, index: usize, coin: &CoinData) -> Option<f64> {
+        match self {
+            SortColumn::Watchlist => Some(index as f64),
+            SortColumn::Pair => None,
+            SortColumn::Price => Some(coin.price),
+            SortColumn::Change => Some(coin.change_24h),
+            SortColumn::High => Some(coin.high_24h),
+            SortColumn::Low => Some(coin.low_24h),
+            SortColumn::Volume => Some(coin.volume_24h),
+        }
+    }
+}
+
+/// The config file's watchlist, which in-app adds, removes and moves are
+/// applied to before it is written back. Kept apart from the watched coins
+/// so `--coins`, `--quote` and coins watched for holdings stay out of the
+/// file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SavedWatchlist {
+    /// Bare coins or `BASE/QUOTE` pairs, as written in the file
+    pub entries: Vec<String>,
+    /// The file's quote asset, which bare coins are quoted in
+    pub quote: String,
+}
+
+impl SavedWatchlist {
+    pub fn new(entries: Vec<String>, quote: &str) -> Self {
+        Self {
+            entries,
+            quote: quote.to_uppercase(),
+        }
+    }
+
+    fn pair(&self, entry: &str) -> Option<Pair> {
+        Pair::parse_with_quote(entry, &self.quote).ok()
+    }
+
+    fn add(&mut self, pair: &Pair) {
+        if self
+            .entries
+            .iter()
+            .any(|e| self.pair(e).as_ref() == Some(pair))
+        {
+            return;
+        }
+        self.entries.push(if pair.quote == self.quote {
+            pair.base.clone()
+        } else {
+            pair.to_string()
+        });
+    }
+
+    fn remove(&mut self, pair: &Pair) {
+        let quote = self.quote.clone();
+        self.entries
+            .retain(|e| Pair::parse_with_quote(e, &quote).ok().as_ref() != Some(pair));
+    }
+
+    /// Puts the entries for pairs in `order` into that order, leaving the
+    /// other entries where they are.
+    fn reorder(&mut self, order: &[Pair]) {
+        let slots: Vec<(usize, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| {
+                let pair = self.pair(e)?;
+                order.iter().position(|p| *p == pair).map(|rank| (i, rank))
+            })
+            .collect();
+        let mut ranked = slots.clone();
+        ranked.sort_by_key(|&(_, rank)| rank);
+        let moved: Vec<String> = ranked
+            .iter()
+            .map(|&(i, _)| self.entries[i].clone())
+            .collect();
+        for (&(slot, _), entry) in slots.iter().zip(moved) {
+            self.entries[slot] = entry;
+        }
+    }
+}
+
+/// A line of input typed into the status bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+    /// Highlighted suggestion, for prompts that offer them
+    pub choice: usize,
+    /// Why the last submission was rejected
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromptKind {
+    /// Editing the holding in this pair
+    Holding(Pair),
+    /// Picking a pair to add to the watchlist
+    AddPair,
+}
+
+impl Prompt {
+    pub fn label(&self) -> String {
+        match &self.kind {
+            PromptKind::Holding(pair) => {
+                format!(
+                    "{} holding (QTY@PRICE, +QTY@PRICE adds a lot, 0 removes): ",
+                    pair
+                )
+            }
+            PromptKind::AddPair => "Add pair (↑/↓ picks a match): ".to_string(),
+        }
+    }
+
+    fn new(kind: PromptKind, input: String) -> Self {
+        Self {
+            kind,
+            input,
+            choice: 0,
+            error: None,
+        }
+    }
+}
+
+pub const MAX_CHARTS_PER_PAGE: usize = 4;
 
 impl App {
-    pub fn new(symbols: Vec<String>) -> Self {
-        let coins = symbols.iter().map(|s| CoinData::new(s)).collect();
+    pub fn new(pairs: Vec<Pair>, timeframe: Timeframe) -> Self {
+        let mut saved_watchlist = SavedWatchlist::new(Vec::new(), DEFAULT_QUOTE);
+        for pair in &pairs {
+            saved_watchlist.add(pair);
+        }
+        let coins = pairs
+            .into_iter()
+            .map(|pair| {
+                let mut coin = CoinData::new(pair);
+                coin.timeframe = timeframe;
+                coin
+            })
+            .collect();
         Self {
             coins,
             last_update: None,
             running: true,
             page_index: 0,
+            charts_per_page: MAX_CHARTS_PER_PAGE,
+            selected: 0,
+            timeframe,
             status_message: "Starting...".to_string(),
+            streaming: false,
+            alerts: AlertEngine::default(),
+            alert_log: VecDeque::new(),
+            alert_scroll: 0,
+            show_alert_log: false,
+            bell_pending: false,
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            rate_limit: None,
+            portfolio: Portfolio::default(),
+            holdings_path: None,
+            prompt: None,
+            view: View::default(),
+            sort_column: SortColumn::default(),
+            sort_descending: false,
+            registry: SymbolRegistry::default(),
+            quote: DEFAULT_QUOTE.to_string(),
+            chart_defaults: ChartDefaults::default(),
+            watchlist_path: None,
+            saved_watchlist,
+            indicators: Vec::new(),
+            pending: Vec::new(),
+            detail_symbol: None,
+            book: None,
+            book_resync: false,
+            large_trade: HashMap::new(),
+            tape_filter: false,
+            tape_pending: Vec::new(),
+            store: None,
+            history_len: DEFAULT_HISTORY,
         }
     }
 
-    pub async fn load_historical(&mut self, client: &BinanceClient) {
+    pub async fn load_historical(&mut self, source: &dyn MarketDataSource) {
         self.status_message = "Loading history...".to_string();
         let symbols: Vec<String> = self.coins.iter().map(|c| c.symbol.clone()).collect();
-        let results = client.get_klines_batch(&symbols, MAX_HISTORY as u32).await;
+        let results = self.fetch_history(source, &symbols).await;
+        self.rate_limit = source.rate_limit();
 
-        for (coin, result) in self.coins.iter_mut().zip(results.into_iter()) {
+        let mut failed = false;
+        for (coin, result) in self.coins.iter_mut().zip(results) {
             match result {
                 Ok(data) => coin.load_history(data),
                 Err(e) => {
-                    self.status_message =
-                        format!("Error loading history for {}: {}", coin.symbol, e);
+                    failed = true;
+                    self.status_message = format!("Error loading history for {}: {}", coin.pair, e);
                 }
             }
         }
+        if !failed {
+            self.status_message = format!("Loaded {} history", self.timeframe);
+        }
     }
 
-    pub async fn fetch_prices(&mut self, client: &BinanceClient) {
-        let symbols: Vec<String> = self.coins.iter().map(|c| c.symbol.clone()).collect();
-        let results = client.get_tickers(&symbols).await;
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
 
-        for (coin, result) in self.coins.iter_mut().zip(results.into_iter()) {
-            match result {
-                Ok(ticker) => coin.update(&ticker),
-                Err(e) => {
-                    self.status_message = format!("Error fetching {}: {}", coin.symbol, e);
+    /// Loads history and prices for coins added since the last call.
+    pub async fn load_pending(&mut self, source: &dyn MarketDataSource) {
+        let symbols = std::mem::take(&mut self.pending);
+        if symbols.is_empty() {
+            return;
+        }
+        let results = self.fetch_history(source, &symbols).await;
+        for (symbol, result) in symbols.iter().zip(results) {
+            let Some(coin) = self.coins.iter_mut().find(|c| &c.symbol == symbol) else {
+                continue;
+            };
+            if let Err(e) = result.map(|data| coin.load_history(data)) {
+                self.status_message = format!("Error loading history for {}: {}", coin.pair, e);
+            }
+        }
+
+        let result = source.tickers(&symbols).await;
+        self.rate_limit = source.rate_limit();
+        match result {
+            Ok(tickers) => {
+                for ticker in tickers {
+                    if let Some(i) = self.coins.iter().position(|c| c.symbol == ticker.symbol) {
+                        self.coins[i].update(&ticker);
+                        self.persist(i, false);
+                        self.check_alerts(i);
+                    }
                 }
             }
+            Err(e) => self.status_message = format!("Error fetching prices: {}", e),
+        }
+    }
+
+    /// History for `symbols` at the current timeframe: straight from the
+    /// source, or with a store, what it has plus whatever was missed since.
+    async fn fetch_history(
+        &mut self,
+        source: &dyn MarketDataSource,
+        symbols: &[String],
+    ) -> Vec<anyhow::Result<Vec<Candle>>> {
+        let timeframe = self.timeframe;
+        let Some(store) = &mut self.store else {
+            return source
+                .klines_batch(symbols, timeframe, self.history_len as u32)
+                .await;
+        };
+
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let mut starts = Vec::with_capacity(symbols.len());
+        for symbol in symbols {
+            let last = store.last_open_time(symbol, timeframe).unwrap_or(None);
+            starts.push(store::backfill_start(
+                last,
+                timeframe,
+                self.history_len,
+                now_ms,
+            ));
+        }
+        let fetched = futures::future::join_all(
+            symbols
+                .iter()
+                .zip(&starts)
+                .map(|(symbol, &start)| source.klines_range(symbol, timeframe, start, now_ms + 1)),
+        )
+        .await;
+
+        let mut results = Vec::with_capacity(symbols.len());
+        for (symbol, candles) in symbols.iter().zip(fetched) {
+            // Without the network the stored history still shows
+            if let Err(e) = candles.and_then(|c| store.save_candles(symbol, timeframe, &c)) {
+                self.status_message = format!("Error backfilling {}: {:#}", symbol, e);
+            }
+            results.push(store.candles(symbol, timeframe, self.history_len));
+        }
+        results
+    }
+
+    /// Sets how many candles every chart keeps, for coins present and added
+    /// later; history must be reloaded afterwards.
+    pub fn set_history_len(&mut self, len: usize) {
+        self.history_len = len;
+        for coin in &mut self.coins {
+            coin.history_len = len;
+        }
+    }
+
+    /// Writes a coin's current price to the store, or with `streamed_kline`
+    /// its latest candle. Candles built from polled prices have no volume and
+    /// only sampled highs and lows, so only exchange klines are stored; the
+    /// buckets they cover are backfilled on the next start instead.
+    fn persist(&mut self, index: usize, streamed_kline: bool) {
+        let (Some(store), Some(coin)) = (&mut self.store, self.coins.get(index)) else {
+            return;
+        };
+        let result = if streamed_kline {
+            match coin.price_history.back() {
+                Some(candle) => store.save_candles(&coin.symbol, coin.timeframe, &[*candle]),
+                None => Ok(()),
+            }
+        } else if coin.price > 0.0 {
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            store.save_tick(&coin.symbol, now_ms, coin.price)
+        } else {
+            Ok(())
+        };
+        if let Err(e) = result {
+            self.status_message = format!("Error saving history: {:#}", e);
+        }
+    }
+
+    /// Moves every chart to `timeframe`; history must be reloaded afterwards.
+    pub fn set_timeframe(&mut self, timeframe: Timeframe) {
+        self.timeframe = timeframe;
+        for coin in &mut self.coins {
+            coin.set_timeframe(timeframe);
+        }
+    }
+
+    /// Refreshes every coin's ticker; false when none could be fetched,
+    /// with the error left in the status message.
+    pub async fn fetch_prices(&mut self, source: &dyn MarketDataSource) -> bool {
+        let symbols: Vec<String> = self.coins.iter().map(|c| c.symbol.clone()).collect();
+        let result = source.tickers(&symbols).await;
+        self.rate_limit = source.rate_limit();
+        let tickers = match result {
+            Ok(tickers) => tickers,
+            Err(e) => {
+                self.status_message = format!("Error fetching prices: {}", e);
+                return false;
+            }
+        };
+
+        let mut updated = vec![false; self.coins.len()];
+        for ticker in tickers {
+            if let Some(i) = self.coins.iter().position(|c| c.symbol == ticker.symbol) {
+                self.coins[i].update(&ticker);
+                self.persist(i, false);
+                self.check_alerts(i);
+                updated[i] = true;
+            }
         }
         self.last_update = Some(Instant::now());
-        self.status_message = "Updated".to_string();
+
+        let missing: Vec<String> = self
+            .coins
+            .iter()
+            .zip(&updated)
+            .filter(|(_, updated)| !**updated)
+            .map(|(coin, _)| coin.pair.to_string())
+            .collect();
+        self.status_message = if missing.is_empty() {
+            "Updated".to_string()
+        } else {
+            format!("No data for {}", missing.join(", "))
+        };
+        true
+    }
+
+    fn check_alerts(&mut self, index: usize) {
+        if self.alerts.is_empty() {
+            return;
+        }
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let events = self.alerts.evaluate(&self.coins[index], now_ms);
+        if events.is_empty() {
+            return;
+        }
+
+        self.coins[index].flash_until = Some(Instant::now() + FLASH_DURATION);
+        self.bell_pending = true;
+        for event in events {
+            self.status_message = event.message.clone();
+            if self.alert_log.len() >= MAX_ALERT_LOG {
+                self.alert_log.pop_back();
+            }
+            self.alert_log.push_front(event);
+            // Keep the rows the user is reading in place
+            if self.alert_scroll > 0 {
+                self.alert_scroll += 1;
+            }
+        }
+    }
+
+    /// Returns whether the terminal bell should ring, clearing the request.
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.bell_pending)
+    }
+
+    pub fn toggle_alert_log(&mut self) {
+        self.show_alert_log = !self.show_alert_log;
+        self.alert_scroll = 0;
+    }
+
+    pub fn scroll_alert_log(&mut self, delta: isize) {
+        let max = self.alert_log.len().saturating_sub(1);
+        self.alert_scroll = self.alert_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    pub fn handle_stream(&mut self, event: StreamEvent) {
+        match event {
+            StreamEvent::Connected => {
+                self.streaming = true;
+                self.status_message = "Streaming".to_string();
+                // Trades printed while the stream was down
+                self.tape_pending = self.tape_symbols();
+            }
+            StreamEvent::Disconnected(reason) => {
+                self.streaming = false;
+                self.status_message = format!("Stream down ({}), polling", reason);
+            }
+            StreamEvent::Ticker(ticker) => {
+                if let Some(i) = self.coins.iter().position(|c| c.symbol == ticker.symbol) {
+                    self.coins[i].update_stats(&ticker);
+                    self.persist(i, false);
+                    self.check_alerts(i);
+                    self.last_update = Some(Instant::now());
+                }
+            }
+            StreamEvent::Depth(update) => {
+                let Some(book) = self.book.as_mut().filter(|b| b.symbol == update.symbol) else {
+                    return;
+                };
+                // Diffs are useless until a new snapshot arrives
+                if self.book_resync {
+                    return;
+                }
+                if let Err(e) = book.apply(&update) {
+                    self.status_message = format!("{}, resyncing", e);
+                    self.book_resync = true;
+                }
+            }
+            StreamEvent::Trade { symbol, trade } => {
+                if let Some(coin) = self.coins.iter_mut().find(|c| c.symbol == symbol) {
+                    coin.push_trade(trade);
+                }
+            }
+            StreamEvent::Kline {
+                symbol,
+                timeframe,
+                candle,
+            } => {
+                // Klines for the previous timeframe can still be in flight
+                // right after a switch
+                if timeframe != self.timeframe {
+                    return;
+                }
+                if let Some(i) = self.coins.iter().position(|c| c.symbol == symbol) {
+                    self.coins[i].apply_kline(candle);
+                    self.persist(i, true);
+                }
+            }
+        }
     }
 
     pub fn visible_coins(&self) -> &[CoinData] {
-        let start = self.page_index * CHARTS_PER_PAGE;
-        let end = (start + CHARTS_PER_PAGE).min(self.coins.len());
+        let start = self.page_index * self.charts_per_page;
+        let end = (start + self.charts_per_page).min(self.coins.len());
         &self.coins[start..end]
     }
 
     pub fn total_pages(&self) -> usize {
-        self.coins.len().div_ceil(CHARTS_PER_PAGE)
+        self.coins.len().div_ceil(self.charts_per_page)
     }
 
     pub fn prev_page(&mut self) {
         if self.page_index > 0 {
             self.page_index -= 1;
+            self.selected = self.page_index * self.charts_per_page;
         }
     }
 
     pub fn next_page(&mut self) {
         if self.page_index < self.total_pages().saturating_sub(1) {
             self.page_index += 1;
+            self.selected = self.page_index * self.charts_per_page;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        if self.view == View::Table {
+            return self.step_table_selection(-1);
+        }
+        if self.selected > 0 {
+            self.select(self.selected - 1);
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.view == View::Table {
+            return self.step_table_selection(1);
+        }
+        if self.selected + 1 < self.coins.len() {
+            self.select(self.selected + 1);
+        }
+    }
+
+    /// Moves the selection up or down the table's sorted rows.
+    fn step_table_selection(&mut self, delta: isize) {
+        let order = self.table_order();
+        let Some(row) = order.iter().position(|&i| i == self.selected) else {
+            return;
+        };
+        if let Some(&index) = row.checked_add_signed(delta).and_then(|r| order.get(r)) {
+            self.select(index);
+        }
+    }
+
+    /// Coin indices in table row order; ties keep watchlist order.
+    pub fn table_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.coins.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (coin_a, coin_b) = (&self.coins[a], &self.coins[b]);
+            let column = self.sort_column;
+            let ordering = match (column.key(a, coin_a), column.key(b, coin_b)) {
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                _ => {
+                    let (a, b) = (&coin_a.pair, &coin_b.pair);
+                    (&a.base, &a.quote).cmp(&(&b.base, &b.quote))
+                }
+            };
+            if self.sort_descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        order
+    }
+
+    pub fn toggle_table(&mut self) {
+        self.view = match self.view {
+            View::Charts | View::Detail => View::Table,
+            View::Table => View::Charts,
+        };
+        self.detail_symbol = None;
+        self.book = None;
+    }
+
+    /// Sorts the table by the next column, largest first for numbers.
+    pub fn cycle_sort(&mut self) {
+        self.sort_column = self.sort_column.next();
+        self.sort_descending =
+            !matches!(self.sort_column, SortColumn::Watchlist | SortColumn::Pair);
+    }
+
+    pub fn reverse_sort(&mut self) {
+        self.sort_descending = !self.sort_descending;
+    }
+
+    /// From the table, shows the selected coin's chart; from the charts,
+    /// opens its detail view.
+    pub fn open_selected(&mut self) {
+        match self.view {
+            View::Table => {
+                self.view = View::Charts;
+                self.select(self.selected);
+            }
+            View::Charts if !self.coins.is_empty() => self.view = View::Detail,
+            View::Charts | View::Detail => {}
+        }
+    }
+
+    /// Returns to the chart grid, or quits from it.
+    pub fn back(&mut self) {
+        match self.view {
+            View::Charts => self.quit(),
+            View::Table | View::Detail => {
+                self.view = View::Charts;
+                self.detail_symbol = None;
+                self.book = None;
+                self.select(self.selected);
+            }
+        }
+    }
+
+    /// Whether the detail view shows a coin whose trades aren't loaded yet.
+    pub fn detail_stale(&self) -> bool {
+        self.view == View::Detail
+            && self.coins.get(self.selected).map(|c| &c.symbol) != self.detail_symbol.as_ref()
+    }
+
+    /// Symbol whose order book should be streamed, while the detail view is
+    /// open.
+    pub fn depth_symbol(&self) -> Option<String> {
+        match self.view {
+            View::Detail => self.coins.get(self.selected).map(|c| c.symbol.clone()),
+            View::Charts | View::Table => None,
+        }
+    }
+
+    /// Whether the order book fell out of sequence and needs a snapshot.
+    pub fn book_stale(&self) -> bool {
+        self.book_resync && self.book.is_some()
+    }
+
+    /// Replaces the order book with a fresh snapshot.
+    pub async fn load_book(&mut self, source: &dyn MarketDataSource) {
+        let Some(symbol) = self.book.as_ref().map(|b| b.symbol.clone()) else {
+            return;
+        };
+        let result = source.depth(&symbol, DEPTH_LIMIT).await;
+        self.rate_limit = source.rate_limit();
+        self.set_book(&symbol, result);
+    }
+
+    fn set_book(&mut self, symbol: &str, depth: anyhow::Result<Depth>) {
+        match depth {
+            Ok(depth) => {
+                self.book = Some(OrderBook::new(symbol, depth));
+                self.book_resync = false;
+            }
+            Err(e) => self.status_message = format!("Error fetching order book: {}", e),
+        }
+    }
+
+    /// Fetches the full ticker, recent trades and order book for the detail
+    /// view. While streaming, trades and the book are only fetched when the
+    /// coin is first shown; the stream keeps them current after that, and
+    /// the book is only snapshotted again on a gap, by `load_book`.
+    pub async fn load_detail(&mut self, source: &dyn MarketDataSource) {
+        let Some(symbol) = self.coins.get(self.selected).map(|c| c.symbol.clone()) else {
+            return;
+        };
+        let (ticker, trades, depth) = if self.streaming && !self.detail_stale() {
+            (source.ticker(&symbol).await, None, None)
+        } else {
+            let (ticker, trades, depth) = futures::join!(
+                source.ticker(&symbol),
+                source.recent_trades(&symbol, TAPE_TRADES),
+                source.depth(&symbol, DEPTH_LIMIT)
+            );
+            (ticker, Some(trades), Some(depth))
+        };
+        self.rate_limit = source.rate_limit();
+        self.detail_symbol = Some(symbol.clone());
+        if let Some(depth) = depth {
+            self.set_book(&symbol, depth);
+        }
+
+        let Some(i) = self.coins.iter().position(|c| c.symbol == symbol) else {
+            return;
+        };
+        match ticker {
+            Ok(ticker) => {
+                self.coins[i].update_stats(&ticker);
+                self.check_alerts(i);
+            }
+            Err(e) => self.status_message = format!("Error fetching {}: {}", self.coins[i].pair, e),
+        }
+        match trades {
+            Some(Ok(trades)) => self.coins[i].merge_trades(trades),
+            None => {}
+            Some(Err(e)) => {
+                self.status_message =
+                    format!("Error fetching trades for {}: {}", self.coins[i].pair, e)
+            }
+        }
+    }
+
+    /// Symbols whose trades are shown: coins with their tape on, plus the
+    /// coin in the detail view.
+    pub fn tape_symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .coins
+            .iter()
+            .filter(|c| c.show_tape)
+            .map(|c| c.symbol.clone())
+            .collect();
+        if let Some(symbol) = self.depth_symbol().filter(|s| !symbols.contains(s)) {
+            symbols.push(symbol);
+        }
+        symbols
+    }
+
+    pub fn toggle_tape(&mut self) {
+        let Some(coin) = self.coins.get_mut(self.selected) else {
+            return;
+        };
+        coin.show_tape = !coin.show_tape;
+        if coin.show_tape {
+            self.tape_pending.push(coin.symbol.clone());
+        }
+    }
+
+    pub fn toggle_tape_filter(&mut self) {
+        self.tape_filter = !self.tape_filter;
+    }
+
+    /// Notional that makes a trade in `pair` a large print, if its quote
+    /// asset has one configured.
+    pub fn large_trade_threshold(&self, pair: &Pair) -> Option<f64> {
+        self.large_trade.get(&pair.quote).copied()
+    }
+
+    /// Whether a trade is big enough to stand out on the tape.
+    pub fn is_large_trade(&self, pair: &Pair, trade: &Trade) -> bool {
+        self.large_trade_threshold(pair)
+            .is_some_and(|threshold| trade.price * trade.quantity >= threshold)
+    }
+
+    /// Queues every tape shown in the grid for a fresh backlog, for when
+    /// trades aren't streamed.
+    pub fn refresh_tapes(&mut self) {
+        for coin in self.coins.iter().filter(|c| c.show_tape) {
+            if !self.tape_pending.contains(&coin.symbol) {
+                self.tape_pending.push(coin.symbol.clone());
+            }
+        }
+    }
+
+    pub fn has_pending_tapes(&self) -> bool {
+        !self.tape_pending.is_empty()
+    }
+
+    /// Fetches the recent trades of every queued tape.
+    pub async fn load_tapes(&mut self, source: &dyn MarketDataSource) {
+        let symbols = std::mem::take(&mut self.tape_pending);
+        let results = futures::future::join_all(
+            symbols
+                .iter()
+                .map(|symbol| source.recent_trades(symbol, TAPE_TRADES)),
+        )
+        .await;
+        self.rate_limit = source.rate_limit();
+        for (symbol, result) in symbols.iter().zip(results) {
+            let Some(coin) = self.coins.iter_mut().find(|c| &c.symbol == symbol) else {
+                continue;
+            };
+            match result {
+                Ok(trades) => coin.merge_trades(trades),
+                Err(e) => {
+                    self.status_message = format!("Error fetching trades for {}: {}", coin.pair, e)
+                }
+            }
+        }
+    }
+
+    /// Selects a coin by index, flipping to the page that shows it.
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.coins.len().saturating_sub(1));
+        self.page_index = self.selected / self.charts_per_page;
+    }
+
+    pub fn is_selected(&self, visible_index: usize) -> bool {
+        self.page_index * self.charts_per_page + visible_index == self.selected
+    }
+
+    /// Gives every chart, including ones added later, the same overlays.
+    pub fn set_indicators(&mut self, indicators: &[Indicator]) {
+        for coin in &mut self.coins {
+            coin.set_indicators(indicators);
+        }
+        self.indicators = indicators.to_vec();
+    }
+
+    /// Exchange symbols of the watched coins, in watchlist order.
+    pub fn symbols(&self) -> Vec<String> {
+        self.coins.iter().map(|c| c.symbol.clone()).collect()
+    }
+
+    /// Opens the prompt for adding a pair to the watchlist.
+    pub fn prompt_add_pair(&mut self) {
+        self.prompt = Some(Prompt::new(PromptKind::AddPair, String::new()));
+    }
+
+    /// Pairs matching the add-pair prompt's input, best first.
+    pub fn suggestions(&self) -> Vec<Pair> {
+        self.prompt
+            .as_ref()
+            .map(|prompt| self.suggestions_for(prompt))
+            .unwrap_or_default()
+    }
+
+    fn suggestions_for(&self, prompt: &Prompt) -> Vec<Pair> {
+        match prompt.kind {
+            PromptKind::AddPair => {
+                self.registry
+                    .search(prompt.input.trim(), &self.quote, MAX_SUGGESTIONS)
+            }
+            PromptKind::Holding(_) => Vec::new(),
+        }
+    }
+
+    /// Appends `pair` and selects it; its history is fetched by the next
+    /// `load_pending`. A pair already watched is just selected.
+    pub fn add_pair(&mut self, pair: Pair) -> Result<(), String> {
+        if let Some(index) = self.coins.iter().position(|c| c.pair == pair) {
+            self.select(index);
+            self.status_message = format!("Already watching {}", pair);
+            return Ok(());
+        }
+        if self.coins.len() >= MAX_COINS {
+            return Err(format!("at most {} coins can be watched", MAX_COINS));
+        }
+        let mut coin = CoinData::new(pair);
+        coin.history_len = self.history_len;
+        if !self.registry.is_empty() {
+            coin.price_decimals = self.registry.validate(&coin.pair)?.price_decimals();
+        }
+        coin.timeframe = self.timeframe;
+        coin.set_indicators(&self.indicators);
+        self.chart_defaults.apply(&mut coin);
+
+        self.status_message = format!("Added {}", coin.pair);
+        self.pending.push(coin.symbol.clone());
+        if coin.show_tape {
+            self.tape_pending.push(coin.symbol.clone());
+        }
+        self.saved_watchlist.add(&coin.pair);
+        self.coins.push(coin);
+        self.select(self.coins.len() - 1);
+        self.save_watchlist();
+        Ok(())
+    }
+
+    /// Drops the selected coin from the watchlist, keeping at least one, and
+    /// at least one in the config file when edits are saved there.
+    pub fn remove_selected(&mut self) {
+        if self.coins.len() <= 1 {
+            self.status_message = "Cannot remove the last coin".to_string();
+            return;
+        }
+        if self.watchlist_path.is_some() {
+            let mut saved = self.saved_watchlist.clone();
+            saved.remove(&self.coins[self.selected].pair);
+            if saved.entries.is_empty() {
+                self.status_message = "Cannot remove the last saved coin".to_string();
+                return;
+            }
+        }
+        let coin = self.coins.remove(self.selected);
+        self.pending.retain(|s| *s != coin.symbol);
+        self.tape_pending.retain(|s| *s != coin.symbol);
+        self.status_message = format!("Removed {}", coin.pair);
+        self.saved_watchlist.remove(&coin.pair);
+        self.select(self.selected);
+        self.save_watchlist();
+    }
+
+    /// Swaps the selected coin with its neighbour up (-1) or down (+1) the
+    /// watchlist.
+    pub fn move_selected(&mut self, delta: isize) {
+        let Some(target) = self
+            .selected
+            .checked_add_signed(delta)
+            .filter(|&i| i < self.coins.len())
+        else {
+            return;
+        };
+        self.coins.swap(self.selected, target);
+        self.select(target);
+        let order: Vec<Pair> = self.coins.iter().map(|c| c.pair.clone()).collect();
+        self.saved_watchlist.reorder(&order);
+        self.save_watchlist();
+    }
+
+    /// Watchlist entries as written back to the config.
+    pub fn watchlist(&self) -> Vec<String> {
+        self.saved_watchlist.entries.clone()
+    }
+
+    fn save_watchlist(&mut self) {
+        let Some(path) = &self.watchlist_path else {
+            return;
+        };
+        if let Err(e) = config::save_watchlist(path, &self.watchlist()) {
+            self.status_message = format!("Error saving watchlist: {:#}", e);
+        }
+    }
+
+    pub fn toggle_indicators(&mut self) {
+        if let Some(coin) = self.coins.get_mut(self.selected) {
+            coin.show_indicators = !coin.show_indicators;
+        }
+    }
+
+    /// Steps the selected chart's sub-pane through off, RSI and MACD.
+    pub fn cycle_oscillator(&mut self) {
+        if let Some(coin) = self.coins.get_mut(self.selected) {
+            coin.oscillator = match coin.oscillator {
+                None => Some(Oscillator::Rsi),
+                Some(Oscillator::Rsi) => Some(Oscillator::Macd),
+                Some(Oscillator::Macd) => None,
+            };
+        }
+    }
+
+    pub fn toggle_candles(&mut self) {
+        if let Some(coin) = self.coins.get_mut(self.selected) {
+            coin.show_candles = !coin.show_candles;
         }
     }
 
@@ -182,6 +1381,96 @@ impl App {
         self.running = false;
     }
 
+    /// Opens the holding prompt for the selected coin, filled in with the
+    /// current position when it is a single lot.
+    pub fn edit_holding(&mut self) {
+        let Some(coin) = self.coins.get(self.selected) else {
+            return;
+        };
+        let input = match self.portfolio.get(&coin.pair) {
+            Some(holding) if holding.lots.len() == 1 => {
+                format!("{}@{}", holding.quantity(), holding.average_cost())
+            }
+            _ => String::new(),
+        };
+        self.prompt = Some(Prompt::new(PromptKind::Holding(coin.pair.clone()), input));
+    }
+
+    pub fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let choices = self.suggestions().len();
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Enter => self.submit_prompt(),
+            KeyCode::Backspace => {
+                prompt.input.pop();
+                prompt.choice = 0;
+            }
+            KeyCode::Up | KeyCode::BackTab => prompt.choice = prompt.choice.saturating_sub(1),
+            KeyCode::Down | KeyCode::Tab => {
+                prompt.choice = (prompt.choice + 1).min(choices.saturating_sub(1));
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                prompt.input.push(c);
+                prompt.choice = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Applies the prompt; on a mistake it stays open so the input can be
+    /// corrected, and an empty input just closes it.
+    fn submit_prompt(&mut self) {
+        let Some(mut prompt) = self.prompt.take() else {
+            return;
+        };
+        if prompt.input.trim().is_empty() {
+            return;
+        }
+        let suggestions = self.suggestions_for(&prompt);
+        match &prompt.kind {
+            PromptKind::Holding(pair) => {
+                let result = prompt
+                    .input
+                    .parse::<HoldingEdit>()
+                    .and_then(|edit| self.portfolio.apply(pair, edit));
+                if let Err(e) = result {
+                    prompt.error = Some(e);
+                    self.prompt = Some(prompt);
+                    return;
+                }
+                self.status_message = match &self.holdings_path {
+                    Some(path) => match self.portfolio.save(path) {
+                        Ok(()) => format!("Saved {} holding", pair),
+                        Err(e) => format!("Error saving holdings: {:#}", e),
+                    },
+                    None => format!("Updated {} holding", pair),
+                };
+            }
+            PromptKind::AddPair => {
+                // Typed pairs the exchange doesn't list are rejected by
+                // `add_pair` with a suggestion
+                let result = match suggestions.into_iter().nth(prompt.choice) {
+                    Some(pair) => Ok(pair),
+                    None => Pair::parse_with_quote(prompt.input.trim(), &self.quote),
+                }
+                .and_then(|pair| self.add_pair(pair));
+                if let Err(e) = result {
+                    prompt.error = Some(e);
+                    self.prompt = Some(prompt);
+                }
+            }
+        }
+    }
+
+    /// Seconds until requests resume, while the source is rate limited.
+    pub fn rate_limited_for(&self) -> Option<u64> {
+        let remaining = self.rate_limit?.remaining(Instant::now())?;
+        Some(remaining.as_secs_f64().ceil() as u64)
+    }
+
     pub fn last_update_str(&self) -> String {
         match self.last_update {
             Some(instant) => {
@@ -199,22 +1488,93 @@ impl App {
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
+    use anyhow::anyhow;
+    use async_trait::async_trait;
+
     use super::*;
+    use crate::book::DepthUpdate;
+    use crate::source::{Level, SymbolInfo};
+
+    fn pairs(coins: &[&str]) -> Vec<Pair> {
+        coins.iter().map(|c| c.parse().unwrap()).collect()
+    }
+
+    fn candles(points: &[(i64, f64)]) -> Vec<Candle> {
+        points.iter().map(|&(ts, p)| Candle::flat(ts, p)).collect()
+    }
+
+    /// Serves canned data so `App` can be exercised without the network.
+    struct MockSource;
+
+    #[async_trait]
+    impl MarketDataSource for MockSource {
+        async fn ticker(&self, symbol: &str) -> anyhow::Result<Ticker> {
+            if symbol == "BADUSDT" {
+                return Err(anyhow!("unknown symbol"));
+            }
+            Ok(Ticker {
+                symbol: symbol.to_string(),
+                last_price: 42.0,
+                price_change_percent: -1.5,
+                high_price: 50.0,
+                low_price: 40.0,
+                volume: 1_000.0,
+                open_price: 42.64,
+                quote_volume: 42_000.0,
+                weighted_avg_price: Some(44.5),
+                trade_count: Some(500),
+            })
+        }
+
+        async fn klines(
+            &self,
+            _symbol: &str,
+            _timeframe: Timeframe,
+            _limit: u32,
+        ) -> anyhow::Result<Vec<Candle>> {
+            Ok(candles(&[(1000, 10.0), (2000, 20.0), (3000, 30.0)]))
+        }
+
+        async fn symbols(&self) -> anyhow::Result<Vec<SymbolInfo>> {
+            Ok(vec![])
+        }
+
+        async fn recent_trades(&self, _symbol: &str, _limit: u32) -> anyhow::Result<Vec<Trade>> {
+            let trade = |id, buyer_maker| Trade {
+                id,
+                price: 42.0,
+                quantity: 0.5,
+                time: 3000,
+                buyer_maker,
+            };
+            Ok(vec![trade(2, true), trade(1, false)])
+        }
+
+        async fn depth(&self, _symbol: &str, _limit: u32) -> anyhow::Result<Depth> {
+            let level = |price, quantity| Level { price, quantity };
+            Ok(Depth {
+                last_update_id: 100,
+                bids: vec![level(41.9, 2.0), level(41.8, 5.0)],
+                asks: vec![level(42.1, 1.0)],
+            })
+        }
+    }
 
     #[test]
     fn test_coin_data_new() {
-        let coin = CoinData::new("BTCUSDT");
+        let coin = CoinData::new(Pair::new("BTC", "USDT"));
         assert_eq!(coin.symbol, "BTCUSDT");
-        assert_eq!(coin.display_name, "BTC/USDT");
+        assert_eq!(coin.pair.to_string(), "BTC/USDT");
         assert_eq!(coin.price, 0.0);
         assert!(coin.price_history.is_empty());
     }
 
     #[test]
     fn test_coin_data_load_history() {
-        let mut coin = CoinData::new("BTCUSDT");
-        let history = vec![(1000, 100.0), (2000, 110.0), (3000, 105.0)];
-        coin.load_history(history);
+        let mut coin = CoinData::new(Pair::new("BTC", "USDT"));
+        coin.load_history(candles(&[(1000, 100.0), (2000, 110.0), (3000, 105.0)]));
 
         assert_eq!(coin.price_history.len(), 3);
         assert_eq!(coin.price, 105.0);
@@ -222,8 +1582,8 @@ mod tests {
 
     #[test]
     fn test_coin_data_history_data() {
-        let mut coin = CoinData::new("BTCUSDT");
-        coin.load_history(vec![(1000, 100.0), (2000, 200.0)]);
+        let mut coin = CoinData::new(Pair::new("BTC", "USDT"));
+        coin.load_history(candles(&[(1000, 100.0), (2000, 200.0)]));
 
         let data = coin.history_data();
         assert_eq!(data, vec![(0.0, 100.0), (1.0, 200.0)]);
@@ -231,8 +1591,8 @@ mod tests {
 
     #[test]
     fn test_coin_data_price_bounds() {
-        let mut coin = CoinData::new("BTCUSDT");
-        coin.load_history(vec![(1000, 100.0), (2000, 200.0)]);
+        let mut coin = CoinData::new(Pair::new("BTC", "USDT"));
+        coin.load_history(candles(&[(1000, 100.0), (2000, 200.0)]));
 
         let (min, max) = coin.price_bounds();
         assert!(min < 100.0);
@@ -241,21 +1601,57 @@ mod tests {
 
     #[test]
     fn test_coin_data_price_bounds_empty() {
-        let coin = CoinData::new("BTCUSDT");
+        let coin = CoinData::new(Pair::new("BTC", "USDT"));
         let (min, max) = coin.price_bounds();
         assert_eq!(min, 0.0);
         assert_eq!(max, 100.0);
     }
 
+    #[test]
+    fn test_coin_data_apply_kline() {
+        let mut coin = CoinData::new(Pair::new("BTC", "USDT"));
+        coin.load_history(candles(&[(1000, 100.0), (2000, 110.0)]));
+
+        coin.apply_kline(Candle::flat(2000, 115.0));
+        assert_eq!(coin.price_history.len(), 2);
+        assert_eq!(coin.price_history.back().map(|c| c.close), Some(115.0));
+
+        coin.apply_kline(Candle::flat(3000, 120.0));
+        assert_eq!(coin.price_history.len(), 3);
+
+        // Late updates for an older kline are dropped
+        coin.apply_kline(Candle::flat(1000, 90.0));
+        assert_eq!(coin.price_history.front().map(|c| c.close), Some(100.0));
+    }
+
+    #[test]
+    fn test_app_handle_stream() {
+        let mut app = App::new(pairs(&["BTC"]), Timeframe::M15);
+        app.handle_stream(StreamEvent::Connected);
+        assert!(app.streaming);
+
+        app.handle_stream(StreamEvent::Ticker(Ticker {
+            symbol: "BTCUSDT".to_string(),
+            last_price: 101.0,
+            price_change_percent: 1.0,
+            high_price: 102.0,
+            low_price: 99.0,
+            volume: 10.0,
+            open_price: 100.0,
+            quote_volume: 1_010.0,
+            weighted_avg_price: None,
+            trade_count: None,
+        }));
+        assert_eq!(app.coins[0].price, 101.0);
+        assert!(app.coins[0].price_history.is_empty());
+
+        app.handle_stream(StreamEvent::Disconnected("reset".to_string()));
+        assert!(!app.streaming);
+    }
+
     #[test]
     fn test_app_pagination() {
-        let mut app = App::new(vec![
-            "BTCUSDT".to_string(),
-            "ETHUSDT".to_string(),
-            "SOLUSDT".to_string(),
-            "XRPUSDT".to_string(),
-            "DOGEUSDT".to_string(),
-        ]);
+        let mut app = App::new(pairs(&["BTC", "ETH", "SOL", "XRP", "DOGE"]), Timeframe::M15);
         assert_eq!(app.page_index, 0);
         assert_eq!(app.total_pages(), 2);
         assert_eq!(app.visible_coins().len(), 4);
@@ -270,4 +1666,592 @@ mod tests {
         app.prev_page();
         assert_eq!(app.page_index, 0);
     }
+
+    #[tokio::test]
+    async fn test_app_load_historical_from_source() {
+        let mut app = App::new(pairs(&["BTC"]), Timeframe::M15);
+        app.load_historical(&MockSource).await;
+
+        assert_eq!(app.coins[0].price_history.len(), 3);
+        assert_eq!(app.coins[0].price, 30.0);
+    }
+
+    #[tokio::test]
+    async fn test_app_fetch_prices_from_source() {
+        let mut app = App::new(pairs(&["BTC", "BAD"]), Timeframe::M15);
+        app.fetch_prices(&MockSource).await;
+
+        assert_eq!(app.coins[0].price, 42.0);
+        assert_eq!(app.coins[0].change_24h, -1.5);
+        assert_eq!(app.coins[0].price_history.len(), 1);
+        assert_eq!(app.coins[1].price, 0.0);
+        assert!(app.coins[1].price_history.is_empty());
+        assert!(app.last_update.is_some());
+        assert_eq!(app.status_message, "No data for BAD/USDT");
+    }
+
+    #[test]
+    fn test_app_selection_follows_pages() {
+        let mut app = App::new(pairs(&["BTC", "ETH", "SOL", "XRP", "DOGE"]), Timeframe::M15);
+        assert!(app.is_selected(0));
+
+        for _ in 0..4 {
+            app.select_next();
+        }
+        assert_eq!(app.selected, 4);
+        assert_eq!(app.page_index, 1);
+        assert!(app.is_selected(0));
+
+        app.select_next();
+        assert_eq!(app.selected, 4);
+
+        app.select_prev();
+        assert_eq!(app.page_index, 0);
+        assert!(app.is_selected(3));
+
+        app.next_page();
+        assert_eq!(app.selected, 4);
+    }
+
+    #[test]
+    fn test_app_toggle_candles() {
+        let mut app = App::new(pairs(&["BTC", "ETH"]), Timeframe::M15);
+        app.select_next();
+        app.toggle_candles();
+        assert!(!app.coins[0].show_candles);
+        assert!(app.coins[1].show_candles);
+    }
+
+    #[test]
+    fn test_coin_data_record_price_folds_into_bucket() {
+        let mut coin = CoinData::new(Pair::new("BTC", "USDT"));
+        coin.timeframe = Timeframe::M15;
+        coin.record_price(900_000 + 1_000, 100.0);
+        coin.record_price(900_000 + 60_000, 105.0);
+        coin.record_price(900_000 + 120_000, 95.0);
+        assert_eq!(coin.price_history.len(), 1);
+
+        let candle = coin.price_history[0];
+        assert_eq!(candle.open_time, 900_000);
+        assert_eq!(candle.open, 100.0);
+        assert_eq!(candle.high, 105.0);
+        assert_eq!(candle.low, 95.0);
+        assert_eq!(candle.close, 95.0);
+
+        coin.record_price(1_800_000, 96.0);
+        assert_eq!(coin.price_history.len(), 2);
+        assert_eq!(coin.price_history[1].open_time, 1_800_000);
+    }
+
+    #[tokio::test]
+    async fn test_app_set_timeframe_reloads_history() {
+        let mut app = App::new(pairs(&["BTC"]), Timeframe::M15);
+        app.load_historical(&MockSource).await;
+        app.set_timeframe(Timeframe::H1);
+        assert!(app.coins[0].price_history.is_empty());
+        assert_eq!(app.coins[0].timeframe, Timeframe::H1);
+
+        // Klines from the old subscription are ignored
+        app.handle_stream(StreamEvent::Kline {
+            symbol: "BTCUSDT".to_string(),
+            timeframe: Timeframe::M15,
+            candle: Candle::flat(0, 1.0),
+        });
+        assert!(app.coins[0].price_history.is_empty());
+
+        app.load_historical(&MockSource).await;
+        assert_eq!(app.coins[0].price_history.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_app_alerts_fire_on_update() {
+        let mut app = App::new(pairs(&["BTC"]), Timeframe::M15);
+        app.alerts = AlertEngine::new(
+            vec!["BTC>40".parse().unwrap()],
+            crate::alert::DEFAULT_COOLDOWN,
+        );
+        app.fetch_prices(&MockSource).await;
+
+        assert_eq!(app.alert_log.len(), 1);
+        assert!(app.coins[0].is_flashing());
+        assert!(app.take_bell());
+        assert!(!app.take_bell());
+
+        // Cooldown keeps the next refresh quiet
+        app.fetch_prices(&MockSource).await;
+        assert_eq!(app.alert_log.len(), 1);
+    }
+
+    #[test]
+    fn test_app_rate_limited_countdown() {
+        let mut app = App::new(pairs(&["BTC"]), Timeframe::M15);
+        assert_eq!(app.rate_limited_for(), None);
+
+        app.rate_limit = Some(RateLimit {
+            used_weight: 6000,
+            weight_limit: 6000,
+            resume_at: Some(Instant::now() + Duration::from_millis(11_500)),
+        });
+        assert_eq!(app.rate_limited_for(), Some(12));
+
+        app.rate_limit = Some(RateLimit {
+            used_weight: 20,
+            weight_limit: 6000,
+            resume_at: Some(Instant::now() - Duration::from_secs(1)),
+        });
+        assert_eq!(app.rate_limited_for(), None);
+    }
+
+    #[test]
+    fn test_coin_data_indicators_follow_history() {
+        let mut coin = CoinData::new(Pair::new("BTC", "USDT"));
+        coin.set_indicators(&[Indicator::Sma(2)]);
+        coin.load_history(candles(&[(1000, 10.0), (2000, 20.0), (3000, 30.0)]));
+
+        let last = |coin: &CoinData| coin.indicators[0].values().back().copied().flatten();
+        assert_eq!(coin.indicators[0].values().len(), 3);
+        assert_eq!(last(&coin).map(|b| b.mid), Some(25.0));
+
+        // The open candle moves, then a new one starts
+        coin.apply_kline(Candle::flat(3000, 40.0));
+        assert_eq!(last(&coin).map(|b| b.mid), Some(30.0));
+        coin.apply_kline(Candle::flat(4000, 50.0));
+        assert_eq!(last(&coin).map(|b| b.mid), Some(45.0));
+        assert_eq!(coin.indicators[0].values().len(), 4);
+
+        // Hidden overlays do not widen the price range
+        assert_eq!(coin.visible_bands().count(), 0);
+        coin.show_indicators = true;
+        assert_eq!(coin.visible_bands().count(), 3);
+
+        coin.set_timeframe(Timeframe::H1);
+        assert!(coin.indicators[0].values().is_empty());
+    }
+
+    #[test]
+    fn test_app_cycle_oscillator() {
+        let mut app = App::new(pairs(&["BTC", "ETH"]), Timeframe::M15);
+        app.coins[0].load_history(candles(&[(1000, 10.0), (2000, 12.0), (3000, 11.0)]));
+        assert_eq!(app.coins[0].rsi.values().len(), 3);
+        assert_eq!(app.coins[0].macd.values().len(), 3);
+
+        app.cycle_oscillator();
+        assert_eq!(app.coins[0].oscillator, Some(Oscillator::Rsi));
+        app.cycle_oscillator();
+        assert_eq!(app.coins[0].oscillator, Some(Oscillator::Macd));
+        app.cycle_oscillator();
+        assert_eq!(app.coins[0].oscillator, None);
+        assert_eq!(app.coins[1].oscillator, None);
+
+        app.coins[0].set_timeframe(Timeframe::H1);
+        assert!(app.coins[0].rsi.values().is_empty());
+    }
+
+    #[test]
+    fn test_app_edit_holding_prompt() {
+        let mut app = App::new(pairs(&["BTC", "ETH"]), Timeframe::M15);
+        let press = |app: &mut App, code: KeyCode| {
+            app.handle_prompt_key(KeyEvent::new(code, KeyModifiers::NONE))
+        };
+        let btc = Pair::new("BTC", "USDT");
+
+        app.edit_holding();
+        assert_eq!(app.prompt.as_ref().unwrap().input, "");
+        for c in "2@1O".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        // A typo keeps the prompt open for correction
+        assert_eq!(
+            app.prompt.as_ref().unwrap().error.as_deref(),
+            Some("invalid number '1O'")
+        );
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Char('0'));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.prompt.is_none());
+        assert_eq!(app.portfolio.get(&btc).unwrap().cost(), 20.0);
+
+        app.edit_holding();
+        assert_eq!(app.prompt.as_ref().unwrap().input, "2@10");
+        press(&mut app, KeyCode::Esc);
+        assert!(app.prompt.is_none());
+        assert_eq!(app.portfolio.get(&btc).unwrap().quantity(), 2.0);
+    }
+
+    #[test]
+    fn test_app_table_sort_and_selection() {
+        let mut app = App::new(pairs(&["BTC", "ETH", "SOL", "DOGE"]), Timeframe::M15);
+        for (coin, change) in app.coins.iter_mut().zip([1.0, -2.0, 5.0, 1.0]) {
+            coin.change_24h = change;
+        }
+        app.toggle_table();
+        assert_eq!(app.table_order(), vec![0, 1, 2, 3]);
+
+        app.cycle_sort();
+        assert_eq!(app.sort_column, SortColumn::Pair);
+        assert_eq!(app.table_order(), vec![0, 3, 1, 2]);
+
+        while app.sort_column != SortColumn::Change {
+            app.cycle_sort();
+        }
+        // Largest first, ties in watchlist order
+        assert_eq!(app.table_order(), vec![2, 0, 3, 1]);
+        app.reverse_sort();
+        assert_eq!(app.table_order(), vec![1, 0, 3, 2]);
+
+        // Selection walks the sorted rows, then opens that coin's chart
+        app.select(0);
+        app.select_next();
+        assert_eq!(app.selected, 3);
+        app.select_next();
+        app.select_next();
+        app.select_next();
+        assert_eq!(app.selected, 2);
+        app.select_prev();
+        app.open_selected();
+        assert_eq!(app.view, View::Charts);
+        assert_eq!(app.selected, 3);
+        assert_eq!(app.page_index, 0);
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_prompt_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_app_edit_watchlist() {
+        let info = |base: &str, quote: &str| SymbolInfo {
+            symbol: format!("{}{}", base, quote),
+            base: base.to_string(),
+            quote: quote.to_string(),
+            trading: true,
+            tick_size: 0.001,
+            quote_precision: 8,
+        };
+        let mut app = App::new(pairs(&["BTC", "ETH"]), Timeframe::M15);
+        app.registry = SymbolRegistry::new(vec![
+            info("BTC", "USDT"),
+            info("ETH", "USDT"),
+            info("ETH", "BTC"),
+            info("SOL", "USDT"),
+            info("SOL", "BTC"),
+        ]);
+        app.chart_defaults.candles = true;
+
+        // Down picks the second match
+        app.prompt_add_pair();
+        type_text(&mut app, "sol");
+        assert_eq!(app.suggestions(), pairs(&["SOL", "SOL/BTC"]));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.prompt.is_none());
+        assert_eq!(app.coins[2].pair, Pair::new("SOL", "BTC"));
+        assert_eq!(app.coins[2].price_decimals, 3);
+        assert!(app.coins[2].show_candles);
+        assert_eq!(app.selected, 2);
+        assert_eq!(app.status_message, "Added SOL/BTC");
+
+        app.load_pending(&MockSource).await;
+        assert!(!app.has_pending());
+        assert!(app.coins[2].price_history.len() >= 3);
+        assert_eq!(app.coins[2].price, 42.0);
+
+        // Pairs already watched are selected, unknown ones keep the prompt open
+        app.prompt_add_pair();
+        type_text(&mut app, "eth");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.coins.len(), 3);
+        assert_eq!(app.selected, 1);
+        app.prompt_add_pair();
+        type_text(&mut app, "xyz");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(
+            app.prompt.as_ref().and_then(|p| p.error.as_deref()),
+            Some("unknown pair XYZ/USDT")
+        );
+        press(&mut app, KeyCode::Esc);
+
+        app.move_selected(1);
+        assert_eq!(app.watchlist(), vec!["BTC", "SOL/BTC", "ETH"]);
+        assert_eq!(app.selected, 2);
+        app.move_selected(1);
+        assert_eq!(app.selected, 2);
+
+        app.select(0);
+        app.remove_selected();
+        app.remove_selected();
+        assert_eq!(app.watchlist(), vec!["ETH"]);
+        app.remove_selected();
+        assert_eq!(app.status_message, "Cannot remove the last coin");
+        assert_eq!(app.symbols(), vec!["ETHUSDT"]);
+    }
+
+    #[test]
+    fn test_saved_watchlist_ignores_overrides() {
+        // Started with `--quote EUR --coins BTC,ETH` and a held ADA
+        let mut app = App::new(
+            vec![
+                Pair::new("BTC", "EUR"),
+                Pair::new("ETH", "EUR"),
+                Pair::new("ADA", "USDT"),
+            ],
+            Timeframe::M15,
+        );
+        app.saved_watchlist =
+            SavedWatchlist::new(vec!["SOL".into(), "ETH/EUR".into(), "x-y".into()], "usdt");
+
+        app.coins.push(CoinData::new(Pair::new("DOT", "EUR")));
+        app.saved_watchlist.add(&Pair::new("DOT", "EUR"));
+        app.saved_watchlist.add(&Pair::new("SOL", "USDT"));
+        assert_eq!(app.watchlist(), vec!["SOL", "ETH/EUR", "x-y", "DOT/EUR"]);
+
+        // ETH/EUR moves below DOT/EUR; entries not on screen keep their place
+        app.select(1);
+        app.move_selected(1);
+        app.move_selected(1);
+        assert_eq!(app.watchlist(), vec!["SOL", "DOT/EUR", "x-y", "ETH/EUR"]);
+
+        app.select(0);
+        app.remove_selected();
+        assert_eq!(app.watchlist(), vec!["SOL", "DOT/EUR", "x-y", "ETH/EUR"]);
+        app.select(1);
+        app.remove_selected();
+        assert_eq!(app.watchlist(), vec!["SOL", "x-y", "ETH/EUR"]);
+    }
+
+    #[test]
+    fn test_keep_last_saved_coin() {
+        // The config lists BTC and a holding adds ETH
+        let dir = std::env::temp_dir().join(format!("cryptowatcher-app-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "watchlist = [\"BTC\"]\n").unwrap();
+        let mut app = App::new(pairs(&["BTC", "ETH"]), Timeframe::M15);
+        app.saved_watchlist = SavedWatchlist::new(vec!["BTC".into()], "USDT");
+        app.watchlist_path = Some(path.clone());
+
+        app.remove_selected();
+        assert_eq!(app.status_message, "Cannot remove the last saved coin");
+        assert_eq!(app.coins.len(), 2);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "watchlist = [\"BTC\"]\n"
+        );
+
+        // The held coin isn't in the file, so it can go
+        app.select(1);
+        app.remove_selected();
+        assert_eq!(app.symbols(), vec!["BTCUSDT"]);
+        assert!(config::load(Some(&path)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_app_detail_view() {
+        let mut app = App::new(pairs(&["BTC", "ETH"]), Timeframe::M15);
+        assert!(!app.detail_stale());
+
+        app.select(1);
+        app.open_selected();
+        assert_eq!(app.view, View::Detail);
+        assert!(app.detail_stale());
+        app.load_detail(&MockSource).await;
+        assert!(!app.detail_stale());
+        let coin = &app.coins[1];
+        assert_eq!(coin.open_24h, 42.64);
+        assert_eq!(coin.weighted_avg_24h, Some(44.5));
+        assert_eq!(coin.trades_24h, Some(500));
+        assert_eq!(coin.trades.iter().map(|t| t.id).collect::<Vec<_>>(), [2, 1]);
+
+        // Streamed tickers keep the full ticker's extra stats
+        app.handle_stream(StreamEvent::Ticker(Ticker {
+            symbol: "ETHUSDT".to_string(),
+            last_price: 43.0,
+            price_change_percent: 0.8,
+            high_price: 50.0,
+            low_price: 40.0,
+            volume: 1_001.0,
+            open_price: 42.64,
+            quote_volume: 42_043.0,
+            weighted_avg_price: None,
+            trade_count: None,
+        }));
+        assert_eq!(app.coins[1].weighted_avg_24h, Some(44.5));
+        assert_eq!(app.coins[1].quote_volume_24h, 42_043.0);
+
+        // Switching coins needs their trades, Esc goes back to the grid
+        app.select_prev();
+        assert!(app.detail_stale());
+        app.back();
+        assert_eq!(app.view, View::Charts);
+        assert!(app.running);
+        app.back();
+        assert!(!app.running);
+    }
+
+    #[tokio::test]
+    async fn test_app_order_book_resyncs_on_gap() {
+        let mut app = App::new(pairs(&["BTC", "ETH"]), Timeframe::M15);
+        assert_eq!(app.depth_symbol(), None);
+        app.open_selected();
+        assert_eq!(app.depth_symbol(), Some("BTCUSDT".to_string()));
+        app.load_detail(&MockSource).await;
+        assert_eq!(app.book.as_ref().map(|b| b.last_update_id), Some(100));
+
+        let diff = |symbol: &str, first, last| {
+            StreamEvent::Depth(DepthUpdate {
+                symbol: symbol.to_string(),
+                first_update_id: first,
+                final_update_id: last,
+                bids: vec![Level {
+                    price: 41.9,
+                    quantity: 0.0,
+                }],
+                asks: vec![],
+            })
+        };
+        app.handle_stream(diff("ETHUSDT", 200, 300));
+        app.handle_stream(diff("BTCUSDT", 99, 101));
+        assert_eq!(
+            app.book
+                .as_ref()
+                .and_then(|b| b.best_bid())
+                .map(|l| l.price),
+            Some(41.8)
+        );
+        assert!(!app.book_stale());
+
+        // Refreshing the streamed detail view keeps the synced book
+        app.streaming = true;
+        app.load_detail(&MockSource).await;
+        assert_eq!(app.book.as_ref().map(|b| b.last_update_id), Some(101));
+
+        app.handle_stream(diff("BTCUSDT", 105, 106));
+        assert!(app.book_stale());
+        assert_eq!(
+            app.status_message,
+            "missed order book updates 102..104, resyncing"
+        );
+        app.load_book(&MockSource).await;
+        assert!(!app.book_stale());
+        assert_eq!(app.book.as_ref().map(|b| b.bids.len()), Some(2));
+
+        app.back();
+        assert!(app.book.is_none());
+        assert_eq!(app.depth_symbol(), None);
+    }
+
+    fn trade(id: u64, quantity: f64) -> Trade {
+        Trade {
+            id,
+            price: 42.0,
+            quantity,
+            time: 3000,
+            buyer_maker: false,
+        }
+    }
+
+    #[test]
+    fn test_coin_data_tape_merges_stream_and_backlog() {
+        let mut coin = CoinData::new("BTC".parse().unwrap());
+        // Streamed before the backlog arrived
+        coin.push_trade(trade(5, 1.0));
+        coin.push_trade(trade(4, 1.0));
+        coin.push_trade(trade(6, 1.0));
+        coin.merge_trades(vec![trade(5, 1.0), trade(4, 1.0), trade(3, 1.0)]);
+        let ids = |coin: &CoinData| coin.trades.iter().map(|t| t.id).collect::<Vec<_>>();
+        assert_eq!(ids(&coin), [6, 5, 4, 3]);
+
+        for id in 7..=(MAX_TRADES as u64 + 10) {
+            coin.push_trade(trade(id, 1.0));
+        }
+        assert_eq!(coin.trades.len(), MAX_TRADES);
+        assert_eq!(coin.trades[0].id, MAX_TRADES as u64 + 10);
+    }
+
+    #[tokio::test]
+    async fn test_app_trades_tape() {
+        let mut app = App::new(pairs(&["BTC", "ETH"]), Timeframe::M15);
+        app.large_trade = HashMap::from([("USDT".to_string(), 100.0)]);
+        assert!(app.tape_symbols().is_empty());
+
+        app.select(1);
+        app.toggle_tape();
+        assert_eq!(app.tape_symbols(), ["ETHUSDT"]);
+        assert!(app.has_pending_tapes());
+        app.load_tapes(&MockSource).await;
+        assert!(!app.has_pending_tapes());
+        assert_eq!(app.coins[1].trades.len(), 2);
+
+        app.handle_stream(StreamEvent::Trade {
+            symbol: "ETHUSDT".to_string(),
+            trade: trade(3, 10.0),
+        });
+        let newest = &app.coins[1].trades[0];
+        assert_eq!(newest.id, 3);
+        let eth = &app.coins[1].pair;
+        assert!(app.is_large_trade(eth, newest));
+        assert!(!app.is_large_trade(eth, &app.coins[1].trades[1]));
+        // No threshold for BTC-quoted pairs
+        assert!(!app.is_large_trade(&Pair::new("ETH", "BTC"), newest));
+
+        // The detail view streams its coin's trades too
+        app.select(0);
+        app.open_selected();
+        assert_eq!(app.tape_symbols(), ["ETHUSDT", "BTCUSDT"]);
+
+        app.back();
+        app.select(1);
+        app.toggle_tape();
+        assert!(app.tape_symbols().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_app_history_store() {
+        let closes = |app: &App| {
+            app.coins[0]
+                .price_history
+                .iter()
+                .map(|c| c.close)
+                .collect::<Vec<_>>()
+        };
+        let mut app = App::new(pairs(&["BTC"]), Timeframe::M15);
+        app.store = Some(Store::open_in_memory().unwrap());
+        app.set_history_len(2);
+        app.load_historical(&MockSource).await;
+        assert_eq!(closes(&app), [20.0, 30.0]);
+
+        app.handle_stream(StreamEvent::Kline {
+            symbol: "BTCUSDT".to_string(),
+            timeframe: Timeframe::M15,
+            candle: Candle::flat(4000, 40.0),
+        });
+        let stored = |app: &App| {
+            app.store
+                .as_ref()
+                .unwrap()
+                .candles("BTCUSDT", Timeframe::M15, 10)
+                .unwrap()
+                .len()
+        };
+        assert_eq!(stored(&app), 4);
+
+        // Polled prices only make a synthetic candle, which isn't stored
+        assert!(app.fetch_prices(&MockSource).await);
+        assert_eq!(stored(&app), 4);
+
+        // A restart shows everything stored, not just what was refetched
+        let mut restarted = App::new(pairs(&["BTC"]), Timeframe::M15);
+        restarted.store = app.store.take();
+        restarted.set_history_len(10);
+        restarted.load_historical(&MockSource).await;
+        assert_eq!(closes(&restarted), [10.0, 20.0, 30.0, 40.0]);
+    }
 }
@@ -0,0 +1,213 @@
+use crate::source::{Depth, Level};
+
+/// A streamed diff of the order book covering update ids
+/// `first_update_id..=final_update_id`; a quantity of 0 removes the level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthUpdate {
+    pub symbol: String,
+    pub first_update_id: u64,
+    pub final_update_id: u64,
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+}
+
+/// Order book for one symbol: a REST snapshot kept current by applying
+/// streamed diffs in sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBook {
+    pub symbol: String,
+    pub last_update_id: u64,
+    /// Highest price first
+    pub bids: Vec<Level>,
+    /// Lowest price first
+    pub asks: Vec<Level>,
+    /// Whether a diff has been chained onto the snapshot yet
+    synced: bool,
+}
+
+impl OrderBook {
+    pub fn new(symbol: &str, depth: Depth) -> Self {
+        let mut book = Self {
+            symbol: symbol.to_string(),
+            last_update_id: depth.last_update_id,
+            bids: Vec::with_capacity(depth.bids.len()),
+            asks: Vec::with_capacity(depth.asks.len()),
+            synced: false,
+        };
+        for level in depth.bids {
+            set_level(&mut book.bids, level, true);
+        }
+        for level in depth.asks {
+            set_level(&mut book.asks, level, false);
+        }
+        book
+    }
+
+    /// Applies a diff. Diffs the snapshot already covers are skipped; the
+    /// first one after it must straddle `last_update_id + 1` and each later
+    /// one must start right where the previous ended. Anything else means
+    /// updates were missed and the book needs a fresh snapshot.
+    pub fn apply(&mut self, update: &DepthUpdate) -> Result<(), String> {
+        if update.final_update_id <= self.last_update_id {
+            return Ok(());
+        }
+        let next = self.last_update_id + 1;
+        let in_sequence = if self.synced {
+            update.first_update_id == next
+        } else {
+            update.first_update_id <= next
+        };
+        if !in_sequence {
+            return Err(format!(
+                "missed order book updates {}..{}",
+                next,
+                update.first_update_id - 1
+            ));
+        }
+
+        for level in &update.bids {
+            set_level(&mut self.bids, *level, true);
+        }
+        for level in &update.asks {
+            set_level(&mut self.asks, *level, false);
+        }
+        self.last_update_id = update.final_update_id;
+        self.synced = true;
+        Ok(())
+    }
+
+    pub fn best_bid(&self) -> Option<Level> {
+        self.bids.first().copied()
+    }
+
+    pub fn best_ask(&self) -> Option<Level> {
+        self.asks.first().copied()
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    pub fn mid_price(&self) -> Option<f64> {
+        Some((self.best_ask()?.price + self.best_bid()?.price) / 2.0)
+    }
+}
+
+/// Running totals of the first `n` levels: (price, quantity at or better
+/// than that price).
+pub fn cumulative(levels: &[Level], n: usize) -> Vec<(f64, f64)> {
+    levels
+        .iter()
+        .take(n)
+        .scan(0.0, |total, level| {
+            *total += level.quantity;
+            Some((level.price, *total))
+        })
+        .collect()
+}
+
+/// Inserts, replaces or (for a zero quantity) removes a level, keeping the
+/// side sorted best first.
+fn set_level(levels: &mut Vec<Level>, level: Level, descending: bool) {
+    let position = levels.binary_search_by(|probe| {
+        if descending {
+            level.price.total_cmp(&probe.price)
+        } else {
+            probe.price.total_cmp(&level.price)
+        }
+    });
+    match position {
+        Ok(i) if level.quantity > 0.0 => levels[i].quantity = level.quantity,
+        Ok(i) => {
+            levels.remove(i);
+        }
+        Err(i) if level.quantity > 0.0 => levels.insert(i, level),
+        Err(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(levels: &[(f64, f64)]) -> Vec<Level> {
+        levels
+            .iter()
+            .map(|&(price, quantity)| Level { price, quantity })
+            .collect()
+    }
+
+    fn book() -> OrderBook {
+        OrderBook::new(
+            "BTCUSDT",
+            Depth {
+                last_update_id: 100,
+                bids: levels(&[(99.0, 1.0), (98.0, 2.0)]),
+                asks: levels(&[(101.0, 1.5), (102.0, 3.0)]),
+            },
+        )
+    }
+
+    fn update(first: u64, last: u64, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> DepthUpdate {
+        DepthUpdate {
+            symbol: "BTCUSDT".to_string(),
+            first_update_id: first,
+            final_update_id: last,
+            bids: levels(bids),
+            asks: levels(asks),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_stats() {
+        let book = book();
+        assert_eq!(book.best_bid().map(|l| l.price), Some(99.0));
+        assert_eq!(book.best_ask().map(|l| l.price), Some(101.0));
+        assert_eq!(book.spread(), Some(2.0));
+        assert_eq!(book.mid_price(), Some(100.0));
+        assert_eq!(cumulative(&book.asks, 5), vec![(101.0, 1.5), (102.0, 4.5)]);
+    }
+
+    #[test]
+    fn test_apply_diffs_in_sequence() {
+        let mut book = book();
+
+        // Already part of the snapshot
+        book.apply(&update(90, 100, &[(99.0, 0.0)], &[])).unwrap();
+        assert_eq!(book.bids.len(), 2);
+
+        // The first diff straddles the snapshot
+        book.apply(&update(
+            95,
+            103,
+            &[(99.5, 4.0), (98.0, 0.0)],
+            &[(101.0, 0.5)],
+        ))
+        .unwrap();
+        assert_eq!(book.bids, levels(&[(99.5, 4.0), (99.0, 1.0)]));
+        assert_eq!(book.asks, levels(&[(101.0, 0.5), (102.0, 3.0)]));
+        assert_eq!(book.last_update_id, 103);
+
+        book.apply(&update(104, 104, &[], &[(100.5, 1.0), (103.0, 0.0)]))
+            .unwrap();
+        assert_eq!(
+            book.asks,
+            levels(&[(100.5, 1.0), (101.0, 0.5), (102.0, 3.0)])
+        );
+    }
+
+    #[test]
+    fn test_sequence_gaps() {
+        // The snapshot is older than the first diff
+        let mut stale = book();
+        assert_eq!(
+            stale.apply(&update(105, 110, &[], &[])),
+            Err("missed order book updates 101..104".to_string())
+        );
+
+        let mut book = book();
+        book.apply(&update(100, 102, &[], &[])).unwrap();
+        assert!(book.apply(&update(104, 105, &[], &[])).is_err());
+        assert_eq!(book.last_update_id, 102);
+    }
+}
@@ -0,0 +1,763 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+use toml_edit::{value, Array, DocumentMut};
+
+use crate::alert::{self, AlertRule};
+use crate::app::MAX_CHARTS_PER_PAGE;
+use crate::indicator::{Indicator, Oscillator, DEFAULT_BAND_WIDTH};
+use crate::keys::{Action, Key, Keymap};
+use crate::source::{Timeframe, DEFAULT_QUOTE};
+use crate::statusline::Template;
+use crate::ui::Theme;
+
+/// Written by `cryptowatcher config init`; must parse to `Config::default()`.
+pub const DEFAULT_CONFIG: &str = r##"# cryptowatcher configuration
+#
+# Command-line flags take precedence over anything set here.
+
+# Coins to watch (at most 20): bare coins use `quote`, or give a full pair
+# such as "ETH/BTC"
+watchlist = ["BTC", "ETH"]
+
+# Write coins added, removed or reordered in the app back to `watchlist`.
+# Rewriting the file keeps its comments.
+persist_watchlist = false
+
+# Quote asset for bare coins
+quote = "USDT"
+
+# Seconds between REST refreshes
+interval = 60
+
+# Chart timeframe: 1m, 5m, 15m, 1h, 4h, 1d or 1w
+timeframe = "15m"
+
+# Stream live prices over WebSocket, polling only while the socket is down
+stream = false
+
+# Chart overlays toggled with `i`: smaN, emaN, bbN or bbN:WIDTH (Bollinger Bands)
+indicators = ["sma20", "ema50", "bb20"]
+
+# Holdings file for position values and P&L, edited in the app with `e`
+# [default: holdings.toml in the default config directory]
+# holdings = "/path/to/holdings.toml"
+
+# Serve Prometheus metrics from http://ADDR/metrics
+# metrics_addr = "127.0.0.1:9184"
+
+# Read prices from a `cryptowatcher serve` instance instead of the exchange
+# server = "10.0.0.5:9185"
+
+[theme]
+# Base palette: "synthwave" or "classic" (plain ANSI colors)
+name = "synthwave"
+# Override single roles with a color name ("red") or hex value ("#ff2e97").
+# Roles: accent, highlight, positive, negative, border, muted, text
+# positive = "#39ff14"
+
+[layout]
+# Charts per page, 1 to 4
+charts_per_page = 4
+# Start every chart in candlestick mode
+candles = false
+# Start every chart with its indicators shown
+indicators = false
+# Per-candle volume bars under every chart
+volume = true
+# Oscillator under every chart: "rsi" or "macd" (unset for none)
+# oscillator = "rsi"
+# Show the alert log panel on startup
+alert_log = false
+
+[tape]
+# Show the trades tape beside every chart
+show = false
+# Trades worth at least this much, per quote asset, are highlighted and are
+# the only ones left when the tape is filtered with `f`. Tapes in other quote
+# assets highlight nothing and are never filtered
+large_trade = { USDT = 50000, USDC = 50000, FDUSD = 50000, BTC = 0.5, ETH = 15 }
+
+[statusline]
+# Text per coin for `cryptowatcher statusline`. Placeholders: {base} {quote}
+# {pair} {price} {price_short} {change} {sign} {arrow} {high} {low} {volume};
+# {change} is unsigned, so pair it with {arrow} or {sign}
+template = "{base} {price_short} {arrow}{change}%"
+# Between coins, except in i3bar output where each coin is its own block
+separator = "  "
+
+[storage]
+# Keep price history in a SQLite database so it survives restarts; on
+# startup only the candles missed since the last run are fetched
+enabled = false
+# [default: history.db in the platform data directory]
+# path = "/path/to/history.db"
+# Candles per chart while history is stored
+history = 500
+# How long polled and streamed prices are kept
+tick_retention = "1d"
+
+# How long candles of each timeframe are kept; unlisted timeframes are kept
+# forever
+[storage.retention]
+1m = "7d"
+5m = "30d"
+15m = "90d"
+
+[alerts]
+# Minimum time between repeats of the same alert: 90s, 5m, 1h, ...
+cooldown = "5m"
+# "BTC>70000"  price above
+# "BTC<60000"  price below
+# "ETH%5/1h"   moves 5% either way within an hour
+# "SOL~sma20"  crosses its 20-candle simple moving average
+rules = []
+
+[keys]
+# Each action takes a list of keys: characters ("q", "T") or names
+# (esc, enter, tab, space, up, down, left, right, pageup, pagedown, f1-f12).
+# Keys listed here are removed from their default action.
+# quit = ["q"]
+# back = ["esc"]
+# refresh = ["r"]
+# prev_page = ["left", "h"]
+# next_page = ["right", "l"]
+# select_prev = ["up", "k"]
+# select_next = ["down", "j"]
+# toggle_candles = ["c"]
+# toggle_indicators = ["i"]
+# cycle_oscillator = ["o"]
+# edit_holding = ["e"]
+# toggle_table = ["v"]
+# cycle_sort = ["s"]
+# reverse_sort = ["S"]
+# open_selected = ["enter"]
+# add_coin = ["+", "insert"]
+# remove_coin = ["-", "delete"]
+# move_coin_up = ["K"]
+# move_coin_down = ["J"]
+# toggle_tape = ["p"]
+# toggle_tape_filter = ["f"]
+# next_timeframe = ["t"]
+# prev_timeframe = ["T"]
+# toggle_alert_log = ["a"]
+# scroll_alerts_up = ["pageup"]
+# scroll_alerts_down = ["pagedown"]
+"##;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    #[serde(deserialize_with = "non_empty")]
+    pub watchlist: Vec<String>,
+    pub persist_watchlist: bool,
+    #[serde(deserialize_with = "asset")]
+    pub quote: String,
+    #[serde(deserialize_with = "positive")]
+    pub interval: u64,
+    pub timeframe: Timeframe,
+    pub stream: bool,
+    pub indicators: Vec<Indicator>,
+    pub holdings: Option<PathBuf>,
+    pub metrics_addr: Option<SocketAddr>,
+    pub server: Option<String>,
+    pub theme: ThemeConfig,
+    pub layout: LayoutConfig,
+    pub tape: TapeConfig,
+    pub statusline: StatuslineConfig,
+    pub storage: StorageConfig,
+    pub alerts: AlertsConfig,
+    pub keys: HashMap<Action, Vec<Key>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            watchlist: vec!["BTC".to_string(), "ETH".to_string()],
+            persist_watchlist: false,
+            quote: DEFAULT_QUOTE.to_string(),
+            interval: 60,
+            timeframe: Timeframe::default(),
+            stream: false,
+            indicators: vec![
+                Indicator::Sma(20),
+                Indicator::Ema(50),
+                Indicator::Bollinger {
+                    period: 20,
+                    width: DEFAULT_BAND_WIDTH,
+                },
+            ],
+            holdings: None,
+            metrics_addr: None,
+            server: None,
+            theme: ThemeConfig::default(),
+            layout: LayoutConfig::default(),
+            tape: TapeConfig::default(),
+            statusline: StatuslineConfig::default(),
+            storage: StorageConfig::default(),
+            alerts: AlertsConfig::default(),
+            keys: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn keymap(&self) -> Keymap {
+        Keymap::with_overrides(&self.keys)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    #[serde(deserialize_with = "theme_name")]
+    pub name: String,
+    pub accent: Option<ThemeColor>,
+    pub highlight: Option<ThemeColor>,
+    pub positive: Option<ThemeColor>,
+    pub negative: Option<ThemeColor>,
+    pub border: Option<ThemeColor>,
+    pub muted: Option<ThemeColor>,
+    pub text: Option<ThemeColor>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            name: "synthwave".to_string(),
+            accent: None,
+            highlight: None,
+            positive: None,
+            negative: None,
+            border: None,
+            muted: None,
+            text: None,
+        }
+    }
+}
+
+impl ThemeConfig {
+    /// The named base palette with any per-role overrides applied.
+    pub fn resolve(&self) -> Theme {
+        let mut theme = Theme::named(&self.name).unwrap_or_default();
+        let overrides = [
+            (&mut theme.accent, self.accent),
+            (&mut theme.highlight, self.highlight),
+            (&mut theme.positive, self.positive),
+            (&mut theme.negative, self.negative),
+            (&mut theme.border, self.border),
+            (&mut theme.muted, self.muted),
+            (&mut theme.text, self.text),
+        ];
+        for (slot, color) in overrides {
+            if let Some(ThemeColor(color)) = color {
+                *slot = color;
+            }
+        }
+        theme
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ThemeColor(Color);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Color::from_str(&s)
+            .map(ThemeColor)
+            .map_err(|_| format!("invalid color '{}', expected a name or #rrggbb", s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    #[serde(deserialize_with = "charts_per_page")]
+    pub charts_per_page: usize,
+    pub candles: bool,
+    pub indicators: bool,
+    pub volume: bool,
+    pub oscillator: Option<Oscillator>,
+    pub alert_log: bool,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            charts_per_page: MAX_CHARTS_PER_PAGE,
+            candles: false,
+            indicators: false,
+            volume: true,
+            oscillator: None,
+            alert_log: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TapeConfig {
+    pub show: bool,
+    /// Notional that makes a trade a large print, by quote asset
+    #[serde(deserialize_with = "large_trade")]
+    pub large_trade: HashMap<String, f64>,
+}
+
+impl Default for TapeConfig {
+    fn default() -> Self {
+        Self {
+            show: false,
+            large_trade: HashMap::from([
+                ("USDT".to_string(), 50_000.0),
+                ("USDC".to_string(), 50_000.0),
+                ("FDUSD".to_string(), 50_000.0),
+                ("BTC".to_string(), 0.5),
+                ("ETH".to_string(), 15.0),
+            ]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatuslineConfig {
+    pub template: Template,
+    pub separator: String,
+}
+
+impl Default for StatuslineConfig {
+    fn default() -> Self {
+        Self {
+            template: Template::default(),
+            separator: "  ".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub enabled: bool,
+    pub path: Option<PathBuf>,
+    #[serde(deserialize_with = "positive")]
+    pub history: u64,
+    #[serde(deserialize_with = "duration")]
+    pub tick_retention: Duration,
+    #[serde(deserialize_with = "retention")]
+    pub retention: HashMap<Timeframe, Duration>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        let days = |n: u64| Duration::from_secs(n * 86_400);
+        Self {
+            enabled: false,
+            path: None,
+            history: 500,
+            tick_retention: days(1),
+            retention: HashMap::from([
+                (Timeframe::M1, days(7)),
+                (Timeframe::M5, days(30)),
+                (Timeframe::M15, days(90)),
+            ]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsConfig {
+    #[serde(deserialize_with = "duration")]
+    pub cooldown: Duration,
+    pub rules: Vec<AlertRule>,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            cooldown: alert::DEFAULT_COOLDOWN,
+            rules: Vec::new(),
+        }
+    }
+}
+
+// Field validators: failing inside deserialization lets the TOML error
+// point at the offending line and column.
+
+fn non_empty<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let list: Vec<String> = Deserialize::deserialize(deserializer)?;
+    if list.is_empty() {
+        return Err(serde::de::Error::custom("must list at least one coin"));
+    }
+    Ok(list)
+}
+
+fn asset<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let asset: String = Deserialize::deserialize(deserializer)?;
+    if asset.is_empty() || !asset.chars().all(|ch| ch.is_ascii_alphanumeric()) {
+        return Err(serde::de::Error::custom(format!(
+            "invalid asset '{}'",
+            asset
+        )));
+    }
+    Ok(asset.to_uppercase())
+}
+
+fn positive<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: u64 = Deserialize::deserialize(deserializer)?;
+    if value == 0 {
+        return Err(serde::de::Error::custom("must be greater than zero"));
+    }
+    Ok(value)
+}
+
+fn large_trade<'de, D>(deserializer: D) -> Result<HashMap<String, f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let map: HashMap<String, f64> = Deserialize::deserialize(deserializer)?;
+    map.into_iter()
+        .map(|(quote, value)| {
+            if !(value >= 0.0 && value.is_finite()) {
+                return Err(format!("{} must be zero or more", quote));
+            }
+            Ok((quote.to_uppercase(), value))
+        })
+        .collect::<Result<_, String>>()
+        .map_err(serde::de::Error::custom)
+}
+
+fn charts_per_page<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: usize = Deserialize::deserialize(deserializer)?;
+    if !(1..=MAX_CHARTS_PER_PAGE).contains(&value) {
+        return Err(serde::de::Error::custom(format!(
+            "must be between 1 and {}",
+            MAX_CHARTS_PER_PAGE
+        )));
+    }
+    Ok(value)
+}
+
+fn theme_name<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let name: String = Deserialize::deserialize(deserializer)?;
+    if Theme::named(&name).is_none() {
+        return Err(serde::de::Error::custom(format!(
+            "unknown theme '{}', expected synthwave or classic",
+            name
+        )));
+    }
+    Ok(name)
+}
+
+fn duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let text: String = Deserialize::deserialize(deserializer)?;
+    alert::parse_duration(&text).map_err(serde::de::Error::custom)
+}
+
+fn retention<'de, D>(deserializer: D) -> Result<HashMap<Timeframe, Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let map: HashMap<Timeframe, String> = Deserialize::deserialize(deserializer)?;
+    map.into_iter()
+        .map(|(timeframe, text)| Ok((timeframe, alert::parse_duration(&text)?)))
+        .collect::<Result<_, String>>()
+        .map_err(serde::de::Error::custom)
+}
+
+/// `$XDG_CONFIG_HOME/cryptowatcher/config.toml` or the platform equivalent.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("cryptowatcher").join("config.toml"))
+}
+
+pub fn parse(text: &str) -> Result<Config, toml::de::Error> {
+    toml::from_str(text)
+}
+
+/// Loads the config at `path`, or the default location if none is given.
+/// A missing file at the default location just means "use defaults".
+pub fn load(path: Option<&Path>) -> Result<Config> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => match default_path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        },
+    };
+
+    let text = fs::read_to_string(&path)
+        .with_context(|| format!("cannot read config file {}", path.display()))?;
+    parse(&text).map_err(|e| anyhow!("invalid config file {}\n{}", path.display(), e))
+}
+
+/// Writes the commented default config, refusing to clobber an existing
+/// file unless `force` is set.
+pub fn init(path: Option<&Path>, force: bool) -> Result<PathBuf> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => default_path().ok_or_else(|| anyhow!("cannot determine the config directory"))?,
+    };
+    if path.exists() && !force {
+        bail!(
+            "{} already exists, pass --force to overwrite it",
+            path.display()
+        );
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("cannot create directory {}", dir.display()))?;
+    }
+    fs::write(&path, DEFAULT_CONFIG)
+        .with_context(|| format!("cannot write config file {}", path.display()))?;
+    Ok(path)
+}
+
+/// Replaces `watchlist` in the config at `path`, keeping the rest of the
+/// file as written. A missing file starts from the commented template.
+/// An empty list is refused, since the config would no longer load.
+pub fn save_watchlist(path: &Path, watchlist: &[String]) -> Result<()> {
+    if watchlist.is_empty() {
+        bail!("the watchlist must list at least one coin");
+    }
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => DEFAULT_CONFIG.to_string(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("cannot read config file {}", path.display()))
+        }
+    };
+    let mut doc: DocumentMut = text
+        .parse()
+        .map_err(|e| anyhow!("invalid config file {}\n{}", path.display(), e))?;
+    doc["watchlist"] = value(Array::from_iter(watchlist));
+    write_atomic(path, &doc.to_string())
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it into
+/// place, so a crash never leaves a half-written file behind.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("cannot create directory {}", dir.display()))?;
+    }
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let tmp = path.with_file_name(name);
+    let mut out = File::create(&tmp).with_context(|| format!("cannot write {}", tmp.display()))?;
+    out.write_all(contents.as_bytes())?;
+    out.sync_all()?;
+    fs::rename(&tmp, path).with_context(|| format!("cannot replace {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_template_matches_defaults() {
+        assert_eq!(parse(DEFAULT_CONFIG).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_empty_config_uses_defaults() {
+        assert_eq!(parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_parse_full_config() {
+        let config = parse(
+            r##"
+watchlist = ["SOL", "ETH/BTC"]
+persist_watchlist = true
+quote = "fdusd"
+interval = 15
+holdings = "/tmp/holdings.toml"
+metrics_addr = "0.0.0.0:9184"
+server = "http://10.0.0.5:9185"
+timeframe = "1h"
+
+[theme]
+name = "classic"
+positive = "#00ff00"
+
+[layout]
+charts_per_page = 2
+candles = true
+volume = false
+oscillator = "macd"
+
+[tape]
+show = true
+large_trade = { usdt = 2500.5, BTC = 0.1 }
+
+[statusline]
+template = "{pair} {price}"
+separator = " | "
+
+[storage]
+enabled = true
+path = "/tmp/history.db"
+history = 2000
+
+[storage.retention]
+1h = "365d"
+
+[alerts]
+cooldown = "90s"
+rules = ["SOL>200", "SOL~sma50"]
+
+[keys]
+quit = ["x"]
+"##,
+        )
+        .unwrap();
+
+        assert_eq!(config.watchlist, vec!["SOL", "ETH/BTC"]);
+        assert!(config.persist_watchlist);
+        assert_eq!(config.quote, "FDUSD");
+        assert_eq!(config.interval, 15);
+        assert_eq!(config.holdings, Some(PathBuf::from("/tmp/holdings.toml")));
+        assert_eq!(config.metrics_addr, Some("0.0.0.0:9184".parse().unwrap()));
+        assert_eq!(config.server.as_deref(), Some("http://10.0.0.5:9185"));
+        assert_eq!(config.timeframe, Timeframe::H1);
+        assert_eq!(config.layout.charts_per_page, 2);
+        assert!(config.layout.candles);
+        assert!(!config.layout.volume);
+        assert_eq!(config.layout.oscillator, Some(Oscillator::Macd));
+        assert!(config.tape.show);
+        assert_eq!(
+            config.tape.large_trade,
+            HashMap::from([("USDT".to_string(), 2500.5), ("BTC".to_string(), 0.1)])
+        );
+        assert_eq!(config.statusline.template.to_string(), "{pair} {price}");
+        assert_eq!(config.statusline.separator, " | ");
+        assert!(config.storage.enabled);
+        assert_eq!(config.storage.history, 2000);
+        assert_eq!(
+            config.storage.retention,
+            HashMap::from([(Timeframe::H1, Duration::from_secs(365 * 86_400))])
+        );
+        assert_eq!(config.alerts.cooldown, Duration::from_secs(90));
+        assert_eq!(config.alerts.rules.len(), 2);
+
+        let theme = config.theme.resolve();
+        assert_eq!(theme.positive, Color::Rgb(0, 255, 0));
+        assert_eq!(theme.negative, Color::Red);
+        assert_eq!(config.keymap().label(Action::Quit), "X");
+    }
+
+    #[test]
+    fn test_errors_point_at_line_and_field() {
+        let err = parse("watchlist = [\"BTC\"]\ntimeframe = \"2h\"\n").unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("line 2"), "{}", msg);
+        assert!(msg.contains("unknown timeframe '2h'"), "{}", msg);
+
+        let msg = parse("[layout]\ncharts_per_page = 9\n")
+            .unwrap_err()
+            .to_string();
+        assert!(msg.contains("line 2"), "{}", msg);
+        assert!(msg.contains("between 1 and 4"), "{}", msg);
+
+        let msg = parse("[tape]\nlarge_trade = { BTC = -1 }\n")
+            .unwrap_err()
+            .to_string();
+        assert!(msg.contains("BTC must be zero or more"), "{}", msg);
+
+        let msg = parse("[statusline]\ntemplate = \"{base} {prize}\"\n")
+            .unwrap_err()
+            .to_string();
+        assert!(msg.contains("line 2"), "{}", msg);
+        assert!(msg.contains("unknown placeholder '{prize}'"), "{}", msg);
+
+        let msg = parse("[storage.retention]\n2h = \"1d\"\n")
+            .unwrap_err()
+            .to_string();
+        assert!(msg.contains("unknown timeframe '2h'"), "{}", msg);
+
+        let msg = parse("[storage.retention]\n1m = \"7 days\"\n")
+            .unwrap_err()
+            .to_string();
+        assert!(msg.contains("duration '7 days'"), "{}", msg);
+
+        let msg = parse("intervall = 5\n").unwrap_err().to_string();
+        assert!(msg.contains("unknown field `intervall`"), "{}", msg);
+
+        let msg = parse("[keys]\nquit = [\"hyper\"]\n")
+            .unwrap_err()
+            .to_string();
+        assert!(msg.contains("unknown key 'hyper'"), "{}", msg);
+
+        let msg = parse("[keys]\nexplode = [\"x\"]\n")
+            .unwrap_err()
+            .to_string();
+        assert!(msg.contains("unknown variant `explode`"), "{}", msg);
+
+        let msg = parse("[alerts]\nrules = [\"BTC>>1\"]\n")
+            .unwrap_err()
+            .to_string();
+        assert!(msg.contains("invalid alert"), "{}", msg);
+
+        let msg = parse("indicators = [\"wma9\"]\n").unwrap_err().to_string();
+        assert!(msg.contains("invalid indicator 'wma9'"), "{}", msg);
+    }
+
+    #[test]
+    fn test_save_watchlist_keeps_comments() {
+        let dir = std::env::temp_dir().join(format!("cryptowatcher-config-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        let _ = fs::remove_file(&path);
+
+        // A missing file starts from the template
+        save_watchlist(&path, &["SOL".to_string(), "ETH/BTC".to_string()]).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("# Coins to watch"));
+        assert!(text.contains("# back = [\"esc\"]"));
+        let config = parse(&text).unwrap();
+        assert_eq!(config.watchlist, vec!["SOL", "ETH/BTC"]);
+        assert_eq!(config.quote, "USDT");
+
+        fs::write(
+            &path,
+            "# mine\nquote = \"EUR\" # euros\nwatchlist = [\"BTC\"]\n",
+        )
+        .unwrap();
+        save_watchlist(&path, &["BTC".to_string(), "ETH".to_string()]).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# mine\nquote = \"EUR\" # euros\nwatchlist = [\"BTC\", \"ETH\"]\n"
+        );
+
+        assert!(save_watchlist(&path, &[]).is_err());
+        assert!(parse(&fs::read_to_string(&path).unwrap()).is_ok());
+    }
+}
@@ -6,17 +6,24 @@ use futures::{FutureExt, StreamExt};
 use tokio::sync::mpsc;
 use tokio::time::interval;
 
+use crate::stream::StreamEvent;
+
+// Keeps time-based UI (alert flashes, "updated Ns ago") moving between ticks
+const REDRAW_RATE: Duration = Duration::from_millis(500);
+
 #[derive(Debug, Clone)]
 pub enum AppEvent {
     Key(KeyEvent),
     Tick,
+    Redraw,
     Quit,
     Resize,
+    Stream(StreamEvent),
 }
 
 pub struct EventHandler {
     rx: mpsc::UnboundedReceiver<AppEvent>,
-    _tx: mpsc::UnboundedSender<AppEvent>,
+    tx: mpsc::UnboundedSender<AppEvent>,
 }
 
 impl EventHandler {
@@ -27,6 +34,7 @@ impl EventHandler {
         tokio::spawn(async move {
             let mut reader = EventStream::new();
             let mut tick = interval(tick_rate);
+            let mut redraw = interval(REDRAW_RATE);
 
             loop {
                 let tick_delay = tick.tick();
@@ -38,6 +46,11 @@ impl EventHandler {
                             break;
                         }
                     }
+                    _ = redraw.tick() => {
+                        if tx_clone.send(AppEvent::Redraw).is_err() {
+                            break;
+                        }
+                    }
                     maybe_event = crossterm_event => {
                         match maybe_event {
                             Some(Ok(Event::Key(key))) => {
@@ -49,6 +62,8 @@ impl EventHandler {
                                     break;
                                 }
                             }
+                            // Keep the send out of the pattern guard
+                            #[allow(clippy::collapsible_match)]
                             Some(Ok(Event::Resize(_, _))) => {
                                 if tx_clone.send(AppEvent::Resize).is_err() {
                                     break;
@@ -63,7 +78,12 @@ impl EventHandler {
             }
         });
 
-        Self { rx, _tx: tx }
+        Self { rx, tx }
+    }
+
+    /// Handle for background producers (e.g. the WebSocket feed) to inject events.
+    pub fn sender(&self) -> mpsc::UnboundedSender<AppEvent> {
+        self.tx.clone()
     }
 
     pub async fn next(&mut self) -> Result<AppEvent> {
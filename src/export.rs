@@ -0,0 +1,410 @@
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use clap::ValueEnum;
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use serde_json::{Map, Value};
+
+use crate::source::{Candle, Ticker, Timeframe};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Csv,
+    Jsonl,
+    Parquet,
+}
+
+impl Format {
+    /// Guessed from the output file's extension; CSV for anything else.
+    pub fn from_path(path: Option<&Path>) -> Self {
+        let extension = path
+            .and_then(|p| p.extension())
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("jsonl" | "ndjson" | "json") => Format::Jsonl,
+            Some("parquet") => Format::Parquet,
+            _ => Format::Csv,
+        }
+    }
+}
+
+/// Rows to export, held column by column so every format can be written
+/// from the same data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    columns: Vec<Column>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Column {
+    name: &'static str,
+    values: Values,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Values {
+    Text(Vec<String>),
+    /// `None` where the value is unknown
+    Int(Vec<Option<i64>>),
+    /// Milliseconds since the epoch
+    Time(Vec<i64>),
+    Float(Vec<f64>),
+}
+
+impl Table {
+    /// One row per candle, symbols in the order given.
+    pub fn klines(timeframe: Timeframe, klines: &[(String, Vec<Candle>)]) -> Self {
+        let candles = || klines.iter().flat_map(|(_, candles)| candles);
+        let floats = |f: fn(&Candle) -> f64| Values::Float(candles().map(f).collect());
+        let column = |name, values| Column { name, values };
+        let symbols = klines
+            .iter()
+            .flat_map(|(symbol, candles)| candles.iter().map(|_| symbol.clone()))
+            .collect();
+        Self {
+            columns: vec![
+                column("symbol", Values::Text(symbols)),
+                column(
+                    "timeframe",
+                    Values::Text(candles().map(|_| timeframe.to_string()).collect()),
+                ),
+                column(
+                    "open_time",
+                    Values::Time(candles().map(|c| c.open_time).collect()),
+                ),
+                column("open", floats(|c| c.open)),
+                column("high", floats(|c| c.high)),
+                column("low", floats(|c| c.low)),
+                column("close", floats(|c| c.close)),
+                column("volume", floats(|c| c.volume)),
+            ],
+        }
+    }
+
+    /// One row per 24h ticker.
+    pub fn tickers(tickers: &[Ticker]) -> Self {
+        let floats = |f: fn(&Ticker) -> f64| Values::Float(tickers.iter().map(f).collect());
+        let column = |name, values| Column { name, values };
+        Self {
+            columns: vec![
+                column(
+                    "symbol",
+                    Values::Text(tickers.iter().map(|t| t.symbol.clone()).collect()),
+                ),
+                column("last_price", floats(|t| t.last_price)),
+                column("price_change_percent", floats(|t| t.price_change_percent)),
+                column("open_price", floats(|t| t.open_price)),
+                column("high_price", floats(|t| t.high_price)),
+                column("low_price", floats(|t| t.low_price)),
+                column("volume", floats(|t| t.volume)),
+                column("quote_volume", floats(|t| t.quote_volume)),
+                column(
+                    "weighted_avg_price",
+                    floats(|t| t.weighted_avg_price.unwrap_or(f64::NAN)),
+                ),
+                column(
+                    "trade_count",
+                    Values::Int(
+                        tickers
+                            .iter()
+                            .map(|t| t.trade_count.map(|n| n as i64))
+                            .collect(),
+                    ),
+                ),
+            ],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns.first().map_or(0, |c| c.values.len())
+    }
+}
+
+impl Values {
+    fn len(&self) -> usize {
+        match self {
+            Values::Text(v) => v.len(),
+            Values::Int(v) => v.len(),
+            Values::Time(v) => v.len(),
+            Values::Float(v) => v.len(),
+        }
+    }
+
+    fn json(&self, row: usize) -> Value {
+        match self {
+            Values::Text(v) => Value::from(v[row].as_str()),
+            Values::Int(v) => Value::from(v[row]),
+            Values::Time(v) => Value::from(v[row]),
+            // NaN has no JSON form and becomes null
+            Values::Float(v) => Value::from(v[row]),
+        }
+    }
+
+    fn csv(&self, row: usize) -> String {
+        match self {
+            Values::Text(v) => csv_field(&v[row]),
+            Values::Int(v) => v[row].map(|n| n.to_string()).unwrap_or_default(),
+            Values::Time(v) => v[row].to_string(),
+            Values::Float(v) if v[row].is_nan() => String::new(),
+            Values::Float(v) => v[row].to_string(),
+        }
+    }
+}
+
+pub fn write(table: &Table, format: Format, out: impl Write + Send) -> Result<()> {
+    match format {
+        Format::Csv => write_csv(table, out),
+        Format::Jsonl => write_jsonl(table, out),
+        Format::Parquet => write_parquet(table, out),
+    }
+}
+
+fn write_csv(table: &Table, mut out: impl Write) -> Result<()> {
+    let header: Vec<&str> = table.columns.iter().map(|c| c.name).collect();
+    writeln!(out, "{}", header.join(","))?;
+    for row in 0..table.len() {
+        let fields: Vec<String> = table.columns.iter().map(|c| c.values.csv(row)).collect();
+        writeln!(out, "{}", fields.join(","))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn write_jsonl(table: &Table, mut out: impl Write) -> Result<()> {
+    for row in 0..table.len() {
+        let object: Map<String, Value> = table
+            .columns
+            .iter()
+            .map(|c| (c.name.to_string(), c.values.json(row)))
+            .collect();
+        serde_json::to_writer(&mut out, &object)?;
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the table as a single row group; times are annotated as UTC
+/// millisecond timestamps.
+fn write_parquet(table: &Table, out: impl Write + Send) -> Result<()> {
+    let fields: Vec<String> = table
+        .columns
+        .iter()
+        .map(|c| {
+            let kind = match c.values {
+                Values::Text(_) => "BYTE_ARRAY",
+                Values::Int(_) | Values::Time(_) => "INT64",
+                Values::Float(_) => "DOUBLE",
+            };
+            let annotation = match c.values {
+                Values::Text(_) => " (UTF8)",
+                Values::Time(_) => " (TIMESTAMP(MILLIS, true))",
+                Values::Int(_) | Values::Float(_) => "",
+            };
+            let repetition = match c.values {
+                Values::Int(_) => "OPTIONAL",
+                _ => "REQUIRED",
+            };
+            format!("{} {} {}{};", repetition, kind, c.name, annotation)
+        })
+        .collect();
+    let schema = parse_message_type(&format!("message export {{ {} }}", fields.join(" ")))?;
+    let properties = WriterProperties::builder().build();
+    let mut writer = SerializedFileWriter::new(out, Arc::new(schema), Arc::new(properties))?;
+
+    let mut row_group = writer.next_row_group()?;
+    for column in &table.columns {
+        let Some(mut chunk) = row_group.next_column()? else {
+            break;
+        };
+        match &column.values {
+            Values::Text(v) => {
+                let bytes: Vec<ByteArray> = v.iter().map(|s| ByteArray::from(s.as_str())).collect();
+                chunk
+                    .typed::<ByteArrayType>()
+                    .write_batch(&bytes, None, None)?;
+            }
+            Values::Int(v) => {
+                let present: Vec<i64> = v.iter().flatten().copied().collect();
+                let levels: Vec<i16> = v.iter().map(|n| n.is_some() as i16).collect();
+                chunk
+                    .typed::<Int64Type>()
+                    .write_batch(&present, Some(&levels), None)?;
+            }
+            Values::Time(v) => {
+                chunk.typed::<Int64Type>().write_batch(v, None, None)?;
+            }
+            Values::Float(v) => {
+                chunk.typed::<DoubleType>().write_batch(v, None, None)?;
+            }
+        }
+        chunk.close()?;
+    }
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Parses a UTC date (`2024-01-31`), date and time (`2024-01-31T12:00`,
+/// `2024-01-31 12:00:00`), RFC 3339 timestamp or epoch milliseconds.
+pub fn parse_time(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    if let Ok(ms) = s.parse::<i64>() {
+        return Ok(ms);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc()
+            .timestamp_millis());
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Utc).timestamp_millis());
+    }
+    for pattern in [
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%d %H:%M:%S",
+    ] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, pattern) {
+            return Ok(dt.and_utc().timestamp_millis());
+        }
+    }
+    Err(format!(
+        "invalid time '{}', expected a date like 2024-01-31 or 2024-01-31T12:00",
+        s
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Table {
+        let candle = |open_time, close| Candle {
+            open_time,
+            open: 1.5,
+            high: 2.0,
+            low: 1.0,
+            close,
+            volume: 10.0,
+        };
+        Table::klines(
+            Timeframe::H1,
+            &[
+                (
+                    "BTCUSDT".to_string(),
+                    vec![candle(0, 1.75), candle(3_600_000, 1.8)],
+                ),
+                ("ETHUSDT".to_string(), vec![candle(0, 2.0)]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(Format::from_path(None), Format::Csv);
+        assert_eq!(
+            Format::from_path(Some(Path::new("out.PARQUET"))),
+            Format::Parquet
+        );
+        assert_eq!(
+            Format::from_path(Some(Path::new("out.jsonl"))),
+            Format::Jsonl
+        );
+        assert_eq!(Format::from_path(Some(Path::new("out.txt"))), Format::Csv);
+    }
+
+    #[test]
+    fn test_write_csv_and_jsonl() {
+        let table = sample();
+        assert_eq!(table.len(), 3);
+
+        let mut csv = Vec::new();
+        write(&table, Format::Csv, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "symbol,timeframe,open_time,open,high,low,close,volume\n\
+             BTCUSDT,1h,0,1.5,2,1,1.75,10\n\
+             BTCUSDT,1h,3600000,1.5,2,1,1.8,10\n\
+             ETHUSDT,1h,0,1.5,2,1,2,10\n"
+        );
+
+        let mut jsonl = Vec::new();
+        write(&table, Format::Jsonl, &mut jsonl).unwrap();
+        let text = String::from_utf8(jsonl).unwrap();
+        let first: Value = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+        assert_eq!(first["symbol"], "BTCUSDT");
+        assert_eq!(first["open_time"], 0);
+        assert_eq!(first["close"], 1.75);
+        assert_eq!(text.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_write_parquet() {
+        let mut out = Vec::new();
+        write(&sample(), Format::Parquet, &mut out).unwrap();
+        assert!(out.starts_with(b"PAR1"));
+        assert!(out.ends_with(b"PAR1"));
+    }
+
+    #[test]
+    fn test_missing_values() {
+        let ticker = Ticker {
+            symbol: "BTCUSDT".to_string(),
+            last_price: 1.0,
+            price_change_percent: 0.5,
+            high_price: 2.0,
+            low_price: 0.5,
+            volume: 3.0,
+            open_price: 1.0,
+            quote_volume: 4.0,
+            weighted_avg_price: None,
+            trade_count: None,
+        };
+        let table = Table::tickers(&[ticker]);
+        let mut csv = Vec::new();
+        write(&table, Format::Csv, &mut csv).unwrap();
+        assert!(String::from_utf8(csv).unwrap().ends_with(",4,,\n"));
+
+        let mut jsonl = Vec::new();
+        write(&table, Format::Jsonl, &mut jsonl).unwrap();
+        let row: Value = serde_json::from_slice(&jsonl).unwrap();
+        assert_eq!(row["weighted_avg_price"], Value::Null);
+        assert_eq!(row["trade_count"], Value::Null);
+
+        let mut parquet = Vec::new();
+        write(&table, Format::Parquet, &mut parquet).unwrap();
+        assert!(parquet.ends_with(b"PAR1"));
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("2024-01-01"), Ok(1_704_067_200_000));
+        assert_eq!(parse_time("2024-01-01T01:00"), Ok(1_704_070_800_000));
+        assert_eq!(parse_time("2024-01-01 01:00:30"), Ok(1_704_070_830_000));
+        assert_eq!(
+            parse_time("2024-01-01T02:00:00+01:00"),
+            Ok(1_704_070_800_000)
+        );
+        assert_eq!(parse_time("1704067200000"), Ok(1_704_067_200_000));
+        assert!(parse_time("yesterday").is_err());
+    }
+}
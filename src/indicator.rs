@@ -0,0 +1,646 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+/// Standard deviations between the middle and outer Bollinger Bands.
+pub const DEFAULT_BAND_WIDTH: f64 = 2.0;
+
+/// A price overlay computed from candle closes.
+///
+/// Parsed from `sma20`, `ema50`, `bb20` or `bb20:2.5` (period and band width).
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Indicator {
+    Sma(usize),
+    Ema(usize),
+    Bollinger { period: usize, width: f64 },
+}
+
+impl Indicator {
+    pub fn period(&self) -> usize {
+        match *self {
+            Indicator::Sma(period) | Indicator::Ema(period) => period,
+            Indicator::Bollinger { period, .. } => period,
+        }
+    }
+}
+
+impl FromStr for Indicator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase();
+        let bad = || {
+            format!(
+                "invalid indicator '{}': expected smaN, emaN or bbN[:WIDTH]",
+                s
+            )
+        };
+        let split = lower.find(|ch: char| ch.is_ascii_digit()).ok_or_else(bad)?;
+        let (kind, rest) = lower.split_at(split);
+        let (period, width) = match rest.split_once(':') {
+            Some((period, width)) => (period, Some(width)),
+            None => (rest, None),
+        };
+        let period = period
+            .parse::<usize>()
+            .ok()
+            .filter(|&p| p > 1)
+            .ok_or_else(bad)?;
+
+        match (kind, width) {
+            ("sma", None) => Ok(Indicator::Sma(period)),
+            ("ema", None) => Ok(Indicator::Ema(period)),
+            ("bb", width) => {
+                let width = match width {
+                    Some(w) => w
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|w| w.is_finite() && *w > 0.0)
+                        .ok_or_else(bad)?,
+                    None => DEFAULT_BAND_WIDTH,
+                };
+                Ok(Indicator::Bollinger { period, width })
+            }
+            _ => Err(bad()),
+        }
+    }
+}
+
+impl TryFrom<String> for Indicator {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Indicator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Indicator::Sma(period) => write!(f, "SMA{}", period),
+            Indicator::Ema(period) => write!(f, "EMA{}", period),
+            Indicator::Bollinger { period, width } if width == DEFAULT_BAND_WIDTH => {
+                write!(f, "BB{}", period)
+            }
+            Indicator::Bollinger { period, width } => write!(f, "BB{}:{}", period, width),
+        }
+    }
+}
+
+/// One indicator value; moving averages have `upper == lower == mid`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Band {
+    pub mid: f64,
+    pub upper: f64,
+    pub lower: f64,
+}
+
+impl Band {
+    fn line(value: f64) -> Self {
+        Self {
+            mid: value,
+            upper: value,
+            lower: value,
+        }
+    }
+}
+
+/// Simple moving average of the last `period` values, if there are enough.
+pub fn sma(values: impl DoubleEndedIterator<Item = f64>, period: usize) -> Option<f64> {
+    let window: Vec<f64> = values.rev().take(period).collect();
+    (window.len() == period).then(|| window.iter().sum::<f64>() / period as f64)
+}
+
+/// An indicator kept in step with a candle history: `push` for a new candle,
+/// `update_last` when the open candle changes, `pop_front` when the oldest
+/// one is dropped. Only the newest value is ever recomputed.
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub indicator: Indicator,
+    /// Closes of the last `period` candles, newest last
+    window: VecDeque<f64>,
+    /// Running average for `Indicator::Ema`, which needs more than the window
+    ema: Option<Incremental<EmaStep>>,
+    values: VecDeque<Option<Band>>,
+}
+
+impl Series {
+    pub fn new(indicator: Indicator) -> Self {
+        Self {
+            indicator,
+            window: VecDeque::with_capacity(indicator.period() + 1),
+            ema: match indicator {
+                Indicator::Ema(period) => Some(Incremental::new(EmaStep::new(period))),
+                _ => None,
+            },
+            values: VecDeque::new(),
+        }
+    }
+
+    /// Values aligned with the history; `None` until the period fills up.
+    pub fn values(&self) -> &VecDeque<Option<Band>> {
+        &self.values
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new(self.indicator);
+    }
+
+    pub fn push(&mut self, close: f64) {
+        self.window.push_back(close);
+        if self.window.len() > self.indicator.period() {
+            self.window.pop_front();
+        }
+        if let Some(ema) = &mut self.ema {
+            ema.push(close);
+        }
+        let value = self.compute();
+        self.values.push_back(value);
+    }
+
+    pub fn update_last(&mut self, close: f64) {
+        let Some(last) = self.window.back_mut() else {
+            return self.push(close);
+        };
+        *last = close;
+        if let Some(ema) = &mut self.ema {
+            ema.update_last(close);
+        }
+        let value = self.compute();
+        if let Some(slot) = self.values.back_mut() {
+            *slot = value;
+        }
+    }
+
+    pub fn pop_front(&mut self) {
+        self.values.pop_front();
+        if let Some(ema) = &mut self.ema {
+            ema.pop_front();
+        }
+    }
+
+    fn compute(&self) -> Option<Band> {
+        let closes = self.window.iter().copied();
+        match self.indicator {
+            Indicator::Sma(period) => sma(closes, period).map(Band::line),
+            Indicator::Ema(_) => self.ema.as_ref()?.last().map(Band::line),
+            Indicator::Bollinger { period, width } => {
+                let mid = sma(closes, period)?;
+                let variance =
+                    self.window.iter().map(|c| (c - mid).powi(2)).sum::<f64>() / period as f64;
+                let spread = width * variance.sqrt();
+                Some(Band {
+                    mid,
+                    upper: mid + spread,
+                    lower: mid - spread,
+                })
+            }
+        }
+    }
+}
+
+/// Which oscillator a chart shows in its sub-pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Oscillator {
+    Rsi,
+    Macd,
+}
+
+pub const RSI_PERIOD: usize = 14;
+pub const MACD_FAST: usize = 12;
+pub const MACD_SLOW: usize = 26;
+pub const MACD_SIGNAL: usize = 9;
+
+/// A calculation that advances one close at a time. Steps are pure so the
+/// newest value can be recomputed from the previous state when the open
+/// candle changes.
+pub trait Step: Copy {
+    type Output: Copy;
+
+    fn step(self, close: f64) -> (Self, Option<Self::Output>);
+}
+
+/// Values of a `Step` kept aligned with a candle history, with the same
+/// `push` / `update_last` / `pop_front` contract as `Series`.
+#[derive(Debug, Clone)]
+pub struct Incremental<S: Step> {
+    initial: S,
+    /// State before the newest close
+    committed: S,
+    /// State including the newest close
+    latest: S,
+    values: VecDeque<Option<S::Output>>,
+}
+
+impl<S: Step> Incremental<S> {
+    pub fn new(initial: S) -> Self {
+        Self {
+            initial,
+            committed: initial,
+            latest: initial,
+            values: VecDeque::new(),
+        }
+    }
+
+    pub fn values(&self) -> &VecDeque<Option<S::Output>> {
+        &self.values
+    }
+
+    pub fn last(&self) -> Option<S::Output> {
+        self.values.back().copied().flatten()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new(self.initial);
+    }
+
+    pub fn push(&mut self, close: f64) {
+        self.committed = self.latest;
+        let (state, value) = self.committed.step(close);
+        self.latest = state;
+        self.values.push_back(value);
+    }
+
+    pub fn update_last(&mut self, close: f64) {
+        if self.values.is_empty() {
+            return self.push(close);
+        }
+        let (state, value) = self.committed.step(close);
+        self.latest = state;
+        if let Some(slot) = self.values.back_mut() {
+            *slot = value;
+        }
+    }
+
+    pub fn pop_front(&mut self) {
+        self.values.pop_front();
+    }
+}
+
+/// Exponential moving average seeded with the SMA of its first window.
+#[derive(Debug, Clone, Copy)]
+pub struct EmaStep {
+    period: usize,
+    count: usize,
+    sum: f64,
+    value: Option<f64>,
+}
+
+impl EmaStep {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            count: 0,
+            sum: 0.0,
+            value: None,
+        }
+    }
+}
+
+impl Step for EmaStep {
+    type Output = f64;
+
+    fn step(mut self, close: f64) -> (Self, Option<f64>) {
+        self.count += 1;
+        if self.count <= self.period {
+            self.sum += close;
+            if self.count == self.period {
+                self.value = Some(self.sum / self.period as f64);
+            }
+        } else if let Some(prev) = self.value {
+            let alpha = 2.0 / (self.period as f64 + 1.0);
+            self.value = Some(alpha * close + (1.0 - alpha) * prev);
+        }
+        (self, self.value)
+    }
+}
+
+/// Wilder's relative strength index, 0 to 100.
+#[derive(Debug, Clone, Copy)]
+pub struct RsiStep {
+    period: usize,
+    prev_close: Option<f64>,
+    changes: usize,
+    avg_gain: f64,
+    avg_loss: f64,
+}
+
+impl RsiStep {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            prev_close: None,
+            changes: 0,
+            avg_gain: 0.0,
+            avg_loss: 0.0,
+        }
+    }
+}
+
+impl Step for RsiStep {
+    type Output = f64;
+
+    fn step(mut self, close: f64) -> (Self, Option<f64>) {
+        let Some(prev) = self.prev_close.replace(close) else {
+            return (self, None);
+        };
+        let change = close - prev;
+        let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+        let period = self.period as f64;
+        self.changes += 1;
+        if self.changes <= self.period {
+            // Plain average over the first window, Wilder smoothing after
+            self.avg_gain += gain / period;
+            self.avg_loss += loss / period;
+            if self.changes < self.period {
+                return (self, None);
+            }
+        } else {
+            self.avg_gain = (self.avg_gain * (period - 1.0) + gain) / period;
+            self.avg_loss = (self.avg_loss * (period - 1.0) + loss) / period;
+        }
+        let rsi = if self.avg_loss == 0.0 {
+            100.0
+        } else {
+            100.0 - 100.0 / (1.0 + self.avg_gain / self.avg_loss)
+        };
+        (self, Some(rsi))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacdPoint {
+    pub macd: f64,
+    /// EMA of the MACD line, once enough of it exists
+    pub signal: Option<f64>,
+}
+
+impl MacdPoint {
+    pub fn histogram(&self) -> Option<f64> {
+        self.signal.map(|signal| self.macd - signal)
+    }
+}
+
+/// Fast EMA minus slow EMA, with a signal EMA over the difference.
+#[derive(Debug, Clone, Copy)]
+pub struct MacdStep {
+    fast: EmaStep,
+    slow: EmaStep,
+    signal: EmaStep,
+}
+
+impl MacdStep {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        Self {
+            fast: EmaStep::new(fast),
+            slow: EmaStep::new(slow),
+            signal: EmaStep::new(signal),
+        }
+    }
+}
+
+impl Default for MacdStep {
+    fn default() -> Self {
+        Self::new(MACD_FAST, MACD_SLOW, MACD_SIGNAL)
+    }
+}
+
+impl Step for MacdStep {
+    type Output = MacdPoint;
+
+    fn step(mut self, close: f64) -> (Self, Option<MacdPoint>) {
+        let (fast, fast_value) = self.fast.step(close);
+        let (slow, slow_value) = self.slow.step(close);
+        self.fast = fast;
+        self.slow = slow;
+        let (Some(fast_value), Some(slow_value)) = (fast_value, slow_value) else {
+            return (self, None);
+        };
+        let macd = fast_value - slow_value;
+        let (signal, signal_value) = self.signal.step(macd);
+        self.signal = signal;
+        (
+            self,
+            Some(MacdPoint {
+                macd,
+                signal: signal_value,
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// StockCharts' EMA worked example.
+    const CLOSES: [f64; 15] = [
+        22.27, 22.19, 22.08, 22.17, 22.18, 22.13, 22.23, 22.43, 22.24, 22.29, 22.15, 22.39, 22.38,
+        22.61, 23.36,
+    ];
+
+    fn mids(series: &Series) -> Vec<Option<f64>> {
+        series
+            .values()
+            .iter()
+            .map(|v| v.map(|b| (b.mid * 10_000.0).round() / 10_000.0))
+            .collect()
+    }
+
+    fn series_of(indicator: Indicator, closes: &[f64]) -> Series {
+        let mut series = Series::new(indicator);
+        for &close in closes {
+            series.push(close);
+        }
+        series
+    }
+
+    #[test]
+    fn test_parse_indicators() {
+        assert_eq!("sma20".parse(), Ok(Indicator::Sma(20)));
+        assert_eq!("EMA50".parse(), Ok(Indicator::Ema(50)));
+        assert_eq!(
+            "bb20".parse(),
+            Ok(Indicator::Bollinger {
+                period: 20,
+                width: 2.0
+            })
+        );
+        assert_eq!(
+            "bb10:2.5".parse::<Indicator>().map(|i| i.to_string()),
+            Ok("BB10:2.5".to_string())
+        );
+        assert!("sma".parse::<Indicator>().is_err());
+        assert!("sma1".parse::<Indicator>().is_err());
+        assert!("sma20:2".parse::<Indicator>().is_err());
+        assert!("wma20".parse::<Indicator>().is_err());
+        assert!("bb20:-1".parse::<Indicator>().is_err());
+    }
+
+    #[test]
+    fn test_sma_reference() {
+        let series = series_of(Indicator::Sma(10), &CLOSES);
+        let values = mids(&series);
+        assert_eq!(values[..9], [None; 9]);
+        assert_eq!(values[9], Some(22.221));
+        assert_eq!(values[10], Some(22.209));
+        assert_eq!(values[14], Some(22.421));
+    }
+
+    #[test]
+    fn test_ema_reference() {
+        let series = series_of(Indicator::Ema(10), &CLOSES);
+        assert_eq!(
+            mids(&series)[9..],
+            [
+                Some(22.221),
+                Some(22.2081),
+                Some(22.2412),
+                Some(22.2664),
+                Some(22.3289),
+                Some(22.5164)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bollinger_reference() {
+        // Mean 5, population standard deviation 2
+        let series = series_of(
+            Indicator::Bollinger {
+                period: 8,
+                width: 2.0,
+            },
+            &[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0],
+        );
+        assert_eq!(
+            series.values().back(),
+            Some(&Some(Band {
+                mid: 5.0,
+                upper: 9.0,
+                lower: 1.0
+            }))
+        );
+    }
+
+    #[test]
+    fn test_incremental_updates_match_batch() {
+        for indicator in [
+            Indicator::Sma(4),
+            Indicator::Ema(4),
+            Indicator::Bollinger {
+                period: 4,
+                width: 2.0,
+            },
+        ] {
+            // Each close arrives as a provisional value first, then is revised
+            let mut live = Series::new(indicator);
+            for &close in &CLOSES {
+                live.push(close - 1.0);
+                live.update_last(close + 0.5);
+                live.update_last(close);
+            }
+            let batch = series_of(indicator, &CLOSES);
+            for (a, b) in live.values().iter().zip(batch.values()) {
+                match (a, b) {
+                    (Some(a), Some(b)) => {
+                        assert!((a.mid - b.mid).abs() < 1e-9, "{}", indicator);
+                        assert!((a.upper - b.upper).abs() < 1e-9, "{}", indicator);
+                    }
+                    (a, b) => assert_eq!(a, b),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pop_front_keeps_alignment() {
+        let mut series = series_of(Indicator::Sma(3), &CLOSES[..5]);
+        series.pop_front();
+        series.push(CLOSES[5]);
+        assert_eq!(series.values().len(), 5);
+        assert_eq!(mids(&series).last(), Some(&Some(22.16)));
+    }
+
+    /// StockCharts' RSI worked example.
+    const RSI_CLOSES: [f64; 20] = [
+        44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03, 45.61,
+        46.28, 46.28, 46.00, 46.03, 46.41, 46.22, 45.64,
+    ];
+
+    fn round(value: f64, places: i32) -> f64 {
+        let scale = 10f64.powi(places);
+        (value * scale).round() / scale
+    }
+
+    fn incremental_of<S: Step>(initial: S, closes: &[f64]) -> Incremental<S> {
+        let mut series = Incremental::new(initial);
+        for &close in closes {
+            series.push(close);
+        }
+        series
+    }
+
+    #[test]
+    fn test_rsi_reference() {
+        let rsi = incremental_of(RsiStep::new(14), &RSI_CLOSES);
+        let values: Vec<Option<f64>> = rsi
+            .values()
+            .iter()
+            .map(|v| v.map(|r| round(r, 2)))
+            .collect();
+        assert_eq!(values[..14], [None; 14]);
+        assert_eq!(
+            values[14..],
+            [
+                Some(70.46),
+                Some(66.25),
+                Some(66.48),
+                Some(69.35),
+                Some(66.29),
+                Some(57.92)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rsi_without_losses_is_100() {
+        let rsi = incremental_of(RsiStep::new(3), &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(rsi.last(), Some(100.0));
+    }
+
+    #[test]
+    fn test_macd_reference() {
+        let macd = incremental_of(MacdStep::new(3, 6, 4), &RSI_CLOSES);
+        let point =
+            |i: usize| macd.values()[i].map(|p| (round(p.macd, 4), p.signal.map(|s| round(s, 4))));
+        assert_eq!(point(4), None);
+        assert_eq!(point(5), Some((0.2479, None)));
+        assert_eq!(point(8), Some((0.4138, Some(0.3328))));
+        assert_eq!(point(19), Some((-0.0635, Some(0.0417))));
+        let last = macd.last().unwrap();
+        assert_eq!(last.histogram().map(|h| round(h, 4)), Some(-0.1053));
+    }
+
+    #[test]
+    fn test_incremental_revisions_match_batch() {
+        let mut live = Incremental::new(MacdStep::new(3, 6, 4));
+        let mut rsi = Incremental::new(RsiStep::new(5));
+        for &close in &RSI_CLOSES {
+            live.push(close + 1.0);
+            live.update_last(close);
+            rsi.push(close - 1.0);
+            rsi.update_last(close);
+        }
+        let batch = incremental_of(MacdStep::new(3, 6, 4), &RSI_CLOSES);
+        assert_eq!(live.values(), batch.values());
+        assert_eq!(
+            rsi.values(),
+            incremental_of(RsiStep::new(5), &RSI_CLOSES).values()
+        );
+    }
+}
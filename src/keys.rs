@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use serde::Deserialize;
+
+/// Everything a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Back,
+    Refresh,
+    PrevPage,
+    NextPage,
+    SelectPrev,
+    SelectNext,
+    ToggleCandles,
+    ToggleIndicators,
+    CycleOscillator,
+    EditHolding,
+    ToggleTable,
+    CycleSort,
+    ReverseSort,
+    OpenSelected,
+    AddCoin,
+    RemoveCoin,
+    MoveCoinUp,
+    MoveCoinDown,
+    ToggleTape,
+    ToggleTapeFilter,
+    NextTimeframe,
+    PrevTimeframe,
+    ToggleAlertLog,
+    ScrollAlertsUp,
+    ScrollAlertsDown,
+}
+
+const DEFAULT_BINDINGS: &[(Action, &[&str])] = &[
+    (Action::Quit, &["q"]),
+    (Action::Back, &["esc"]),
+    (Action::Refresh, &["r"]),
+    (Action::PrevPage, &["left", "h"]),
+    (Action::NextPage, &["right", "l"]),
+    (Action::SelectPrev, &["up", "k"]),
+    (Action::SelectNext, &["down", "j"]),
+    (Action::ToggleCandles, &["c"]),
+    (Action::ToggleIndicators, &["i"]),
+    (Action::CycleOscillator, &["o"]),
+    (Action::EditHolding, &["e"]),
+    (Action::ToggleTable, &["v"]),
+    (Action::CycleSort, &["s"]),
+    (Action::ReverseSort, &["S"]),
+    (Action::OpenSelected, &["enter"]),
+    (Action::AddCoin, &["+", "insert"]),
+    (Action::RemoveCoin, &["-", "delete"]),
+    (Action::MoveCoinUp, &["K"]),
+    (Action::MoveCoinDown, &["J"]),
+    (Action::ToggleTape, &["p"]),
+    (Action::ToggleTapeFilter, &["f"]),
+    (Action::NextTimeframe, &["t"]),
+    (Action::PrevTimeframe, &["T"]),
+    (Action::ToggleAlertLog, &["a"]),
+    (Action::ScrollAlertsUp, &["pageup"]),
+    (Action::ScrollAlertsDown, &["pagedown"]),
+];
+
+/// A single key as written in the config file: a character (`q`, `T`, `?`)
+/// or a named key (`esc`, `enter`, `pageup`, `f5`, ...).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Key(KeyCode);
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        if let (Some(ch), None) = (chars.next(), chars.next()) {
+            return Ok(Key(KeyCode::Char(ch)));
+        }
+
+        let code = match s.to_lowercase().as_str() {
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            other => match other.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(format!("unknown key '{}'", s)),
+            },
+        };
+        Ok(Key(code))
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Key {
+    /// Short label for the status bar hints.
+    fn label(self) -> String {
+        match self.0 {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(ch) => ch.to_uppercase().to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            other => format!("{:?}", other),
+        }
+    }
+}
+
+/// Maps key presses to actions, starting from the defaults and applying
+/// per-action overrides from the config file.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyCode, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = DEFAULT_BINDINGS
+            .iter()
+            .flat_map(|(action, keys)| {
+                keys.iter().map(move |k| {
+                    let key: Key = k.parse().expect("default bindings are valid");
+                    (key.0, *action)
+                })
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Replaces the bindings of each listed action; a key claimed by an
+    /// override is taken away from whichever action had it by default.
+    pub fn with_overrides(overrides: &HashMap<Action, Vec<Key>>) -> Self {
+        let mut keymap = Self::default();
+        for (action, keys) in overrides {
+            keymap.bindings.retain(|_, bound| bound != action);
+            for key in keys {
+                keymap.bindings.insert(key.0, *action);
+            }
+        }
+        keymap
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&key.code).copied()
+    }
+
+    /// Label of the first key bound to `action`, in default-binding order.
+    pub fn label(&self, action: Action) -> String {
+        let defaults = DEFAULT_BINDINGS
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, keys)| *keys)
+            .unwrap_or_default();
+        let default_key = defaults
+            .iter()
+            .filter_map(|k| k.parse::<Key>().ok())
+            .find(|k| self.bindings.get(&k.0) == Some(&action));
+        let mut bound: Vec<Key> = self
+            .bindings
+            .iter()
+            .filter(|(_, a)| **a == action)
+            .map(|(code, _)| Key(*code))
+            .collect();
+        bound.sort_by_key(|k| k.label());
+
+        default_key
+            .or_else(|| bound.first().copied())
+            .map(Key::label)
+            .unwrap_or_else(|| "-".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!("q".parse(), Ok(Key(KeyCode::Char('q'))));
+        assert_eq!("T".parse(), Ok(Key(KeyCode::Char('T'))));
+        assert_eq!("Esc".parse(), Ok(Key(KeyCode::Esc)));
+        assert_eq!("pagedown".parse(), Ok(Key(KeyCode::PageDown)));
+        assert_eq!("f5".parse(), Ok(Key(KeyCode::F(5))));
+        assert!("f13".parse::<Key>().is_err());
+        assert!("hyper".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn test_default_keymap() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('q'))),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('T'))),
+            Some(Action::PrevTimeframe)
+        );
+        assert_eq!(keymap.action(&press(KeyCode::Char('z'))), None);
+        assert_eq!(keymap.label(Action::SelectPrev), "↑");
+    }
+
+    #[test]
+    fn test_overrides_replace_and_steal_keys() {
+        let overrides = HashMap::from([
+            (Action::Quit, vec![Key(KeyCode::Char('x'))]),
+            (Action::ToggleCandles, vec![Key(KeyCode::Char('r'))]),
+        ]);
+        let keymap = Keymap::with_overrides(&overrides);
+
+        assert_eq!(keymap.action(&press(KeyCode::Char('q'))), None);
+        assert_eq!(keymap.action(&press(KeyCode::Esc)), Some(Action::Back));
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('x'))),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('r'))),
+            Some(Action::ToggleCandles)
+        );
+        assert_eq!(keymap.label(Action::Quit), "X");
+        assert_eq!(keymap.label(Action::Refresh), "-");
+    }
+}
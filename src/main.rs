@@ -1,64 +1,478 @@
+mod alert;
 mod api;
 mod app;
+mod book;
+mod config;
 mod event;
+mod export;
+mod indicator;
+mod keys;
+mod metrics;
+mod portfolio;
+mod quote;
+mod remote;
+mod server;
+mod source;
+mod statusline;
+mod store;
+mod stream;
+mod symbols;
 mod ui;
 
-use std::io::{self, stdout};
+use std::fs::File;
+use std::io::{self, stdout, BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
 use crossterm::{
-    event::KeyCode,
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::prelude::*;
+use tokio::sync::watch;
 
+use alert::{AlertEngine, AlertRule};
 use api::BinanceClient;
-use app::App;
+use app::{App, ChartDefaults, SavedWatchlist, View, MAX_COINS};
 use event::{AppEvent, EventHandler};
-
-const MAX_COINS: usize = 20;
+use indicator::Indicator;
+use keys::Action;
+use metrics::Metrics;
+use portfolio::Portfolio;
+use remote::RemoteSource;
+use source::{MarketDataSource, Pair, Timeframe};
+use store::Store;
+use symbols::SymbolRegistry;
 
 #[derive(Parser, Debug)]
 #[command(name = "cryptowatcher")]
 #[command(about = "Real-time cryptocurrency price watcher with TUI charts")]
 struct Args {
-    #[arg(short, long, default_value = "BTC,ETH", value_delimiter = ',')]
+    /// Config file [default: ~/.config/cryptowatcher/config.toml]
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Coins or pairs to watch: BTC,ETH/BTC,SOL/FDUSD
+    #[arg(short, long, value_delimiter = ',')]
+    coins: Option<Vec<String>>,
+
+    /// Quote asset for coins given without one [default: USDT]
+    #[arg(short, long)]
+    quote: Option<String>,
+
+    #[arg(short, long)]
+    interval: Option<u64>,
+
+    /// Chart timeframe: 1m, 5m, 15m, 1h, 4h, 1d or 1w
+    #[arg(short, long)]
+    timeframe: Option<Timeframe>,
+
+    /// Stream live prices over WebSocket, polling only while the socket is down
+    #[arg(short, long)]
+    stream: bool,
+
+    /// Chart overlay, repeatable: sma20, ema50, bb20 or bb20:2.5
+    #[arg(long = "indicator", value_name = "SPEC")]
+    indicators: Vec<Indicator>,
+
+    /// Alert rule, repeatable: BTC>70000, BTC<60000, ETH%5/1h or SOL~sma20
+    #[arg(short, long = "alert", value_name = "RULE")]
+    alerts: Vec<AlertRule>,
+
+    /// Minimum seconds between repeats of the same alert
+    #[arg(long)]
+    alert_cooldown: Option<u64>,
+
+    /// Holdings file [default: ~/.config/cryptowatcher/holdings.toml]
+    #[arg(long, value_name = "PATH")]
+    holdings: Option<PathBuf>,
+
+    /// Serve Prometheus metrics from http://ADDR/metrics, e.g. 127.0.0.1:9184
+    #[arg(long, value_name = "ADDR")]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Keep prices (and metrics or stored history) updated without the TUI
+    #[arg(long)]
+    headless: bool,
+
+    /// Read prices from a `cryptowatcher serve` instance, e.g. 10.0.0.5:9185
+    #[arg(long, value_name = "URL")]
+    server: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Write klines, or current 24h tickers, to CSV, JSON Lines or Parquet
+    Export(ExportArgs),
+    /// Print current prices and 24h stats, once or on every refresh
+    Quote(QuoteArgs),
+    /// Print a compact ticker line for tmux, i3bar, swaybar or waybar
+    Statusline(StatuslineArgs),
+    /// Collect prices without the TUI and share them over a local HTTP API
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = server::DEFAULT_ADDR)]
+        addr: SocketAddr,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct ExportArgs {
+    /// Coins or pairs to export: BTC,ETH/BTC,SOL/FDUSD
+    #[arg(short, long, value_delimiter = ',', required = true)]
+    coins: Vec<String>,
+
+    /// Quote asset for coins given without one [default: from the config]
+    #[arg(short, long)]
+    quote: Option<String>,
+
+    /// Kline timeframe [default: from the config]
+    #[arg(short, long)]
+    timeframe: Option<Timeframe>,
+
+    /// First kline to include, in UTC: 2024-01-31, 2024-01-31T12:00 or epoch ms
+    #[arg(long, value_parser = export::parse_time, required_unless_present = "tickers")]
+    from: Option<i64>,
+
+    /// Export klines opening before this time [default: now]
+    #[arg(long, value_parser = export::parse_time)]
+    to: Option<i64>,
+
+    /// Export the current 24h tickers instead of klines
+    #[arg(long, conflicts_with_all = ["from", "to", "timeframe"])]
+    tickers: bool,
+
+    /// Output format [default: from the output file's extension, else csv]
+    #[arg(short, long, value_enum)]
+    format: Option<export::Format>,
+
+    /// Output file; stdout when omitted or "-"
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct QuoteArgs {
+    /// Coins or pairs to quote: BTC,ETH/BTC,SOL/FDUSD [default: the watchlist]
+    #[arg(value_delimiter = ',')]
     coins: Vec<String>,
 
-    #[arg(short, long, default_value = "60")]
-    interval: u64,
+    /// Quote asset for coins given without one [default: from the config]
+    #[arg(short, long)]
+    quote: Option<String>,
+
+    /// Print JSON instead of a table
+    #[arg(long)]
+    json: bool,
+
+    /// Keep printing, one line per refresh
+    #[arg(short, long)]
+    watch: bool,
+
+    /// Seconds between refreshes with --watch [default: from the config]
+    #[arg(short, long, requires = "watch")]
+    interval: Option<u64>,
+}
+
+#[derive(clap::Args, Debug)]
+struct StatuslineArgs {
+    /// Coins or pairs to show: BTC,ETH/BTC,SOL/FDUSD [default: the watchlist]
+    #[arg(value_delimiter = ',')]
+    coins: Vec<String>,
+
+    /// Quote asset for coins given without one [default: from the config]
+    #[arg(short, long)]
+    quote: Option<String>,
+
+    /// Output for the bar reading it
+    #[arg(short, long, value_enum, default_value_t = statusline::Format::Plain)]
+    format: statusline::Format,
+
+    /// Text per coin, e.g. "{base} {price_short} {arrow}{change}%" [default: from the config]
+    #[arg(long)]
+    template: Option<statusline::Template>,
+
+    /// Seconds between updates [default: from the config]
+    #[arg(short, long)]
+    interval: Option<u64>,
+
+    /// Print one update and exit, for bars that run a command per refresh
+    #[arg(long, conflicts_with = "interval")]
+    once: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Write a commented default config file
+    Init {
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print where the config file is read from
+    Path,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
 
-    let mut symbols: Vec<String> = Vec::new();
-    for coin in args.coins.iter().take(MAX_COINS) {
-        let trimmed = coin.trim().to_uppercase();
-        if trimmed.chars().all(|ch| ch.is_alphanumeric()) {
-            symbols.push(format!("{}USDT", trimmed));
+    if let Some(Command::Config { command }) = &args.command {
+        match command {
+            ConfigCommand::Init { force } => {
+                let path = config::init(args.config.as_deref(), *force)?;
+                println!("Wrote {}", path.display());
+            }
+            ConfigCommand::Path => match args.config.clone().or_else(config::default_path) {
+                Some(path) => println!("{}", path.display()),
+                None => anyhow::bail!("cannot determine the config directory"),
+            },
+        }
+        return Ok(());
+    }
+
+    let config = match config::load(args.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(Command::Export(export_args)) = &args.command {
+        if let Err(e) = run_export(export_args, &config).await {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Statusline(statusline_args)) = &args.command {
+        if let Err(e) = run_statusline(statusline_args, &config).await {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Quote(quote_args)) = &args.command {
+        if let Err(e) = run_quote(quote_args, &config).await {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let coins = args.coins.as_ref().unwrap_or(&config.watchlist);
+    let quote = args.quote.as_deref().unwrap_or(&config.quote);
+    let mut pairs = parse_pairs(coins, quote, BadPair::Skip)?;
+
+    let holdings_path = args
+        .holdings
+        .clone()
+        .or_else(|| config.holdings.clone())
+        .or_else(portfolio::default_path);
+    let portfolio = match holdings_path.as_deref().map(Portfolio::load) {
+        Some(Ok(portfolio)) => portfolio.with_default_quote(quote),
+        Some(Err(e)) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        None => Portfolio::default(),
+    };
+    // Held coins are always watched so they have a price
+    for pair in portfolio.pairs() {
+        if !pairs.contains(pair) && pairs.len() < MAX_COINS {
+            pairs.push(pair.clone());
+        }
+    }
+    let rules: Vec<AlertRule> = config
+        .alerts
+        .rules
+        .iter()
+        .cloned()
+        .chain(args.alerts)
+        .map(|rule| rule.with_default_quote(quote))
+        .collect();
+    // Rules only fire for watched coins, so their coins are watched too
+    let mut unwatched: Vec<&Pair> = Vec::new();
+    for rule in &rules {
+        if pairs.contains(&rule.pair) || unwatched.contains(&&rule.pair) {
+            continue;
+        }
+        if pairs.len() < MAX_COINS {
+            pairs.push(rule.pair.clone());
         } else {
-            eprintln!("Warning: Skipping invalid coin symbol: {}", coin);
+            eprintln!(
+                "Warning: Alerts for {} will not fire, at most {} coins can be watched",
+                rule.pair, MAX_COINS
+            );
+            unwatched.push(&rule.pair);
         }
     }
 
-    if symbols.is_empty() {
-        eprintln!("Error: No valid coin symbols provided");
+    let metrics_addr = args.metrics_addr.or(config.metrics_addr);
+    let metrics = metrics_addr.map(|_| Arc::new(Metrics::new()));
+    // A collector always reads from the exchange; a shared config's `server`
+    // would otherwise point it at another collector, or at itself
+    let serving = matches!(args.command, Some(Command::Serve { .. }));
+    if serving && args.server.is_some() {
+        eprintln!("Error: --server cannot be used with serve");
         std::process::exit(1);
     }
+    let server_url = args
+        .server
+        .clone()
+        .or_else(|| config.server.clone())
+        .filter(|_| !serving);
+    let source: Arc<dyn MarketDataSource> = match &server_url {
+        Some(url) => Arc::new(RemoteSource::new(url)?),
+        None => {
+            let mut client = BinanceClient::new()?;
+            if let Some(metrics) = &metrics {
+                client = client.with_metrics(metrics.clone());
+            }
+            Arc::new(client)
+        }
+    };
+    let registry = match validate_pairs(&mut pairs, &*source, BadPair::Skip).await {
+        Ok(registry) => registry,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let tick_rate = Duration::from_secs(args.interval.unwrap_or(config.interval).max(1));
+    let mut stream = args.stream || config.stream;
+    if stream && server_url.is_some() {
+        // The server streams from the exchange; clients poll it
+        eprintln!("Warning: Not streaming while reading from a server");
+        stream = false;
+    }
 
-    let tick_rate = Duration::from_secs(args.interval);
+    let mut app = App::new(pairs, args.timeframe.unwrap_or(config.timeframe));
+    let cooldown = args
+        .alert_cooldown
+        .map(Duration::from_secs)
+        .unwrap_or(config.alerts.cooldown);
+    app.alerts = AlertEngine::new(rules, cooldown);
+    app.theme = config.theme.resolve();
+    app.keymap = config.keymap();
+    app.charts_per_page = config.layout.charts_per_page;
+    app.show_alert_log = config.layout.alert_log;
+    app.portfolio = portfolio;
+    app.holdings_path = holdings_path;
+    app.quote = quote.to_uppercase();
+    if config.persist_watchlist {
+        app.watchlist_path = args.config.clone().or_else(config::default_path);
+        app.saved_watchlist = SavedWatchlist::new(config.watchlist.clone(), &config.quote);
+    }
+    if args.indicators.is_empty() {
+        app.set_indicators(&config.indicators);
+    } else {
+        app.set_indicators(&args.indicators);
+    }
+    app.chart_defaults = ChartDefaults {
+        candles: config.layout.candles,
+        indicators: config.layout.indicators,
+        volume: config.layout.volume,
+        oscillator: config.layout.oscillator,
+        tape: config.tape.show,
+    };
+    app.large_trade = config.tape.large_trade;
+    for coin in &mut app.coins {
+        app.chart_defaults.apply(coin);
+        if let Some(info) = registry.get(&coin.pair) {
+            coin.price_decimals = info.price_decimals();
+        }
+    }
+    app.registry = registry;
+    app.refresh_tapes();
+
+    if config.storage.enabled {
+        let path = config
+            .storage
+            .path
+            .clone()
+            .or_else(store::default_path)
+            .ok_or_else(|| anyhow::anyhow!("cannot determine the data directory"))?;
+        let store = Store::open(&path).and_then(|store| {
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            store.prune(
+                &config.storage.retention,
+                config.storage.tick_retention,
+                now_ms,
+            )?;
+            Ok(store)
+        });
+        match store {
+            Ok(store) => app.store = Some(store),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(1);
+            }
+        }
+        app.set_history_len(config.storage.history as usize);
+    }
+
+    if let (Some(addr), Some(metrics)) = (metrics_addr, &metrics) {
+        if let Err(e) = metrics::serve(addr, metrics.clone()).await {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(Command::Serve { addr }) = &args.command {
+        let hub = Arc::new(server::Hub::new(source.clone()));
+        match server::serve(*addr, hub.clone()).await {
+            Ok(addr) => eprintln!("Serving on http://{}", addr),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(1);
+            }
+        }
+        return run_headless(
+            &*source,
+            app,
+            tick_rate,
+            stream,
+            metrics.as_deref(),
+            Some(&hub),
+        )
+        .await;
+    }
+
+    if args.headless {
+        return run_headless(&*source, app, tick_rate, stream, metrics.as_deref(), None).await;
+    }
 
     enable_raw_mode()?;
     execute!(stdout(), EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout());
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run(&mut terminal, symbols, tick_rate).await;
+    let result = run(
+        &mut terminal,
+        &*source,
+        app,
+        tick_rate,
+        stream,
+        metrics.as_deref(),
+    )
+    .await;
 
     disable_raw_mode()?;
     execute!(io::stdout(), LeaveAlternateScreen)?;
@@ -70,41 +484,328 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// What pair resolution does with a coin it can't use: the TUI and its
+/// headless modes skip it with a warning, one-shot subcommands fail.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BadPair {
+    Skip,
+    Fail,
+}
+
+impl BadPair {
+    fn handle(self, e: impl std::fmt::Display) -> Result<()> {
+        match self {
+            BadPair::Skip => {
+                eprintln!("Warning: Skipping {}", e);
+                Ok(())
+            }
+            BadPair::Fail => Err(anyhow::anyhow!("{}", e)),
+        }
+    }
+}
+
+/// Parses `coins` against `quote`, dropping repeats and keeping at most
+/// `MAX_COINS`.
+fn parse_pairs(coins: &[String], quote: &str, bad: BadPair) -> Result<Vec<Pair>> {
+    let mut pairs: Vec<Pair> = Vec::new();
+    for coin in coins {
+        match Pair::parse_with_quote(coin, quote) {
+            Ok(pair) if pairs.contains(&pair) => {}
+            Ok(pair) if pairs.len() >= MAX_COINS => bad.handle(format!(
+                "{}, at most {} coins can be watched",
+                pair, MAX_COINS
+            ))?,
+            Ok(pair) => pairs.push(pair),
+            Err(e) => bad.handle(e)?,
+        }
+    }
+    Ok(pairs)
+}
+
+/// Checks `pairs` against the exchange's symbols when those can be loaded,
+/// and fails if none are left. The registry is empty when the symbols can't
+/// be loaded, in which case nothing is validated.
+async fn validate_pairs(
+    pairs: &mut Vec<Pair>,
+    source: &dyn MarketDataSource,
+    bad: BadPair,
+) -> Result<SymbolRegistry> {
+    if pairs.is_empty() {
+        anyhow::bail!("No valid coin symbols provided");
+    }
+    let registry = match SymbolRegistry::load(source, symbols::cache_path().as_deref(), pairs).await
+    {
+        Ok(registry) => registry,
+        Err(e) => {
+            eprintln!("Warning: {:#}, not validating coins", e);
+            SymbolRegistry::default()
+        }
+    };
+    if !registry.is_empty() {
+        let mut valid = Vec::with_capacity(pairs.len());
+        for pair in pairs.drain(..) {
+            match registry.validate(&pair) {
+                Ok(_) => valid.push(pair),
+                Err(e) => bad.handle(e)?,
+            }
+        }
+        *pairs = valid;
+    }
+    if pairs.is_empty() {
+        anyhow::bail!("No valid coin symbols provided");
+    }
+    Ok(registry)
+}
+
+/// Pairs for a one-shot subcommand, failing on the first bad coin.
+async fn resolve_pairs(
+    coins: &[String],
+    quote: &str,
+    client: &BinanceClient,
+) -> Result<(Vec<Pair>, SymbolRegistry)> {
+    let mut pairs = parse_pairs(coins, quote, BadPair::Fail)?;
+    let registry = validate_pairs(&mut pairs, client, BadPair::Fail).await?;
+    Ok((pairs, registry))
+}
+
+async fn run_export(args: &ExportArgs, config: &config::Config) -> Result<()> {
+    let quote = args.quote.as_deref().unwrap_or(&config.quote);
+    let client = BinanceClient::new()?;
+    let (pairs, _) = resolve_pairs(&args.coins, quote, &client).await?;
+    let symbols: Vec<String> = pairs.iter().map(Pair::symbol).collect();
+    let table = if args.tickers {
+        export::Table::tickers(&client.tickers(&symbols).await?)
+    } else {
+        let timeframe = args.timeframe.unwrap_or(config.timeframe);
+        let from = args.from.unwrap_or_default();
+        let to = args
+            .to
+            .unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+        if from >= to {
+            anyhow::bail!("--from must be before --to");
+        }
+        let mut klines = Vec::with_capacity(symbols.len());
+        for symbol in symbols {
+            let candles = client.klines_range(&symbol, timeframe, from, to).await?;
+            eprintln!(
+                "Fetched {} {} klines for {}",
+                candles.len(),
+                timeframe,
+                symbol
+            );
+            klines.push((symbol, candles));
+        }
+        export::Table::klines(timeframe, &klines)
+    };
+
+    let path = args.output.as_deref().filter(|p| *p != Path::new("-"));
+    let format = args
+        .format
+        .unwrap_or_else(|| export::Format::from_path(path));
+    match path {
+        Some(path) => {
+            let file =
+                File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+            export::write(&table, format, BufWriter::new(file))?;
+            eprintln!("Wrote {} rows to {}", table.len(), path.display());
+        }
+        None => export::write(&table, format, BufWriter::new(io::stdout()))?,
+    }
+    Ok(())
+}
+
+async fn run_quote(args: &QuoteArgs, config: &config::Config) -> Result<()> {
+    let coins = if args.coins.is_empty() {
+        &config.watchlist
+    } else {
+        &args.coins
+    };
+    let quote = args.quote.as_deref().unwrap_or(&config.quote);
+    let client = BinanceClient::new()?;
+    let (pairs, registry) = resolve_pairs(coins, quote, &client).await?;
+    let mut coins = Vec::with_capacity(pairs.len());
+    for pair in pairs {
+        let mut coin = app::CoinData::new(pair);
+        if let Some(info) = registry.get(&coin.pair) {
+            coin.price_decimals = info.price_decimals();
+        }
+        coins.push(coin);
+    }
+
+    let watch = args
+        .watch
+        .then(|| Duration::from_secs(args.interval.unwrap_or(config.interval).max(1)));
+    quote::run(&client, &mut coins, args.json, watch).await
+}
+
+async fn run_statusline(args: &StatuslineArgs, config: &config::Config) -> Result<()> {
+    let coins = if args.coins.is_empty() {
+        &config.watchlist
+    } else {
+        &args.coins
+    };
+    let quote = args.quote.as_deref().unwrap_or(&config.quote);
+    let client = BinanceClient::new()?;
+    let (pairs, registry) = resolve_pairs(coins, quote, &client).await?;
+
+    let mut app = App::new(pairs, config.timeframe);
+    for coin in &mut app.coins {
+        if let Some(info) = registry.get(&coin.pair) {
+            coin.price_decimals = info.price_decimals();
+        }
+    }
+    let template = args
+        .template
+        .clone()
+        .unwrap_or_else(|| config.statusline.template.clone());
+    let mut statusline = statusline::Statusline::new(
+        template,
+        &config.statusline.separator,
+        args.format,
+        &config.theme.resolve(),
+    );
+    let interval =
+        (!args.once).then(|| Duration::from_secs(args.interval.unwrap_or(config.interval).max(1)));
+    statusline::run(&mut app, &client, &mut statusline, interval).await
+}
+
 async fn run<B: Backend>(
     terminal: &mut Terminal<B>,
-    symbols: Vec<String>,
+    source: &dyn MarketDataSource,
+    mut app: App,
     tick_rate: Duration,
+    stream: bool,
+    metrics: Option<&Metrics>,
 ) -> Result<()> {
-    let mut app = App::new(symbols);
-    let client = BinanceClient::new()?;
     let mut events = EventHandler::new(tick_rate);
+    let (timeframe_tx, timeframe_rx) = watch::channel(app.timeframe);
+    let (symbols_tx, symbols_rx) = watch::channel(app.symbols());
+    let (depth_tx, depth_rx) = watch::channel(None);
+    let (trades_tx, trades_rx) = watch::channel(app.tape_symbols());
+    if stream {
+        let subscriptions = stream::Subscriptions {
+            symbols: symbols_rx,
+            timeframe: timeframe_rx,
+            depth: depth_rx,
+            trades: trades_rx,
+        };
+        stream::spawn(subscriptions, events.sender());
+    }
 
     // Load last hour's history on startup
-    app.load_historical(&client).await;
-    app.fetch_prices(&client).await;
+    app.load_historical(source).await;
+    app.fetch_prices(source).await;
 
     loop {
         terminal.draw(|f| ui::render(f, &app))?;
 
         match events.next().await? {
             AppEvent::Tick => {
-                app.status_message = "Fetching...".to_string();
-                terminal.draw(|f| ui::render(f, &app))?;
-                app.fetch_prices(&client).await;
+                // The socket keeps prices fresh; polling is only the fallback
+                if !app.streaming {
+                    app.status_message = "Fetching...".to_string();
+                    terminal.draw(|f| ui::render(f, &app))?;
+                    app.fetch_prices(source).await;
+                    app.refresh_tapes();
+                }
+                if app.view == View::Detail {
+                    app.load_detail(source).await;
+                }
             }
-            AppEvent::Key(key) => match key.code {
-                KeyCode::Char('q') | KeyCode::Esc => app.quit(),
-                KeyCode::Char('r') => {
+            AppEvent::Key(key) if app.prompt.is_some() => app.handle_prompt_key(key),
+            AppEvent::Key(key) => match app.keymap.action(&key) {
+                Some(Action::Quit) => app.quit(),
+                Some(Action::Back) => app.back(),
+                Some(Action::Refresh) => {
                     app.status_message = "Refreshing...".to_string();
                     terminal.draw(|f| ui::render(f, &app))?;
-                    app.fetch_prices(&client).await;
+                    app.fetch_prices(source).await;
+                    app.refresh_tapes();
+                    if app.view == View::Detail {
+                        app.load_detail(source).await;
+                    }
                 }
-                KeyCode::Left | KeyCode::Char('h') => app.prev_page(),
-                KeyCode::Right | KeyCode::Char('l') => app.next_page(),
-                _ => {}
+                Some(Action::PrevPage) => app.prev_page(),
+                Some(Action::NextPage) => app.next_page(),
+                Some(Action::SelectPrev) => app.select_prev(),
+                Some(Action::SelectNext) => app.select_next(),
+                Some(Action::ToggleCandles) => app.toggle_candles(),
+                Some(Action::ToggleIndicators) => app.toggle_indicators(),
+                Some(Action::CycleOscillator) => app.cycle_oscillator(),
+                Some(Action::EditHolding) => app.edit_holding(),
+                Some(Action::ToggleTable) => app.toggle_table(),
+                Some(Action::CycleSort) => app.cycle_sort(),
+                Some(Action::ReverseSort) => app.reverse_sort(),
+                Some(Action::OpenSelected) => app.open_selected(),
+                Some(Action::AddCoin) => app.prompt_add_pair(),
+                Some(Action::RemoveCoin) => app.remove_selected(),
+                Some(Action::MoveCoinUp) => app.move_selected(-1),
+                Some(Action::MoveCoinDown) => app.move_selected(1),
+                Some(Action::ToggleTape) => app.toggle_tape(),
+                Some(Action::ToggleTapeFilter) => app.toggle_tape_filter(),
+                Some(action @ (Action::NextTimeframe | Action::PrevTimeframe)) => {
+                    let next = if action == Action::NextTimeframe {
+                        app.timeframe.next()
+                    } else {
+                        app.timeframe.prev()
+                    };
+                    app.set_timeframe(next);
+                    timeframe_tx.send_replace(next);
+                    terminal.draw(|f| ui::render(f, &app))?;
+                    app.load_historical(source).await;
+                }
+                Some(Action::ToggleAlertLog) => app.toggle_alert_log(),
+                Some(Action::ScrollAlertsUp) => app.scroll_alert_log(5),
+                Some(Action::ScrollAlertsDown) => app.scroll_alert_log(-5),
+                None => {}
             },
             AppEvent::Quit => app.quit(),
-            AppEvent::Resize => {}
+            AppEvent::Resize | AppEvent::Redraw => {}
+            AppEvent::Stream(event) => app.handle_stream(event),
+        }
+
+        if app.has_pending() {
+            terminal.draw(|f| ui::render(f, &app))?;
+            app.load_pending(source).await;
+        }
+        let symbols = app.symbols();
+        symbols_tx.send_if_modified(|current| {
+            let changed = *current != symbols;
+            *current = symbols;
+            changed
+        });
+        // Subscribe before the snapshot so no diff after it is missed
+        let depth = app.depth_symbol();
+        depth_tx.send_if_modified(|current| {
+            let changed = *current != depth;
+            *current = depth;
+            changed
+        });
+        let trades = app.tape_symbols();
+        trades_tx.send_if_modified(|current| {
+            let changed = *current != trades;
+            *current = trades;
+            changed
+        });
+        if app.has_pending_tapes() {
+            app.load_tapes(source).await;
+        }
+        if app.detail_stale() {
+            terminal.draw(|f| ui::render(f, &app))?;
+            app.load_detail(source).await;
+        }
+        if app.book_stale() {
+            app.load_book(source).await;
+        }
+
+        if let Some(metrics) = metrics {
+            metrics.record(&app);
+        }
+
+        if app.take_bell() {
+            let mut out = io::stdout();
+            out.write_all(b"\x07")?;
+            out.flush()?;
         }
 
         if !app.running {
@@ -114,3 +815,87 @@ async fn run<B: Backend>(
 
     Ok(())
 }
+
+/// Keeps `app` updated like the TUI does, minus charts, tapes and books,
+/// until interrupted. Status changes such as fetch errors go to stderr.
+async fn run_headless(
+    source: &dyn MarketDataSource,
+    mut app: App,
+    tick_rate: Duration,
+    stream: bool,
+    metrics: Option<&Metrics>,
+    hub: Option<&server::Hub>,
+) -> Result<()> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (_symbols_tx, symbols_rx) = watch::channel(app.symbols());
+    let (_timeframe_tx, timeframe_rx) = watch::channel(app.timeframe);
+    let (_depth_tx, depth_rx) = watch::channel(None);
+    let (_trades_tx, trades_rx) = watch::channel(Vec::new());
+    if stream {
+        let subscriptions = stream::Subscriptions {
+            symbols: symbols_rx,
+            timeframe: timeframe_rx,
+            depth: depth_rx,
+            trades: trades_rx,
+        };
+        stream::spawn(subscriptions, tx);
+    }
+
+    // Clients can see the watchlist while history loads
+    if let Some(hub) = hub {
+        hub.record(&app);
+    }
+    // History feeds indicator alerts and the store, as in the TUI
+    app.load_historical(source).await;
+    let mut ticks = tokio::time::interval(tick_rate);
+    let mut shutdown = std::pin::pin!(shutdown_signal());
+    let mut status = String::new();
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {
+                if !app.streaming {
+                    app.fetch_prices(source).await;
+                }
+            }
+            Some(AppEvent::Stream(event)) = rx.recv() => app.handle_stream(event),
+            _ = &mut shutdown => break,
+        }
+
+        if app.has_pending() {
+            app.load_pending(source).await;
+        }
+        if let Some(metrics) = metrics {
+            metrics.record(&app);
+        }
+        if let Some(hub) = hub {
+            hub.record(&app);
+        }
+        if app.status_message != status {
+            status = app.status_message.clone();
+            eprintln!("{} {}", chrono::Local::now().format("%H:%M:%S"), status);
+        }
+    }
+    Ok(())
+}
+
+/// Ctrl-C, or SIGTERM from a service manager.
+#[cfg(unix)]
+async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+        Ok(mut term) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = term.recv() => {}
+            }
+        }
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
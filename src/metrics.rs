@@ -0,0 +1,270 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder, TEXT_FORMAT,
+};
+
+use crate::app::App;
+
+/// Prometheus metrics for the watched coins and the exchange API, served
+/// as text from `/metrics`.
+pub struct Metrics {
+    registry: Registry,
+    price: GaugeVec,
+    change: GaugeVec,
+    volume: GaugeVec,
+    high: GaugeVec,
+    low: GaugeVec,
+    request_duration: HistogramVec,
+    api_errors: IntCounterVec,
+    used_weight: IntGauge,
+    weight_limit: IntGauge,
+    stream_connected: IntGauge,
+    /// Label values of the coin gauges set by the last `record`
+    recorded: Mutex<Vec<[String; 2]>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let coin_gauge = |name: &str, help: &str| {
+            let gauge = GaugeVec::new(Opts::new(name, help), &["symbol", "pair"])
+                .expect("metric options are valid");
+            registry
+                .register(Box::new(gauge.clone()))
+                .expect("metric names are unique");
+            gauge
+        };
+        let price = coin_gauge("cryptowatcher_price", "Last price in the quote asset");
+        let change = coin_gauge(
+            "cryptowatcher_change_24h_percent",
+            "Price change over the last 24 hours, in percent",
+        );
+        let volume = coin_gauge(
+            "cryptowatcher_volume_24h",
+            "Volume over the last 24 hours in the base asset",
+        );
+        let high = coin_gauge(
+            "cryptowatcher_high_24h",
+            "Highest price in the last 24 hours",
+        );
+        let low = coin_gauge("cryptowatcher_low_24h", "Lowest price in the last 24 hours");
+
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "cryptowatcher_request_duration_seconds",
+                "Time until the exchange API responded, by endpoint",
+            ),
+            &["endpoint"],
+        )
+        .expect("metric options are valid");
+        let api_errors = IntCounterVec::new(
+            Opts::new(
+                "cryptowatcher_api_errors_total",
+                "Failed API requests by HTTP status, or timeout, connect or other",
+            ),
+            &["status"],
+        )
+        .expect("metric options are valid");
+        let used_weight = IntGauge::new(
+            "cryptowatcher_rate_limit_used_weight",
+            "Request weight used in the current rate limit window",
+        )
+        .expect("metric options are valid");
+        let weight_limit = IntGauge::new(
+            "cryptowatcher_rate_limit_weight_limit",
+            "Request weight allowed per rate limit window",
+        )
+        .expect("metric options are valid");
+        let stream_connected = IntGauge::new(
+            "cryptowatcher_stream_connected",
+            "1 while live prices arrive over the WebSocket",
+        )
+        .expect("metric options are valid");
+        for collector in [
+            Box::new(request_duration.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(api_errors.clone()),
+            Box::new(used_weight.clone()),
+            Box::new(weight_limit.clone()),
+            Box::new(stream_connected.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+
+        Self {
+            registry,
+            price,
+            change,
+            volume,
+            high,
+            low,
+            request_duration,
+            api_errors,
+            used_weight,
+            weight_limit,
+            stream_connected,
+            recorded: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn observe_request(&self, endpoint: &str, elapsed: Duration) {
+        self.request_duration
+            .with_label_values(&[endpoint])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn count_error(&self, status: &str) {
+        self.api_errors.with_label_values(&[status]).inc();
+    }
+
+    /// Copies the app's coin stats and rate limit into the gauges. Coins
+    /// without a price yet are left out, and coins no longer watched are
+    /// dropped.
+    pub fn record(&self, app: &App) {
+        let mut current = Vec::with_capacity(app.coins.len());
+        for coin in app.coins.iter().filter(|c| c.price > 0.0) {
+            let labels = [coin.symbol.clone(), coin.pair.to_string()];
+            let values = [
+                (&self.price, coin.price),
+                (&self.change, coin.change_24h),
+                (&self.volume, coin.volume_24h),
+                (&self.high, coin.high_24h),
+                (&self.low, coin.low_24h),
+            ];
+            for (gauge, value) in values {
+                gauge.with_label_values(&labels).set(value);
+            }
+            current.push(labels);
+        }
+
+        let mut recorded = self.recorded.lock().unwrap();
+        for labels in recorded.iter().filter(|l| !current.contains(l)) {
+            for gauge in [
+                &self.price,
+                &self.change,
+                &self.volume,
+                &self.high,
+                &self.low,
+            ] {
+                let _ = gauge.remove_label_values(&[&labels[0], &labels[1]]);
+            }
+        }
+        *recorded = current;
+
+        if let Some(limit) = app.rate_limit {
+            self.used_weight.set(limit.used_weight.into());
+            self.weight_limit.set(limit.weight_limit.into());
+        }
+        self.stream_connected.set(app.streaming.into());
+    }
+
+    /// Everything in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("encoding to memory cannot fail");
+        String::from_utf8(buffer).expect("the text format is UTF-8")
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Starts serving `/metrics` on `addr` in the background, returning the
+/// bound address once listening.
+pub async fn serve(addr: SocketAddr, metrics: Arc<Metrics>) -> Result<SocketAddr> {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("cannot listen on {}", addr))?;
+    let bound = listener.local_addr()?;
+    let router = Router::new()
+        .route("/metrics", get(scrape))
+        .with_state(metrics);
+    tokio::spawn(async move {
+        // Only fails if accepting connections does; there is nothing to retry
+        let _ = axum::serve(listener, router).await;
+    });
+    Ok(bound)
+}
+
+async fn scrape(State(metrics): State<Arc<Metrics>>) -> impl IntoResponse {
+    ([(CONTENT_TYPE, TEXT_FORMAT)], metrics.render())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{Pair, RateLimit, Timeframe};
+
+    #[test]
+    fn test_record_app() {
+        let metrics = Metrics::new();
+        let mut app = App::new(
+            vec![Pair::new("BTC", "USDT"), Pair::new("ETH", "BTC")],
+            Timeframe::M1,
+        );
+        app.coins[0].price = 42000.5;
+        app.coins[0].change_24h = -1.5;
+        app.rate_limit = Some(RateLimit {
+            used_weight: 12,
+            weight_limit: 6000,
+            resume_at: None,
+        });
+        app.streaming = true;
+        metrics.record(&app);
+        metrics.observe_request("/api/v3/ticker/24hr", Duration::from_millis(120));
+        metrics.count_error("429");
+
+        let text = metrics.render();
+        assert!(text.contains("cryptowatcher_price{pair=\"BTC/USDT\",symbol=\"BTCUSDT\"} 42000.5"));
+        assert!(text.contains(
+            "cryptowatcher_change_24h_percent{pair=\"BTC/USDT\",symbol=\"BTCUSDT\"} -1.5"
+        ));
+        // No price yet
+        assert!(!text.contains("ETHBTC"));
+        assert!(text.contains("cryptowatcher_rate_limit_used_weight 12"));
+        assert!(text.contains("cryptowatcher_stream_connected 1"));
+        assert!(text.contains(
+            "cryptowatcher_request_duration_seconds_count{endpoint=\"/api/v3/ticker/24hr\"} 1"
+        ));
+        assert!(text.contains("cryptowatcher_api_errors_total{status=\"429\"} 1"));
+
+        app.coins.remove(0);
+        metrics.record(&app);
+        assert!(!metrics.render().contains("BTCUSDT"));
+    }
+
+    #[tokio::test]
+    async fn test_serve_metrics() {
+        let metrics = Arc::new(Metrics::new());
+        metrics.count_error("timeout");
+        let addr = serve("127.0.0.1:0".parse().unwrap(), metrics)
+            .await
+            .unwrap();
+
+        let resp = reqwest::get(format!("http://{}/metrics", addr))
+            .await
+            .unwrap();
+        assert!(resp.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+        let body = resp.text().await.unwrap();
+        assert!(body.contains("cryptowatcher_api_errors_total{status=\"timeout\"} 1"));
+    }
+}
@@ -0,0 +1,574 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app::CoinData;
+use crate::config;
+use crate::source::{Pair, DEFAULT_QUOTE};
+
+/// `~/.config/cryptowatcher/holdings.toml` or the platform equivalent.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("cryptowatcher").join("holdings.toml"))
+}
+
+/// One purchase: `quantity` units bought at `price` each.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lot {
+    pub quantity: f64,
+    pub price: f64,
+}
+
+/// A position in one pair, made of one or more lots.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "HoldingEntry")]
+pub struct Holding {
+    pub pair: Pair,
+    pub lots: Vec<Lot>,
+    /// Whether the file named the quote, see `AlertRule::with_default_quote`
+    explicit_quote: bool,
+}
+
+/// A holding as written in the file: either `quantity` with `cost_basis`
+/// (average price paid per unit), or a list of `lots`.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HoldingEntry {
+    pair: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quantity: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cost_basis: Option<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    lots: Vec<Lot>,
+}
+
+impl TryFrom<HoldingEntry> for Holding {
+    type Error = String;
+
+    fn try_from(entry: HoldingEntry) -> Result<Self, Self::Error> {
+        let pair = Pair::parse_with_quote(&entry.pair, DEFAULT_QUOTE)?;
+        let lots = match (entry.quantity, entry.cost_basis, entry.lots.is_empty()) {
+            (Some(quantity), Some(price), true) => vec![Lot { quantity, price }],
+            (None, None, false) => entry.lots,
+            (Some(_), None, true) => return Err(format!("{} needs a cost_basis", pair)),
+            _ => {
+                return Err(format!(
+                    "{} needs either quantity and cost_basis, or lots",
+                    pair
+                ))
+            }
+        };
+        if let Some(lot) = lots.iter().find(|l| l.quantity <= 0.0 || l.price < 0.0) {
+            return Err(format!(
+                "{} has an invalid lot of {} at {}",
+                pair, lot.quantity, lot.price
+            ));
+        }
+        Ok(Self {
+            explicit_quote: entry.pair.contains('/'),
+            pair,
+            lots,
+        })
+    }
+}
+
+impl From<&Holding> for HoldingEntry {
+    fn from(holding: &Holding) -> Self {
+        // Bare coins stay bare so they keep following `--quote`
+        let pair = if holding.explicit_quote {
+            holding.pair.to_string()
+        } else {
+            holding.pair.base.clone()
+        };
+        match holding.lots.as_slice() {
+            [lot] => Self {
+                pair,
+                quantity: Some(lot.quantity),
+                cost_basis: Some(lot.price),
+                lots: Vec::new(),
+            },
+            lots => Self {
+                pair,
+                quantity: None,
+                cost_basis: None,
+                lots: lots.to_vec(),
+            },
+        }
+    }
+}
+
+impl Holding {
+    pub fn new(pair: Pair, lots: Vec<Lot>) -> Self {
+        Self {
+            pair,
+            lots,
+            explicit_quote: true,
+        }
+    }
+
+    pub fn quantity(&self) -> f64 {
+        self.lots.iter().map(|l| l.quantity).sum()
+    }
+
+    /// Total paid for every lot.
+    pub fn cost(&self) -> f64 {
+        self.lots.iter().map(|l| l.quantity * l.price).sum()
+    }
+
+    pub fn average_cost(&self) -> f64 {
+        let quantity = self.quantity();
+        if quantity > 0.0 {
+            self.cost() / quantity
+        } else {
+            0.0
+        }
+    }
+
+    /// Value and P&L at the coin's last price.
+    pub fn position(&self, coin: &CoinData) -> Position {
+        let value = self.quantity() * coin.price;
+        let cost = self.cost();
+        Position {
+            value,
+            pnl: value - cost,
+            pnl_percent: (cost > 0.0).then(|| (value - cost) / cost * 100.0),
+            day_change: value - value / (1.0 + coin.change_24h / 100.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub value: f64,
+    /// Unrealized profit or loss against the cost basis
+    pub pnl: f64,
+    pub pnl_percent: Option<f64>,
+    /// Value gained over the exchange's 24h window
+    pub day_change: f64,
+}
+
+/// Totals for the holdings quoted in one asset; values in different quotes
+/// are never added together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub quote: String,
+    pub value: f64,
+    pub day_change: f64,
+    /// `(base, percent of value)`, largest first
+    pub allocations: Vec<(String, f64)>,
+}
+
+impl Summary {
+    pub fn day_change_percent(&self) -> f64 {
+        let before = self.value - self.day_change;
+        if before > 0.0 {
+            self.day_change / before * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A change typed into the holding prompt:
+/// `0.5@30000` sets the position, `+0.1@65000` adds a lot, `0.4` keeps the
+/// average cost, and `0` removes it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HoldingEdit {
+    Set { quantity: f64, price: Option<f64> },
+    Add(Lot),
+    Remove,
+}
+
+impl FromStr for HoldingEdit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let number = |text: &str| -> Result<f64, String> {
+            match text.trim().parse::<f64>() {
+                Ok(value) if value.is_finite() && value >= 0.0 => Ok(value),
+                _ => Err(format!("invalid number '{}'", text.trim())),
+            }
+        };
+
+        let (add, rest) = match s.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (quantity, price) = match rest.split_once('@') {
+            Some((quantity, price)) => (number(quantity)?, Some(number(price)?)),
+            None => (number(rest)?, None),
+        };
+
+        match (add, price) {
+            (true, Some(price)) if quantity > 0.0 => Ok(Self::Add(Lot { quantity, price })),
+            (true, _) => Err("a new lot needs QTY@PRICE".to_string()),
+            (false, _) if quantity == 0.0 => Ok(Self::Remove),
+            (false, price) => Ok(Self::Set { quantity, price }),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct HoldingsFile {
+    #[serde(rename = "holding")]
+    holdings: Vec<Holding>,
+}
+
+#[derive(Serialize)]
+struct HoldingsFileOut {
+    holding: Vec<HoldingEntry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Portfolio {
+    pub holdings: Vec<Holding>,
+}
+
+impl Portfolio {
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        let file: HoldingsFile = toml::from_str(text)?;
+        Ok(Self {
+            holdings: file.holdings,
+        })
+    }
+
+    /// Reads the holdings file; a missing file is an empty portfolio.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("cannot read holdings file {}", path.display()))?;
+        Self::parse(&text).map_err(|e| anyhow!("invalid holdings file {}\n{}", path.display(), e))
+    }
+
+    /// Writes to a temporary file beside `path` and renames it into place,
+    /// so a crash mid-write never leaves a truncated file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = HoldingsFileOut {
+            holding: self.holdings.iter().map(HoldingEntry::from).collect(),
+        };
+        let text = toml::to_string(&file)?;
+        config::write_atomic(path, &text)
+    }
+
+    /// Re-quotes holdings given as a bare coin, so `BTC` follows `--quote`.
+    pub fn with_default_quote(mut self, quote: &str) -> Self {
+        for holding in &mut self.holdings {
+            if !holding.explicit_quote {
+                holding.pair.quote = quote.to_uppercase();
+            }
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.holdings.is_empty()
+    }
+
+    pub fn pairs(&self) -> impl Iterator<Item = &Pair> {
+        self.holdings.iter().map(|h| &h.pair)
+    }
+
+    pub fn get(&self, pair: &Pair) -> Option<&Holding> {
+        self.holdings.iter().find(|h| &h.pair == pair)
+    }
+
+    pub fn apply(&mut self, pair: &Pair, edit: HoldingEdit) -> Result<(), String> {
+        let index = self.holdings.iter().position(|h| &h.pair == pair);
+        match (edit, index) {
+            (HoldingEdit::Remove, Some(i)) => {
+                self.holdings.remove(i);
+            }
+            (HoldingEdit::Remove, None) => {}
+            (HoldingEdit::Add(lot), Some(i)) => self.holdings[i].lots.push(lot),
+            (HoldingEdit::Add(lot), None) => {
+                self.holdings.push(Holding::new(pair.clone(), vec![lot]));
+            }
+            (HoldingEdit::Set { quantity, price }, index) => {
+                let price = match (price, index) {
+                    (Some(price), _) => price,
+                    (None, Some(i)) => self.holdings[i].average_cost(),
+                    (None, None) => return Err(format!("{} needs QTY@PRICE", pair)),
+                };
+                let lots = vec![Lot { quantity, price }];
+                match index {
+                    Some(i) => self.holdings[i].lots = lots,
+                    None => self.holdings.push(Holding::new(pair.clone(), lots)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Totals per quote asset over the holdings that have a live price.
+    pub fn summaries(&self, coins: &[CoinData]) -> Vec<Summary> {
+        let mut summaries: Vec<Summary> = Vec::new();
+        for holding in &self.holdings {
+            let Some(coin) = coins
+                .iter()
+                .find(|c| c.pair == holding.pair && c.price > 0.0)
+            else {
+                continue;
+            };
+            let position = holding.position(coin);
+            let index = match summaries.iter().position(|s| s.quote == coin.pair.quote) {
+                Some(i) => i,
+                None => {
+                    summaries.push(Summary {
+                        quote: coin.pair.quote.clone(),
+                        value: 0.0,
+                        day_change: 0.0,
+                        allocations: Vec::new(),
+                    });
+                    summaries.len() - 1
+                }
+            };
+            let summary = &mut summaries[index];
+            summary.value += position.value;
+            summary.day_change += position.day_change;
+            summary
+                .allocations
+                .push((coin.pair.base.clone(), position.value));
+        }
+        for summary in &mut summaries {
+            let total = summary.value;
+            for (_, share) in &mut summary.allocations {
+                *share = if total > 0.0 {
+                    *share / total * 100.0
+                } else {
+                    0.0
+                };
+            }
+            summary.allocations.sort_by(|a, b| b.1.total_cmp(&a.1));
+        }
+        summaries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coin(pair: &str, price: f64, change_24h: f64) -> CoinData {
+        let mut coin = CoinData::new(pair.parse().unwrap());
+        coin.price = price;
+        coin.change_24h = change_24h;
+        coin
+    }
+
+    #[test]
+    fn test_parse_holdings() {
+        let portfolio = Portfolio::parse(
+            r#"
+[[holding]]
+pair = "BTC"
+quantity = 0.5
+cost_basis = 30000
+
+[[holding]]
+pair = "ETH/BTC"
+lots = [
+    { quantity = 2.0, price = 0.05 },
+    { quantity = 1.0, price = 0.08 },
+]
+"#,
+        )
+        .unwrap()
+        .with_default_quote("FDUSD");
+
+        let btc = portfolio.get(&Pair::new("BTC", "FDUSD")).unwrap();
+        assert_eq!(btc.quantity(), 0.5);
+        assert_eq!(btc.cost(), 15_000.0);
+
+        let eth = portfolio.get(&Pair::new("ETH", "BTC")).unwrap();
+        assert_eq!(eth.quantity(), 3.0);
+        assert!((eth.average_cost() - 0.06).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |text: &str| Portfolio::parse(text).unwrap_err().to_string();
+        assert!(err("[[holding]]\npair = \"BTC\"\nquantity = 1\n").contains("needs a cost_basis"));
+        assert!(err("[[holding]]\npair = \"BTC\"\n").contains("needs either quantity"));
+        assert!(
+            err("[[holding]]\npair = \"BTC\"\nquantity = -1\ncost_basis = 5\n")
+                .contains("invalid lot")
+        );
+        assert!(err("[[holding]]\npair = \"BTC\"\nqty = 1\n").contains("unknown field"));
+    }
+
+    #[test]
+    fn test_position() {
+        let holding = Holding::new(
+            Pair::new("BTC", "USDT"),
+            vec![Lot {
+                quantity: 0.5,
+                price: 40_000.0,
+            }],
+        );
+        let position = holding.position(&coin("BTC", 50_000.0, 25.0));
+        assert_eq!(position.value, 25_000.0);
+        assert_eq!(position.pnl, 5_000.0);
+        assert_eq!(position.pnl_percent, Some(25.0));
+        // Worth 20,000 a day ago
+        assert_eq!(position.day_change, 5_000.0);
+    }
+
+    #[test]
+    fn test_parse_edits() {
+        let edit = |s: &str| s.parse::<HoldingEdit>();
+        assert_eq!(
+            edit("0.5@30000"),
+            Ok(HoldingEdit::Set {
+                quantity: 0.5,
+                price: Some(30_000.0)
+            })
+        );
+        assert_eq!(
+            edit("0.4"),
+            Ok(HoldingEdit::Set {
+                quantity: 0.4,
+                price: None
+            })
+        );
+        assert_eq!(
+            edit("+0.1 @ 65000"),
+            Ok(HoldingEdit::Add(Lot {
+                quantity: 0.1,
+                price: 65_000.0
+            }))
+        );
+        assert!(edit("").is_err());
+        assert_eq!(edit("0"), Ok(HoldingEdit::Remove));
+        assert!(edit("+0.1").is_err());
+        assert!(edit("lots").is_err());
+        assert!(edit("-1@5").is_err());
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let btc = Pair::new("BTC", "USDT");
+        let mut portfolio = Portfolio::default();
+        assert!(portfolio
+            .apply(&btc, "1".parse().unwrap())
+            .unwrap_err()
+            .contains("QTY@PRICE"));
+
+        portfolio.apply(&btc, "1@100".parse().unwrap()).unwrap();
+        portfolio.apply(&btc, "+1@200".parse().unwrap()).unwrap();
+        assert_eq!(portfolio.get(&btc).unwrap().average_cost(), 150.0);
+
+        // Resizing keeps the average cost
+        portfolio.apply(&btc, "0.5".parse().unwrap()).unwrap();
+        let holding = portfolio.get(&btc).unwrap();
+        assert_eq!(holding.quantity(), 0.5);
+        assert_eq!(holding.average_cost(), 150.0);
+
+        portfolio.apply(&btc, "0".parse().unwrap()).unwrap();
+        assert!(portfolio.is_empty());
+    }
+
+    #[test]
+    fn test_summaries_by_quote() {
+        let portfolio = Portfolio {
+            holdings: vec![
+                Holding::new(
+                    Pair::new("BTC", "USDT"),
+                    vec![Lot {
+                        quantity: 1.0,
+                        price: 0.0,
+                    }],
+                ),
+                Holding::new(
+                    Pair::new("ETH", "USDT"),
+                    vec![Lot {
+                        quantity: 10.0,
+                        price: 0.0,
+                    }],
+                ),
+                Holding::new(
+                    Pair::new("SOL", "BTC"),
+                    vec![Lot {
+                        quantity: 4.0,
+                        price: 0.0,
+                    }],
+                ),
+                // No price yet
+                Holding::new(
+                    Pair::new("DOGE", "USDT"),
+                    vec![Lot {
+                        quantity: 4.0,
+                        price: 0.0,
+                    }],
+                ),
+            ],
+        };
+        let coins = vec![
+            coin("BTC", 600.0, 20.0),
+            coin("ETH", 40.0, 0.0),
+            coin("SOL/BTC", 0.5, 0.0),
+            coin("DOGE", 0.0, 0.0),
+        ];
+
+        let summaries = portfolio.summaries(&coins);
+        assert_eq!(summaries.len(), 2);
+        let usdt = &summaries[0];
+        assert_eq!(usdt.quote, "USDT");
+        assert_eq!(usdt.value, 1_000.0);
+        assert_eq!(usdt.day_change, 100.0);
+        assert!((usdt.day_change_percent() - 100.0 / 9.0).abs() < 1e-9);
+        assert_eq!(
+            usdt.allocations,
+            vec![("BTC".to_string(), 60.0), ("ETH".to_string(), 40.0)]
+        );
+        assert_eq!(summaries[1].quote, "BTC");
+        assert_eq!(summaries[1].value, 2.0);
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("cryptowatcher-holdings-{}", std::process::id()));
+        let path = dir.join("holdings.toml");
+        let _ = fs::remove_file(&path);
+        assert!(Portfolio::load(&path).unwrap().is_empty());
+
+        let mut portfolio = Portfolio::parse(
+            r#"
+            [[holding]]
+            pair = "SOL"
+            quantity = 3
+            cost_basis = 20
+            "#,
+        )
+        .unwrap()
+        .with_default_quote("FDUSD");
+        let btc = Pair::new("BTC", "USDT");
+        portfolio.apply(&btc, "1@100".parse().unwrap()).unwrap();
+        portfolio
+            .apply(&Pair::new("ETH", "BTC"), "2@0.05".parse().unwrap())
+            .unwrap();
+        portfolio.apply(&btc, "+1@200".parse().unwrap()).unwrap();
+        portfolio
+            .apply(&Pair::new("SOL", "FDUSD"), "+1@30".parse().unwrap())
+            .unwrap();
+        portfolio.save(&path).unwrap();
+
+        // The bare coin is still bare, not pinned to this run's quote
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("pair = \"SOL\""));
+        assert_eq!(
+            Portfolio::load(&path).unwrap().with_default_quote("FDUSD"),
+            portfolio
+        );
+        assert!(!path.with_extension("toml.tmp").exists());
+    }
+}
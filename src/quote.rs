@@ -0,0 +1,197 @@
+use std::io::{self, Write};
+use std::time::Duration;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::app::CoinData;
+use crate::source::MarketDataSource;
+use crate::ui::{format_price, format_volume};
+
+const HEADERS: [&str; 6] = ["Pair", "Price", "24h %", "High", "Low", "Volume"];
+
+/// Prints quotes for `coins` once, or every `watch` until stdout closes.
+/// A one-off quote fails when any coin has no data; while watching, errors
+/// go to stderr and the next refresh tries again.
+pub async fn run(
+    source: &dyn MarketDataSource,
+    coins: &mut [CoinData],
+    json: bool,
+    watch: Option<Duration>,
+) -> Result<()> {
+    let mut out = io::stdout();
+    let mut ticks = watch.map(tokio::time::interval);
+    loop {
+        if let Some(ticks) = &mut ticks {
+            ticks.tick().await;
+        }
+        let result = refresh(source, coins).await;
+        let time = chrono::Utc::now().timestamp_millis();
+        let (quoted, missing): (Vec<&CoinData>, Vec<&CoinData>) = match result {
+            Ok(updated) => coins.iter().partition(|c| updated.contains(&c.symbol)),
+            Err(e) if watch.is_some() => {
+                eprintln!("Error: {:#}", e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let text = if json {
+            let rows: Vec<Value> = quoted.iter().map(|c| to_json(c, time)).collect();
+            format!("{}\n", Value::Array(rows))
+        } else if watch.is_some() {
+            let local = chrono::DateTime::from_timestamp_millis(time)
+                .unwrap_or_default()
+                .with_timezone(&chrono::Local);
+            format!("{}\n", line(&quoted, &local.format("%H:%M:%S").to_string()))
+        } else {
+            table(&quoted)
+        };
+        match out.write_all(text.as_bytes()).and_then(|_| out.flush()) {
+            // Piped into `head` or similar: the reader has all it wants
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+
+        if !missing.is_empty() {
+            let pairs: Vec<String> = missing.iter().map(|c| c.pair.to_string()).collect();
+            let message = format!("no data for {}", pairs.join(", "));
+            if watch.is_none() {
+                anyhow::bail!(message);
+            }
+            eprintln!("Warning: {}", message);
+        }
+        if watch.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+/// Fetches tickers into `coins`, returning the symbols that got one.
+async fn refresh(source: &dyn MarketDataSource, coins: &mut [CoinData]) -> Result<Vec<String>> {
+    let symbols: Vec<String> = coins.iter().map(|c| c.symbol.clone()).collect();
+    let tickers = source.tickers(&symbols).await?;
+    let mut updated = Vec::with_capacity(tickers.len());
+    for ticker in &tickers {
+        if let Some(coin) = coins.iter_mut().find(|c| c.symbol == ticker.symbol) {
+            coin.update_stats(ticker);
+            updated.push(coin.symbol.clone());
+        }
+    }
+    Ok(updated)
+}
+
+/// Aligned table with a header row: text left, numbers right.
+fn table(coins: &[&CoinData]) -> String {
+    let rows: Vec<[String; 6]> = coins
+        .iter()
+        .map(|c| {
+            [
+                c.pair.to_string(),
+                format_price(c.price, c),
+                format!("{:+.2}%", c.change_24h),
+                format_price(c.high_24h, c),
+                format_price(c.low_24h, c),
+                format_volume(c.volume_24h),
+            ]
+        })
+        .collect();
+    let mut widths = HEADERS.map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut text = String::new();
+    let headers = HEADERS.map(String::from);
+    for row in std::iter::once(&headers).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(i, (cell, width))| {
+                if i == 0 {
+                    format!("{:<width$}", cell)
+                } else {
+                    format!("{:>width$}", cell)
+                }
+            })
+            .collect();
+        text.push_str(cells.join("  ").trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// One refresh on a single line for `--watch`, e.g.
+/// `12:00:05  BTC/USDT $42,069.42 +1.23%  ETH/USDT $3,210.00 -0.52%`.
+fn line(coins: &[&CoinData], time: &str) -> String {
+    let mut parts = vec![time.to_string()];
+    parts.extend(coins.iter().map(|c| {
+        format!(
+            "{} {} {:+.2}%",
+            c.pair,
+            format_price(c.price, c),
+            c.change_24h
+        )
+    }));
+    parts.join("  ")
+}
+
+/// Machine-readable quote with unformatted numbers; `time` is when it was
+/// fetched, in epoch milliseconds.
+fn to_json(coin: &CoinData, time: i64) -> Value {
+    json!({
+        "pair": coin.pair.to_string(),
+        "symbol": coin.symbol,
+        "price": coin.price,
+        "change_percent": coin.change_24h,
+        "high": coin.high_24h,
+        "low": coin.low_24h,
+        "volume": coin.volume_24h,
+        "quote_volume": coin.quote_volume_24h,
+        "time": time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        let btc = CoinData::quoted("BTC", 42069.42, 1.234);
+        let eth = CoinData::quoted("ETH", 3210.0, -0.5);
+        assert_eq!(
+            table(&[&btc, &eth]),
+            "\
+Pair           Price   24h %        High         Low  Volume
+BTC/USDT  $42,069.42  +1.23%  $46,276.36  $37,862.48   12.3K
+ETH/USDT   $3,210.00  -0.50%   $3,531.00   $2,889.00   12.3K
+"
+        );
+    }
+
+    #[test]
+    fn test_watch_line() {
+        let btc = CoinData::quoted("BTC", 42069.42, 1.234);
+        let eth = CoinData::quoted("ETH", 3210.0, -0.5);
+        assert_eq!(
+            line(&[&btc, &eth], "12:00:05"),
+            "12:00:05  BTC/USDT $42,069.42 +1.23%  ETH/USDT $3,210.00 -0.50%"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let btc = CoinData::quoted("BTC", 100.0, 2.5);
+        let value = to_json(&btc, 1_700_000_000_000);
+        assert_eq!(value["pair"], "BTC/USDT");
+        assert_eq!(value["symbol"], "BTCUSDT");
+        assert_eq!(value["price"], 100.0);
+        assert_eq!(value["change_percent"], 2.5);
+        assert_eq!(value["quote_volume"], 1_234_500.0);
+        assert_eq!(value["time"], 1_700_000_000_000_i64);
+    }
+}
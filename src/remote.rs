@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::source::{Candle, Depth, MarketDataSource, SymbolInfo, Ticker, Timeframe, Trade};
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// Market data from a `cryptowatcher serve` instance instead of the
+/// exchange, so many clients share one collector's requests.
+pub struct RemoteSource {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl RemoteSource {
+    /// `url` is the server's address, e.g. `http://10.0.0.5:9185`; a bare
+    /// `host:port` means plain HTTP.
+    pub fn new(url: &str) -> Result<Self> {
+        let url = url.trim_end_matches('/');
+        let base_url = if url.starts_with("http://") || url.starts_with("https://") {
+            url.to_string()
+        } else {
+            format!("http://{}", url)
+        };
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        Ok(Self { client, base_url })
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T> {
+        let resp = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(query)
+            .send()
+            .await
+            .with_context(|| format!("cannot reach server {}", self.base_url))?;
+        let status = resp.status();
+        if !status.is_success() {
+            let message = match resp.json::<ErrorBody>().await {
+                Ok(body) => body.error,
+                Err(_) => status.to_string(),
+            };
+            return Err(anyhow!("server error: {}", message));
+        }
+        Ok(resp.json().await?)
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for RemoteSource {
+    async fn ticker(&self, symbol: &str) -> Result<Ticker> {
+        self.tickers(&[symbol.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("no ticker for {}", symbol))
+    }
+
+    async fn tickers(&self, symbols: &[String]) -> Result<Vec<Ticker>> {
+        self.get("/api/tickers", &[("symbols", symbols.join(","))])
+            .await
+    }
+
+    async fn klines(&self, symbol: &str, timeframe: Timeframe, limit: u32) -> Result<Vec<Candle>> {
+        let query = [
+            ("timeframe", timeframe.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        self.get(&format!("/api/klines/{}", symbol), &query).await
+    }
+
+    async fn klines_since(
+        &self,
+        symbol: &str,
+        timeframe: Timeframe,
+        start_ms: i64,
+        limit: u32,
+    ) -> Result<Vec<Candle>> {
+        let query = [
+            ("timeframe", timeframe.to_string()),
+            ("start", start_ms.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        self.get(&format!("/api/klines/{}", symbol), &query).await
+    }
+
+    async fn symbols(&self) -> Result<Vec<SymbolInfo>> {
+        self.get("/api/symbols", &[]).await
+    }
+
+    async fn recent_trades(&self, symbol: &str, limit: u32) -> Result<Vec<Trade>> {
+        self.get(
+            &format!("/api/trades/{}", symbol),
+            &[("limit", limit.to_string())],
+        )
+        .await
+    }
+
+    async fn depth(&self, symbol: &str, limit: u32) -> Result<Depth> {
+        self.get(
+            &format!("/api/depth/{}", symbol),
+            &[("limit", limit.to_string())],
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_url() {
+        let url = |s| RemoteSource::new(s).unwrap().base_url;
+        assert_eq!(url("10.0.0.5:9185"), "http://10.0.0.5:9185");
+        assert_eq!(url("https://prices.example/"), "https://prices.example");
+    }
+}
@@ -0,0 +1,676 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::broadcast;
+
+use crate::api::MAX_KLINES;
+use crate::app::{App, CoinData};
+use crate::source::{Candle, Depth, MarketDataSource, SymbolInfo, Ticker, Timeframe, Trade};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:9185";
+const DEFAULT_LIMIT: u32 = 100;
+/// Updates a slow event client can fall behind by before it skips ahead.
+const EVENT_BUFFER: usize = 256;
+/// How long data fetched from upstream for one client is served to others.
+const FETCH_TTL: Duration = Duration::from_secs(2);
+/// Exchange symbols rarely change, and clients cache them too.
+const SYMBOLS_TTL: Duration = Duration::from_secs(3600);
+
+/// A change pushed to `/api/events` subscribers.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Update {
+    Ticker(Ticker),
+    Kline {
+        symbol: String,
+        timeframe: Timeframe,
+        candle: Candle,
+    },
+}
+
+impl Update {
+    fn to_event(&self) -> Event {
+        let name = match self {
+            Update::Ticker(_) => "ticker",
+            Update::Kline { .. } => "kline",
+        };
+        Event::default()
+            .event(name)
+            .json_data(self)
+            .expect("updates serialize to JSON")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct WatchedPair {
+    pair: String,
+    symbol: String,
+    base: String,
+    quote: String,
+}
+
+/// Candles the fetch loop keeps for one symbol, oldest first.
+#[derive(Debug, Clone, PartialEq)]
+struct History {
+    timeframe: Timeframe,
+    candles: Vec<Candle>,
+}
+
+#[derive(Debug, Default)]
+struct Snapshot {
+    watchlist: Vec<WatchedPair>,
+    tickers: HashMap<String, Ticker>,
+    history: HashMap<String, History>,
+}
+
+/// A cached upstream response; `None` until the first fetch succeeds.
+type Slot<T> = Arc<tokio::sync::Mutex<Option<(Instant, T)>>>;
+
+/// Upstream responses by request, reused until `ttl` passes so clients
+/// asking for the same thing share one exchange request. Each key's slot
+/// stays locked while it is fetched, so requests arriving meanwhile wait
+/// for that fetch instead of starting their own.
+struct Cache<T> {
+    ttl: Duration,
+    slots: Mutex<HashMap<String, Slot<T>>>,
+}
+
+impl<T: Clone> Cache<T> {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn get_or_fetch<F>(&self, key: String, fetch: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let slot = {
+            let mut slots = self.slots.lock().unwrap();
+            // Drop expired slots, but not ones being fetched
+            slots.retain(|_, slot| {
+                slot.try_lock().map_or(true, |entry| {
+                    entry
+                        .as_ref()
+                        .is_some_and(|(fetched, _)| fetched.elapsed() < self.ttl)
+                })
+            });
+            slots.entry(key).or_default().clone()
+        };
+        let mut entry = slot.lock().await;
+        if let Some((fetched, value)) = entry.as_ref() {
+            if fetched.elapsed() < self.ttl {
+                return Ok(value.clone());
+            }
+        }
+        let value = fetch.await?;
+        *entry = Some((Instant::now(), value.clone()));
+        Ok(value)
+    }
+}
+
+/// What the `serve` fetch loop last saw, shared with API requests. Anything
+/// it doesn't hold, such as other timeframes, trades or order books, is
+/// fetched from `upstream`, briefly cached where many clients ask for it.
+pub struct Hub {
+    upstream: Arc<dyn MarketDataSource>,
+    snapshot: RwLock<Snapshot>,
+    updates: broadcast::Sender<Update>,
+    tickers: Cache<Vec<Ticker>>,
+    klines: Cache<Vec<Candle>>,
+    trades: Cache<Vec<Trade>>,
+    depth: Cache<Depth>,
+    symbols: Cache<Vec<SymbolInfo>>,
+}
+
+impl Hub {
+    pub fn new(upstream: Arc<dyn MarketDataSource>) -> Self {
+        Self {
+            upstream,
+            snapshot: RwLock::new(Snapshot::default()),
+            updates: broadcast::channel(EVENT_BUFFER).0,
+            tickers: Cache::new(FETCH_TTL),
+            klines: Cache::new(FETCH_TTL),
+            trades: Cache::new(FETCH_TTL),
+            depth: Cache::new(FETCH_TTL),
+            symbols: Cache::new(SYMBOLS_TTL),
+        }
+    }
+
+    /// Copies the app's watchlist, tickers and candles, announcing the
+    /// tickers and latest candles that changed since the last call.
+    pub fn record(&self, app: &App) {
+        let mut snapshot = self.snapshot.write().unwrap();
+        snapshot.watchlist = app
+            .coins
+            .iter()
+            .map(|c| WatchedPair {
+                pair: c.pair.to_string(),
+                symbol: c.symbol.clone(),
+                base: c.pair.base.clone(),
+                quote: c.pair.quote.clone(),
+            })
+            .collect();
+        snapshot
+            .tickers
+            .retain(|symbol, _| app.coins.iter().any(|c| c.symbol == *symbol));
+        snapshot
+            .history
+            .retain(|symbol, _| app.coins.iter().any(|c| c.symbol == *symbol));
+
+        for coin in app.coins.iter().filter(|c| c.price > 0.0) {
+            let ticker = ticker(coin);
+            if snapshot.tickers.get(&coin.symbol) != Some(&ticker) {
+                snapshot.tickers.insert(coin.symbol.clone(), ticker.clone());
+                // Nobody listening is not an error
+                let _ = self.updates.send(Update::Ticker(ticker));
+            }
+
+            let held = snapshot.history.get(&coin.symbol);
+            let unchanged = held.is_some_and(|h| {
+                h.timeframe == coin.timeframe
+                    && h.candles.len() == coin.price_history.len()
+                    && h.candles.first() == coin.price_history.front()
+                    && h.candles.last() == coin.price_history.back()
+            });
+            if unchanged {
+                continue;
+            }
+            if let Some(&candle) = coin.price_history.back() {
+                let _ = self.updates.send(Update::Kline {
+                    symbol: coin.symbol.clone(),
+                    timeframe: coin.timeframe,
+                    candle,
+                });
+            }
+            snapshot.history.insert(
+                coin.symbol.clone(),
+                History {
+                    timeframe: coin.timeframe,
+                    candles: coin.price_history.iter().copied().collect(),
+                },
+            );
+        }
+    }
+
+    /// Held tickers for `symbols` (all when empty), fetching any others.
+    async fn tickers(&self, symbols: &[String]) -> Result<Vec<Ticker>> {
+        let (mut tickers, missing) = {
+            let snapshot = self.snapshot.read().unwrap();
+            if symbols.is_empty() {
+                return Ok(snapshot.tickers.values().cloned().collect());
+            }
+            let mut held = Vec::new();
+            let mut missing = Vec::new();
+            for symbol in symbols {
+                match snapshot.tickers.get(symbol) {
+                    Some(ticker) => held.push(ticker.clone()),
+                    None => missing.push(symbol.clone()),
+                }
+            }
+            (held, missing)
+        };
+        if !missing.is_empty() {
+            let key = missing.join(",");
+            let fetch = self.upstream.tickers(&missing);
+            match self.tickers.get_or_fetch(key, fetch).await {
+                Ok(fetched) => tickers.extend(fetched),
+                Err(e) if tickers.is_empty() => return Err(e),
+                // Partial results, as from any other source
+                Err(_) => {}
+            }
+        }
+        Ok(tickers)
+    }
+
+    /// The latest `limit` candles from the fetch loop when it holds enough
+    /// at `timeframe`, otherwise from upstream.
+    async fn klines(
+        &self,
+        symbol: &str,
+        timeframe: Timeframe,
+        start_ms: Option<i64>,
+        limit: u32,
+    ) -> Result<Vec<Candle>> {
+        if start_ms.is_none() {
+            let snapshot = self.snapshot.read().unwrap();
+            let held = snapshot
+                .history
+                .get(symbol)
+                .filter(|h| h.timeframe == timeframe && h.candles.len() >= limit as usize);
+            if let Some(history) = held {
+                return Ok(history.candles[history.candles.len() - limit as usize..].to_vec());
+            }
+        }
+        let key = format!("{}:{}:{:?}:{}", symbol, timeframe, start_ms, limit);
+        let fetch = async {
+            match start_ms {
+                Some(start_ms) => {
+                    self.upstream
+                        .klines_since(symbol, timeframe, start_ms, limit)
+                        .await
+                }
+                None => self.upstream.klines(symbol, timeframe, limit).await,
+            }
+        };
+        self.klines.get_or_fetch(key, fetch).await
+    }
+
+    async fn trades(&self, symbol: &str, limit: u32) -> Result<Vec<Trade>> {
+        let key = format!("{}:{}", symbol, limit);
+        self.trades
+            .get_or_fetch(key, self.upstream.recent_trades(symbol, limit))
+            .await
+    }
+
+    async fn depth(&self, symbol: &str, limit: u32) -> Result<Depth> {
+        let key = format!("{}:{}", symbol, limit);
+        self.depth
+            .get_or_fetch(key, self.upstream.depth(symbol, limit))
+            .await
+    }
+
+    async fn symbols(&self) -> Result<Vec<SymbolInfo>> {
+        self.symbols
+            .get_or_fetch(String::new(), self.upstream.symbols())
+            .await
+    }
+}
+
+/// A 24h ticker rebuilt from the stats the app keeps.
+fn ticker(coin: &CoinData) -> Ticker {
+    Ticker {
+        symbol: coin.symbol.clone(),
+        last_price: coin.price,
+        price_change_percent: coin.change_24h,
+        high_price: coin.high_24h,
+        low_price: coin.low_24h,
+        volume: coin.volume_24h,
+        open_price: coin.open_24h,
+        quote_volume: coin.quote_volume_24h,
+        weighted_avg_price: coin.weighted_avg_24h,
+        trade_count: coin.trades_24h,
+    }
+}
+
+/// Starts serving the API on `addr` in the background, returning the bound
+/// address once listening.
+pub async fn serve(addr: SocketAddr, hub: Arc<Hub>) -> Result<SocketAddr> {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("cannot listen on {}", addr))?;
+    let bound = listener.local_addr()?;
+    tokio::spawn(async move {
+        let _ = axum::serve(listener, router(hub)).await;
+    });
+    Ok(bound)
+}
+
+fn router(hub: Arc<Hub>) -> Router {
+    Router::new()
+        .route("/api/watchlist", get(watchlist))
+        .route("/api/tickers", get(tickers))
+        .route("/api/klines/{symbol}", get(klines))
+        .route("/api/trades/{symbol}", get(trades))
+        .route("/api/depth/{symbol}", get(depth))
+        .route("/api/symbols", get(symbols))
+        .route("/api/events", get(events))
+        .with_state(hub)
+}
+
+/// Failed requests answer with `{"error": "..."}`.
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        ApiError(StatusCode::BAD_GATEWAY, format!("{:#}", e))
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError(rejection.status(), rejection.body_text())
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+#[derive(Debug, Deserialize)]
+struct TickersQuery {
+    /// Comma-separated exchange symbols
+    symbols: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KlinesQuery {
+    timeframe: Option<Timeframe>,
+    start: Option<i64>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LimitQuery {
+    limit: Option<u32>,
+}
+
+fn limit(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_KLINES)
+}
+
+async fn watchlist(State(hub): State<Arc<Hub>>) -> Json<Vec<WatchedPair>> {
+    Json(hub.snapshot.read().unwrap().watchlist.clone())
+}
+
+async fn tickers(
+    State(hub): State<Arc<Hub>>,
+    query: Result<Query<TickersQuery>, QueryRejection>,
+) -> ApiResult<Vec<Ticker>> {
+    let Query(query) = query?;
+    let symbols: Vec<String> = query
+        .symbols
+        .iter()
+        .flat_map(|s| s.split(','))
+        .map(|s| s.trim().to_uppercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+    Ok(Json(hub.tickers(&symbols).await?))
+}
+
+async fn klines(
+    State(hub): State<Arc<Hub>>,
+    Path(symbol): Path<String>,
+    query: Result<Query<KlinesQuery>, QueryRejection>,
+) -> ApiResult<Vec<Candle>> {
+    let Query(query) = query?;
+    let candles = hub
+        .klines(
+            &symbol.to_uppercase(),
+            query.timeframe.unwrap_or_default(),
+            query.start,
+            limit(query.limit),
+        )
+        .await?;
+    Ok(Json(candles))
+}
+
+async fn trades(
+    State(hub): State<Arc<Hub>>,
+    Path(symbol): Path<String>,
+    query: Result<Query<LimitQuery>, QueryRejection>,
+) -> ApiResult<Vec<Trade>> {
+    let Query(query) = query?;
+    let trades = hub
+        .trades(&symbol.to_uppercase(), limit(query.limit))
+        .await?;
+    Ok(Json(trades))
+}
+
+async fn depth(
+    State(hub): State<Arc<Hub>>,
+    Path(symbol): Path<String>,
+    query: Result<Query<LimitQuery>, QueryRejection>,
+) -> ApiResult<Depth> {
+    let Query(query) = query?;
+    let depth = hub
+        .depth(&symbol.to_uppercase(), limit(query.limit))
+        .await?;
+    Ok(Json(depth))
+}
+
+async fn symbols(State(hub): State<Arc<Hub>>) -> ApiResult<Vec<SymbolInfo>> {
+    Ok(Json(hub.symbols().await?))
+}
+
+/// Every held ticker, then each change as it happens.
+async fn events(State(hub): State<Arc<Hub>>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // Subscribe first so nothing between the snapshot and the feed is lost
+    let receiver = hub.updates.subscribe();
+    let current: Vec<Update> = hub
+        .snapshot
+        .read()
+        .unwrap()
+        .tickers
+        .values()
+        .cloned()
+        .map(Update::Ticker)
+        .collect();
+    let live = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(update) => return Some((update, receiver)),
+                // A lagging client skips ahead; newer tickers supersede the missed ones
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    let stream = futures::stream::iter(current)
+        .chain(live)
+        .map(|update| Ok(update.to_event()));
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::remote::RemoteSource;
+    use crate::source::Pair;
+
+    /// Counts ticker and order book requests
+    #[derive(Default)]
+    struct Upstream {
+        ticker_requests: AtomicUsize,
+        depth_requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl MarketDataSource for Upstream {
+        async fn ticker(&self, symbol: &str) -> Result<Ticker> {
+            self.ticker_requests.fetch_add(1, Ordering::SeqCst);
+            Ok(Ticker {
+                symbol: symbol.to_string(),
+                last_price: 7.0,
+                price_change_percent: 0.0,
+                high_price: 7.0,
+                low_price: 7.0,
+                volume: 0.0,
+                open_price: 7.0,
+                quote_volume: 0.0,
+                weighted_avg_price: None,
+                trade_count: None,
+            })
+        }
+
+        async fn klines(
+            &self,
+            _symbol: &str,
+            _timeframe: Timeframe,
+            limit: u32,
+        ) -> Result<Vec<Candle>> {
+            Ok((0..limit as i64).map(|i| Candle::flat(i, 1.0)).collect())
+        }
+
+        async fn symbols(&self) -> Result<Vec<SymbolInfo>> {
+            Ok(Vec::new())
+        }
+
+        async fn depth(&self, _symbol: &str, _limit: u32) -> Result<Depth> {
+            let requests = self.depth_requests.fetch_add(1, Ordering::SeqCst) + 1;
+            // Slow enough for concurrent requests to overlap
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(Depth {
+                last_update_id: requests as u64,
+                ..Depth::default()
+            })
+        }
+    }
+
+    fn app() -> App {
+        let mut app = App::new(vec![Pair::new("BTC", "USDT")], Timeframe::M1);
+        let coin = &mut app.coins[0];
+        coin.price = 100.0;
+        for i in 0..3 {
+            coin.price_history
+                .push_back(Candle::flat(i * 60_000, 100.0));
+        }
+        app
+    }
+
+    #[tokio::test]
+    async fn test_record_announces_changes() {
+        let hub = Hub::new(Arc::new(Upstream::default()));
+        let mut updates = hub.updates.subscribe();
+        let mut app = app();
+        hub.record(&app);
+        hub.record(&app);
+        assert!(matches!(updates.try_recv(), Ok(Update::Ticker(t)) if t.last_price == 100.0));
+        assert!(
+            matches!(updates.try_recv(), Ok(Update::Kline { candle, .. }) if candle.open_time == 120_000)
+        );
+        // Nothing changed the second time
+        assert!(updates.try_recv().is_err());
+
+        app.coins[0].price = 101.0;
+        hub.record(&app);
+        assert!(matches!(updates.try_recv(), Ok(Update::Ticker(t)) if t.last_price == 101.0));
+        assert!(updates.try_recv().is_err());
+
+        app.coins.clear();
+        hub.record(&app);
+        assert!(hub.tickers(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_held_data_before_upstream() {
+        let hub = Hub::new(Arc::new(Upstream::default()));
+        hub.record(&app());
+
+        let tickers = hub
+            .tickers(&["BTCUSDT".to_string(), "ETHUSDT".to_string()])
+            .await
+            .unwrap();
+        let prices: Vec<f64> = tickers.iter().map(|t| t.last_price).collect();
+        assert_eq!(prices, vec![100.0, 7.0]);
+
+        // Held: the fetch loop's last two candles
+        let held = hub.klines("BTCUSDT", Timeframe::M1, None, 2).await.unwrap();
+        assert_eq!(held[0].open_time, 60_000);
+        // More than held, another timeframe, or a start time go upstream
+        for (timeframe, start, limit) in [
+            (Timeframe::M1, None, 5),
+            (Timeframe::H1, None, 2),
+            (Timeframe::M1, Some(0), 2),
+        ] {
+            let candles = hub
+                .klines("BTCUSDT", timeframe, start, limit)
+                .await
+                .unwrap();
+            assert_eq!(candles[0].open_time, 0, "{:?}", timeframe);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upstream_requests_are_shared() {
+        let upstream = Arc::new(Upstream::default());
+        let hub = Hub::new(upstream.clone());
+
+        // Requests arriving while one is in flight wait for it
+        let books = futures::future::join_all((0..3).map(|_| hub.depth("BTCUSDT", 20))).await;
+        assert!(books
+            .iter()
+            .all(|b| b.as_ref().unwrap().last_update_id == 1));
+        assert_eq!(hub.depth("BTCUSDT", 20).await.unwrap().last_update_id, 1);
+        assert_eq!(hub.depth("ETHUSDT", 20).await.unwrap().last_update_id, 2);
+        assert_eq!(upstream.depth_requests.load(Ordering::SeqCst), 2);
+
+        let unwatched = ["ETHUSDT".to_string()];
+        for _ in 0..3 {
+            assert_eq!(hub.tickers(&unwatched).await.unwrap()[0].last_price, 7.0);
+        }
+        assert_eq!(upstream.ticker_requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_remote_source_roundtrip() {
+        let hub = Arc::new(Hub::new(Arc::new(Upstream::default())));
+        hub.record(&app());
+        let addr = serve("127.0.0.1:0".parse().unwrap(), hub.clone())
+            .await
+            .unwrap();
+        let remote = RemoteSource::new(&addr.to_string()).unwrap();
+
+        let ticker = remote.ticker("BTCUSDT").await.unwrap();
+        assert_eq!(ticker, super::ticker(&app().coins[0]));
+        let candles = remote.klines("BTCUSDT", Timeframe::M1, 3).await.unwrap();
+        assert_eq!(
+            candles,
+            app().coins[0]
+                .price_history
+                .iter()
+                .copied()
+                .collect::<Vec<_>>()
+        );
+        assert!(remote
+            .recent_trades("BTCUSDT", 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let watchlist: serde_json::Value = reqwest::get(format!("http://{}/api/watchlist", addr))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(watchlist[0]["pair"], "BTC/USDT");
+
+        let resp = reqwest::get(format!("http://{}/api/klines/BTCUSDT?timeframe=2h", addr))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert!(body["error"]
+            .as_str()
+            .unwrap()
+            .contains("unknown timeframe '2h'"));
+
+        // The current tickers arrive first
+        let mut events = reqwest::get(format!("http://{}/api/events", addr))
+            .await
+            .unwrap();
+        let chunk = events.chunk().await.unwrap().unwrap();
+        let text = String::from_utf8_lossy(&chunk);
+        assert!(
+            text.starts_with("event: ticker\ndata: {\"symbol\":\"BTCUSDT\""),
+            "{}",
+            text
+        );
+    }
+}
@@ -0,0 +1,545 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::api::MAX_KLINES;
+
+/// 24h rolling statistics for a single symbol, independent of the exchange
+/// that produced them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ticker {
+    pub symbol: String,
+    pub last_price: f64,
+    pub price_change_percent: f64,
+    pub high_price: f64,
+    pub low_price: f64,
+    pub volume: f64,
+    pub open_price: f64,
+    /// Volume in the quote asset
+    pub quote_volume: f64,
+    /// Only in the full 24h ticker, not in streamed updates
+    pub weighted_avg_price: Option<f64>,
+    pub trade_count: Option<u64>,
+}
+
+/// One executed trade; `time` is in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    pub id: u64,
+    pub price: f64,
+    pub quantity: f64,
+    pub time: i64,
+    /// The resting order was the buy, so the taker sold
+    pub buyer_maker: bool,
+}
+
+/// One price level of an order book.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub price: f64,
+    pub quantity: f64,
+}
+
+/// An order book snapshot, each side best price first.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Depth {
+    /// Sequence number the snapshot is current to
+    pub last_update_id: u64,
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+}
+
+/// One OHLCV bar; `open_time` is in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    pub open_time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl Candle {
+    /// A zero-volume candle for a single price sample.
+    pub fn flat(open_time: i64, price: f64) -> Self {
+        Self {
+            open_time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: 0.0,
+        }
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.close >= self.open
+    }
+}
+
+/// Kline interval for chart history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Timeframe {
+    M1,
+    M5,
+    #[default]
+    M15,
+    H1,
+    H4,
+    D1,
+    W1,
+}
+
+impl Timeframe {
+    pub const ALL: [Timeframe; 7] = [
+        Timeframe::M1,
+        Timeframe::M5,
+        Timeframe::M15,
+        Timeframe::H1,
+        Timeframe::H4,
+        Timeframe::D1,
+        Timeframe::W1,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Timeframe::M1 => "1m",
+            Timeframe::M5 => "5m",
+            Timeframe::M15 => "15m",
+            Timeframe::H1 => "1h",
+            Timeframe::H4 => "4h",
+            Timeframe::D1 => "1d",
+            Timeframe::W1 => "1w",
+        }
+    }
+
+    pub fn duration_ms(self) -> i64 {
+        const MINUTE: i64 = 60_000;
+        match self {
+            Timeframe::M1 => MINUTE,
+            Timeframe::M5 => 5 * MINUTE,
+            Timeframe::M15 => 15 * MINUTE,
+            Timeframe::H1 => 60 * MINUTE,
+            Timeframe::H4 => 4 * 60 * MINUTE,
+            Timeframe::D1 => 24 * 60 * MINUTE,
+            Timeframe::W1 => 7 * 24 * 60 * MINUTE,
+        }
+    }
+
+    /// Open time of the candle containing `ts_ms`.
+    pub fn bucket_start(self, ts_ms: i64) -> i64 {
+        // Weekly candles open on Monday; the epoch fell on a Thursday
+        let offset = match self {
+            Timeframe::W1 => 4 * Timeframe::D1.duration_ms(),
+            _ => 0,
+        };
+        ts_ms - (ts_ms - offset).rem_euclid(self.duration_ms())
+    }
+
+    /// strftime pattern for axis labels at this resolution.
+    pub fn label_format(self) -> &'static str {
+        match self {
+            Timeframe::D1 | Timeframe::W1 => "%m-%d",
+            _ => "%H:%M",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&t| t == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn prev(self) -> Self {
+        let i = Self::ALL.iter().position(|&t| t == self).unwrap_or(0);
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for Timeframe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Timeframe {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                let valid: Vec<&str> = Self::ALL.iter().map(|t| t.as_str()).collect();
+                format!(
+                    "unknown timeframe '{}', expected one of {}",
+                    s,
+                    valid.join(", ")
+                )
+            })
+    }
+}
+
+impl TryFrom<String> for Timeframe {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Serialize for Timeframe {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Quote asset assumed when a coin is given without one.
+pub const DEFAULT_QUOTE: &str = "USDT";
+
+/// A trading pair such as BTC/USDT or ETH/BTC.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pair {
+    pub base: String,
+    pub quote: String,
+}
+
+impl Pair {
+    pub fn new(base: &str, quote: &str) -> Self {
+        Self {
+            base: base.to_uppercase(),
+            quote: quote.to_uppercase(),
+        }
+    }
+
+    /// Parses `BASE/QUOTE`, or a bare `BASE` quoted in `default_quote`.
+    pub fn parse_with_quote(s: &str, default_quote: &str) -> Result<Self, String> {
+        let (base, quote) = match s.split_once('/') {
+            Some((base, quote)) => (base.trim(), quote.trim()),
+            None => (s.trim(), default_quote.trim()),
+        };
+        for part in [base, quote] {
+            if part.is_empty() || !part.chars().all(|ch| ch.is_ascii_alphanumeric()) {
+                return Err(format!("invalid pair '{}'", s.trim()));
+            }
+        }
+        Ok(Self::new(base, quote))
+    }
+
+    /// Exchange symbol, e.g. `BTCUSDT`.
+    pub fn symbol(&self) -> String {
+        format!("{}{}", self.base, self.quote)
+    }
+
+    /// Currency sign prices in this pair are shown with, if the quote has one.
+    pub fn currency_sign(&self) -> Option<&'static str> {
+        currency_sign(&self.quote)
+    }
+}
+
+/// Sign for amounts in `quote`, for fiat currencies and their stablecoins.
+pub fn currency_sign(quote: &str) -> Option<&'static str> {
+    match quote {
+        "USD" | "USDT" | "USDC" | "FDUSD" | "BUSD" | "TUSD" | "DAI" => Some("$"),
+        "EUR" | "EURI" => Some("€"),
+        "GBP" => Some("£"),
+        "JPY" => Some("¥"),
+        "TRY" => Some("₺"),
+        _ => None,
+    }
+}
+
+impl FromStr for Pair {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with_quote(s, DEFAULT_QUOTE)
+    }
+}
+
+impl fmt::Display for Pair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.base, self.quote)
+    }
+}
+
+/// Decimals shown when the exchange has not told us a pair's tick size.
+pub const DEFAULT_PRICE_DECIMALS: usize = 2;
+
+/// A tradable symbol as listed by the exchange.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolInfo {
+    pub symbol: String,
+    pub base: String,
+    pub quote: String,
+    pub trading: bool,
+    /// Smallest price increment; 0 if the exchange does not restrict it
+    pub tick_size: f64,
+    /// Decimals the exchange uses for amounts in the quote asset
+    pub quote_precision: u32,
+}
+
+impl SymbolInfo {
+    pub fn pair(&self) -> Pair {
+        Pair::new(&self.base, &self.quote)
+    }
+
+    /// Decimals needed to show a price to one tick, e.g. 2 for a tick of 0.01.
+    pub fn price_decimals(&self) -> usize {
+        if self.tick_size > 0.0 {
+            // Nudge down so 0.01 (stored as 0.01000...0002) still counts as 2
+            (-self.tick_size.log10() - 1e-9).ceil().max(0.0) as usize
+        } else {
+            self.quote_precision as usize
+        }
+    }
+}
+
+/// Request budget as last reported by a source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub used_weight: u32,
+    pub weight_limit: u32,
+    /// Set while the source is backing off after being rate limited
+    pub resume_at: Option<Instant>,
+}
+
+impl RateLimit {
+    /// Time left until requests resume, if still limited at `now`.
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.resume_at
+            .filter(|at| *at > now)
+            .map(|at| at.duration_since(now))
+    }
+}
+
+/// Anything that can feed the dashboard with prices.
+///
+/// `App` only talks to this trait, so a different venue (or a fake in tests)
+/// can be swapped in without touching the rendering or update logic.
+#[async_trait]
+pub trait MarketDataSource: Send + Sync {
+    async fn ticker(&self, symbol: &str) -> Result<Ticker>;
+
+    /// The most recent `limit` klines at `timeframe`, oldest first.
+    async fn klines(&self, symbol: &str, timeframe: Timeframe, limit: u32) -> Result<Vec<Candle>>;
+
+    /// Up to `limit` klines opening at or after `start_ms`, oldest first.
+    /// Sources that can't page back in time return the most recent ones.
+    async fn klines_since(
+        &self,
+        symbol: &str,
+        timeframe: Timeframe,
+        _start_ms: i64,
+        limit: u32,
+    ) -> Result<Vec<Candle>> {
+        self.klines(symbol, timeframe, limit).await
+    }
+
+    /// Every kline opening in `start_ms..end_ms`, oldest first, paging
+    /// through `klines_since` past the per-request limit.
+    async fn klines_range(
+        &self,
+        symbol: &str,
+        timeframe: Timeframe,
+        start_ms: i64,
+        end_ms: i64,
+    ) -> Result<Vec<Candle>> {
+        let step = timeframe.duration_ms();
+        let mut candles = Vec::new();
+        let mut start = start_ms;
+        while start < end_ms {
+            let limit = ((end_ms - start - 1) / step + 1).min(MAX_KLINES as i64) as u32;
+            let page = self.klines_since(symbol, timeframe, start, limit).await?;
+            let full = page.len() == limit as usize;
+            let Some(last) = page.last().map(|c| c.open_time) else {
+                break;
+            };
+            // Sources that can't page back repeat the latest klines
+            let newest = candles.last().map(|c: &Candle| c.open_time);
+            candles.extend(
+                page.into_iter()
+                    .filter(|c| c.open_time < end_ms && newest.is_none_or(|t| c.open_time > t)),
+            );
+            if !full || last < start {
+                break;
+            }
+            start = last + step;
+        }
+        Ok(candles)
+    }
+
+    async fn symbols(&self) -> Result<Vec<SymbolInfo>>;
+
+    /// The most recent `limit` trades, newest first. Sources without a
+    /// trade feed have none.
+    async fn recent_trades(&self, _symbol: &str, _limit: u32) -> Result<Vec<Trade>> {
+        Ok(Vec::new())
+    }
+
+    /// The top `limit` order book levels per side. Sources without an
+    /// order book return an empty one.
+    async fn depth(&self, _symbol: &str, _limit: u32) -> Result<Depth> {
+        Ok(Depth::default())
+    }
+
+    /// Current request budget, for sources that are rate limited.
+    fn rate_limit(&self) -> Option<RateLimit> {
+        None
+    }
+
+    /// Tickers for `symbols` in no particular order; symbols the source has
+    /// no data for are left out. Sources with a batch endpoint should
+    /// override the default of one `ticker` call per symbol.
+    async fn tickers(&self, symbols: &[String]) -> Result<Vec<Ticker>> {
+        let results = futures::future::join_all(symbols.iter().map(|s| self.ticker(s))).await;
+        let mut tickers = Vec::with_capacity(results.len());
+        let mut first_error = None;
+        for result in results {
+            match result {
+                Ok(ticker) => tickers.push(ticker),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            // Only a total failure is an error; gaps show up as missing symbols
+            Some(e) if tickers.is_empty() => Err(e),
+            _ => Ok(tickers),
+        }
+    }
+
+    async fn klines_batch(
+        &self,
+        symbols: &[String],
+        timeframe: Timeframe,
+        limit: u32,
+    ) -> Vec<Result<Vec<Candle>>> {
+        futures::future::join_all(symbols.iter().map(|s| self.klines(s, timeframe, limit))).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeframe_parse_roundtrip() {
+        for tf in Timeframe::ALL {
+            assert_eq!(tf.as_str().parse::<Timeframe>(), Ok(tf));
+        }
+        assert_eq!("1H".parse::<Timeframe>(), Ok(Timeframe::H1));
+        assert!("2m".parse::<Timeframe>().is_err());
+    }
+
+    #[test]
+    fn test_timeframe_cycle() {
+        assert_eq!(Timeframe::M15.next(), Timeframe::H1);
+        assert_eq!(Timeframe::W1.next(), Timeframe::M1);
+        assert_eq!(Timeframe::M1.prev(), Timeframe::W1);
+    }
+
+    #[test]
+    fn test_timeframe_bucket_start() {
+        // 2024-01-03 (Wednesday) 10:37:12 UTC
+        let ts = 1_704_278_232_000;
+        assert_eq!(Timeframe::M15.bucket_start(ts), 1_704_277_800_000);
+        assert_eq!(Timeframe::D1.bucket_start(ts), 1_704_240_000_000);
+        // Monday 2024-01-01 00:00 UTC
+        assert_eq!(Timeframe::W1.bucket_start(ts), 1_704_067_200_000);
+    }
+
+    #[test]
+    fn test_parse_pairs() {
+        let pair: Pair = "eth/btc".parse().unwrap();
+        assert_eq!(pair, Pair::new("ETH", "BTC"));
+        assert_eq!(pair.symbol(), "ETHBTC");
+        assert_eq!(pair.to_string(), "ETH/BTC");
+
+        assert_eq!("SOL".parse(), Ok(Pair::new("SOL", "USDT")));
+        assert_eq!(
+            Pair::parse_with_quote(" btc ", "eur"),
+            Ok(Pair::new("BTC", "EUR"))
+        );
+        assert!("BTC/".parse::<Pair>().is_err());
+        assert!("/USDT".parse::<Pair>().is_err());
+        assert!("BTC-USDT".parse::<Pair>().is_err());
+    }
+
+    /// One-minute klines from time 0 to `self.0`, served a page at a time.
+    struct Paged(i64);
+
+    #[async_trait]
+    impl MarketDataSource for Paged {
+        async fn ticker(&self, symbol: &str) -> Result<Ticker> {
+            Err(anyhow::anyhow!("no ticker for {}", symbol))
+        }
+
+        async fn klines(
+            &self,
+            symbol: &str,
+            timeframe: Timeframe,
+            limit: u32,
+        ) -> Result<Vec<Candle>> {
+            self.klines_since(symbol, timeframe, 0, limit).await
+        }
+
+        async fn klines_since(
+            &self,
+            _symbol: &str,
+            _timeframe: Timeframe,
+            start_ms: i64,
+            limit: u32,
+        ) -> Result<Vec<Candle>> {
+            Ok((start_ms / 60_000..self.0 / 60_000)
+                .take(limit as usize)
+                .map(|i| Candle::flat(i * 60_000, i as f64))
+                .collect())
+        }
+
+        async fn symbols(&self) -> Result<Vec<SymbolInfo>> {
+            Ok(vec![])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_klines_range_pages() {
+        let source = Paged(3_000 * 60_000);
+        let range = |start, end| source.klines_range("BTCUSDT", Timeframe::M1, start, end);
+
+        let candles = range(60_000, 2_500 * 60_000).await.unwrap();
+        assert_eq!(candles.len(), 2_499);
+        assert_eq!(candles.first().map(|c| c.open_time), Some(60_000));
+        assert_eq!(candles.last().map(|c| c.open_time), Some(2_499 * 60_000));
+        assert!(candles
+            .windows(2)
+            .all(|w| w[1].open_time - w[0].open_time == 60_000));
+
+        // Stops where the source runs out
+        assert_eq!(range(0, 10_000 * 60_000).await.unwrap().len(), 3_000);
+        assert!(range(5, 5).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_price_decimals_from_tick_size() {
+        let info = |tick_size: f64| SymbolInfo {
+            symbol: "ETHBTC".to_string(),
+            base: "ETH".to_string(),
+            quote: "BTC".to_string(),
+            trading: true,
+            tick_size,
+            quote_precision: 8,
+        };
+        assert_eq!(info(0.01).price_decimals(), 2);
+        assert_eq!(info(0.00001).price_decimals(), 5);
+        assert_eq!(info(0.05).price_decimals(), 2);
+        assert_eq!(info(1.0).price_decimals(), 0);
+        assert_eq!(info(0.0).price_decimals(), 8);
+    }
+}
@@ -0,0 +1,459 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Result;
+use ratatui::style::Color;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::app::{App, CoinData};
+use crate::source::MarketDataSource;
+use crate::ui::{format_price, format_price_short, format_volume, Theme, SYNTHWAVE};
+
+pub const DEFAULT_TEMPLATE: &str = "{base} {price_short} {arrow}{change}%";
+
+/// Shown in place of prices until the first successful fetch.
+const UNAVAILABLE: &str = "prices unavailable";
+
+/// How each update is written for the bar reading it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// One line of text
+    Plain,
+    /// Text with `#[fg=…]` color markers for `status-right`
+    Tmux,
+    /// The i3bar JSON protocol, one block per coin (also read by swaybar)
+    I3bar,
+    /// Waybar custom module JSON with Pango colors and an up/down class
+    Waybar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Base,
+    Quote,
+    Pair,
+    Price,
+    PriceShort,
+    Change,
+    Sign,
+    Arrow,
+    High,
+    Low,
+    Volume,
+}
+
+const FIELDS: [(&str, Field); 11] = [
+    ("base", Field::Base),
+    ("quote", Field::Quote),
+    ("pair", Field::Pair),
+    ("price", Field::Price),
+    ("price_short", Field::PriceShort),
+    ("change", Field::Change),
+    ("sign", Field::Sign),
+    ("arrow", Field::Arrow),
+    ("high", Field::High),
+    ("low", Field::Low),
+    ("volume", Field::Volume),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+/// Per-coin text such as `{base} {price_short} {arrow}{change}%`. `{change}`
+/// is the unsigned 24h change, so pair it with `{arrow}` or `{sign}`; `{{`
+/// and `}}` are literal braces.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn render(&self, coin: &CoinData) -> String {
+        let up = coin.change_24h >= 0.0;
+        let mut text = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(literal) => text.push_str(literal),
+                Part::Field(field) => text.push_str(&match field {
+                    Field::Base => coin.pair.base.clone(),
+                    Field::Quote => coin.pair.quote.clone(),
+                    Field::Pair => coin.pair.to_string(),
+                    Field::Price => format_price(coin.price, coin),
+                    Field::PriceShort => format_price_short(coin.price, coin),
+                    Field::Change => format!("{:.2}", coin.change_24h.abs()),
+                    Field::Sign => if up { "+" } else { "-" }.to_string(),
+                    Field::Arrow => if up { "▲" } else { "▼" }.to_string(),
+                    Field::High => format_price(coin.high_24h, coin),
+                    Field::Low => format_price(coin.low_24h, coin),
+                    Field::Volume => format_volume(coin.volume_24h),
+                }),
+            }
+        }
+        text
+    }
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        DEFAULT_TEMPLATE.parse().expect("default template is valid")
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unclosed '{{{}' in template", name)),
+                        }
+                    }
+                    let field = FIELDS
+                        .iter()
+                        .find(|(n, _)| *n == name)
+                        .map(|&(_, field)| field)
+                        .ok_or_else(|| {
+                            let names: Vec<&str> = FIELDS.iter().map(|(n, _)| *n).collect();
+                            format!(
+                                "unknown placeholder '{{{}}}', expected one of {}",
+                                name,
+                                names.join(", ")
+                            )
+                        })?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => return Err("unmatched '}' in template, write '}}' for a brace".into()),
+                _ => literal.push(ch),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Text(literal));
+        }
+        Ok(Self {
+            source: s.to_string(),
+            parts,
+        })
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Writes coins as status bar updates in one `Format`.
+pub struct Statusline {
+    template: Template,
+    separator: String,
+    format: Format,
+    /// `#rrggbb` for rising and falling coins
+    positive: String,
+    negative: String,
+    /// Whether the i3bar header and opening bracket went out
+    started: bool,
+}
+
+impl Statusline {
+    /// Colors come from the theme's positive and negative roles; named ANSI
+    /// colors have no fixed value, so those fall back to the synthwave ones.
+    pub fn new(template: Template, separator: &str, format: Format, theme: &Theme) -> Self {
+        Self {
+            template,
+            separator: separator.to_string(),
+            format,
+            positive: hex(theme.positive).unwrap_or_else(|| hex(SYNTHWAVE.positive).unwrap()),
+            negative: hex(theme.negative).unwrap_or_else(|| hex(SYNTHWAVE.negative).unwrap()),
+            started: false,
+        }
+    }
+
+    /// One update with every coin, ending in a newline.
+    pub fn render(&mut self, coins: &[&CoinData]) -> String {
+        let segments: Vec<(String, String)> = coins
+            .iter()
+            .map(|coin| {
+                let color = if coin.change_24h >= 0.0 {
+                    &self.positive
+                } else {
+                    &self.negative
+                };
+                (self.template.render(coin), color.clone())
+            })
+            .collect();
+        let text = match self.format {
+            Format::Plain => join(
+                segments.iter().map(|(text, _)| text.clone()),
+                &self.separator,
+            ),
+            Format::Tmux => join(
+                segments
+                    .iter()
+                    .map(|(text, color)| format!("#[fg={}]{}#[default]", color, text)),
+                &self.separator,
+            ),
+            Format::I3bar => {
+                let blocks = coins
+                    .iter()
+                    .zip(&segments)
+                    .map(|(coin, (text, color))| {
+                        json!({
+                            "name": "cryptowatcher",
+                            "instance": coin.symbol,
+                            "full_text": text,
+                            "color": color,
+                        })
+                    })
+                    .collect();
+                return self.i3bar_update(Value::Array(blocks));
+            }
+            Format::Waybar => {
+                let markup = segments.iter().map(|(text, color)| {
+                    format!("<span color=\"{}\">{}</span>", color, escape_markup(text))
+                });
+                let tooltip: Vec<String> = coins
+                    .iter()
+                    .map(|c| {
+                        format!(
+                            "{} {} {:+.2}%",
+                            c.pair,
+                            format_price(c.price, c),
+                            c.change_24h
+                        )
+                    })
+                    .collect();
+                let rising = coins.iter().filter(|c| c.change_24h >= 0.0).count();
+                let class = if rising == coins.len() {
+                    "up"
+                } else if rising == 0 {
+                    "down"
+                } else {
+                    "mixed"
+                };
+                json!({
+                    "text": join(markup, &self.separator),
+                    "tooltip": escape_markup(&tooltip.join("\n")),
+                    "class": class,
+                })
+                .to_string()
+            }
+        };
+        format!("{}\n", text)
+    }
+
+    /// Update shown before any prices arrived; `message` is the reason,
+    /// kept to the tooltip or an urgent block where the bar supports them.
+    pub fn render_error(&mut self, message: &str) -> String {
+        let text = match self.format {
+            Format::Plain => UNAVAILABLE.to_string(),
+            Format::Tmux => format!("#[fg={}]{}#[default]", self.negative, UNAVAILABLE),
+            Format::I3bar => {
+                let block = json!({
+                    "name": "cryptowatcher",
+                    "full_text": UNAVAILABLE,
+                    "color": self.negative,
+                    "urgent": true,
+                });
+                return self.i3bar_update(json!([block]));
+            }
+            Format::Waybar => json!({
+                "text": UNAVAILABLE,
+                "tooltip": escape_markup(message),
+                "class": "error",
+            })
+            .to_string(),
+        };
+        format!("{}\n", text)
+    }
+
+    /// i3bar reads a header and then one endless array of block lists.
+    fn i3bar_update(&mut self, blocks: Value) -> String {
+        if self.started {
+            format!(",{}\n", blocks)
+        } else {
+            self.started = true;
+            format!("{{\"version\":1}}\n[\n{}\n", blocks)
+        }
+    }
+}
+
+/// Fetches prices through `App::fetch_prices` and writes an update, once
+/// or every `interval` until stdout closes. A single update fails when no
+/// prices could be fetched; after that a failed refresh repeats the last
+/// prices.
+pub async fn run(
+    app: &mut App,
+    source: &dyn MarketDataSource,
+    statusline: &mut Statusline,
+    interval: Option<Duration>,
+) -> Result<()> {
+    let mut out = io::stdout();
+    let mut ticks = interval.map(tokio::time::interval);
+    loop {
+        if let Some(ticks) = &mut ticks {
+            ticks.tick().await;
+        }
+        let fetched = app.fetch_prices(source).await;
+        // Coins the exchange had no ticker for have never had a price
+        let coins: Vec<&CoinData> = app.coins.iter().filter(|c| c.price > 0.0).collect();
+        let text = if coins.is_empty() {
+            statusline.render_error(&app.status_message)
+        } else {
+            statusline.render(&coins)
+        };
+        match out.write_all(text.as_bytes()).and_then(|_| out.flush()) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+        if interval.is_none() {
+            if !fetched {
+                let message = &app.status_message;
+                anyhow::bail!("{}", message.strip_prefix("Error ").unwrap_or(message));
+            }
+            return Ok(());
+        }
+    }
+}
+
+fn join(parts: impl Iterator<Item = String>, separator: &str) -> String {
+    parts.collect::<Vec<_>>().join(separator)
+}
+
+fn hex(color: Color) -> Option<String> {
+    match color {
+        Color::Rgb(r, g, b) => Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        _ => None,
+    }
+}
+
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::CLASSIC;
+
+    fn statusline(format: Format) -> Statusline {
+        Statusline::new(Template::default(), " | ", format, &SYNTHWAVE)
+    }
+
+    #[test]
+    fn test_template() {
+        let btc = CoinData::quoted("BTC", 42069.42, 1.234);
+        let eth = CoinData::quoted("ETH", 3210.0, -0.5);
+        let template = Template::default();
+        assert_eq!(template.render(&btc), "BTC $42.1k ▲1.23%");
+        assert_eq!(template.render(&eth), "ETH $3.2k ▼0.50%");
+
+        let template: Template = "{{{pair}}} {price} {sign}{change}".parse().unwrap();
+        assert_eq!(template.render(&eth), "{ETH/USDT} $3,210.00 -0.50");
+        assert_eq!(template.to_string(), "{{{pair}}} {price} {sign}{change}");
+
+        assert!("{base} {bogus}"
+            .parse::<Template>()
+            .unwrap_err()
+            .contains("unknown placeholder '{bogus}'"));
+        assert!("{base".parse::<Template>().is_err());
+        assert!("base}".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn test_plain_and_tmux() {
+        let btc = CoinData::quoted("BTC", 42069.42, 1.234);
+        let eth = CoinData::quoted("ETH", 3210.0, -0.5);
+        assert_eq!(
+            statusline(Format::Plain).render(&[&btc, &eth]),
+            "BTC $42.1k ▲1.23% | ETH $3.2k ▼0.50%\n"
+        );
+        assert_eq!(
+            statusline(Format::Tmux).render(&[&btc, &eth]),
+            "#[fg=#39ff14]BTC $42.1k ▲1.23%#[default] | #[fg=#ff2e97]ETH $3.2k ▼0.50%#[default]\n"
+        );
+    }
+
+    #[test]
+    fn test_i3bar_protocol() {
+        let btc = CoinData::quoted("BTC", 42069.42, 1.234);
+        let mut statusline = statusline(Format::I3bar);
+        let first = statusline.render(&[&btc]);
+        let mut lines = first.lines();
+        assert_eq!(lines.next(), Some("{\"version\":1}"));
+        assert_eq!(lines.next(), Some("["));
+        let blocks: Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(blocks[0]["full_text"], "BTC $42.1k ▲1.23%");
+        assert_eq!(blocks[0]["color"], "#39ff14");
+        assert_eq!(blocks[0]["instance"], "BTCUSDT");
+
+        let next = statusline.render_error("timed out");
+        assert!(next.starts_with(",["));
+        let blocks: Value = serde_json::from_str(&next[1..]).unwrap();
+        assert_eq!(blocks[0]["urgent"], true);
+    }
+
+    #[test]
+    fn test_waybar() {
+        let btc = CoinData::quoted("BTC", 42069.42, 1.234);
+        let eth = CoinData::quoted("ETH", 3210.0, -0.5);
+        // Named ANSI colors have no hex value to hand out
+        let mut statusline = Statusline::new(
+            "{pair} <{arrow}>".parse().unwrap(),
+            " ",
+            Format::Waybar,
+            &CLASSIC,
+        );
+        let update: Value = serde_json::from_str(&statusline.render(&[&btc, &eth])).unwrap();
+        assert_eq!(
+            update["text"],
+            "<span color=\"#39ff14\">BTC/USDT &lt;▲&gt;</span> \
+             <span color=\"#ff2e97\">ETH/USDT &lt;▼&gt;</span>"
+        );
+        assert_eq!(
+            update["tooltip"],
+            "BTC/USDT $42,069.42 +1.23%\nETH/USDT $3,210.00 -0.50%"
+        );
+        assert_eq!(update["class"], "mixed");
+
+        let update: Value = serde_json::from_str(&statusline.render(&[&btc])).unwrap();
+        assert_eq!(update["class"], "up");
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::api::MAX_KLINES;
+use crate::source::{Candle, Timeframe};
+
+/// Most candles fetched per symbol to fill a gap; anything older is left
+/// missing so a long absence doesn't cost dozens of requests.
+pub const MAX_BACKFILL: i64 = 5 * MAX_KLINES as i64;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS candles (
+    symbol TEXT NOT NULL,
+    timeframe TEXT NOT NULL,
+    open_time INTEGER NOT NULL,
+    open REAL NOT NULL,
+    high REAL NOT NULL,
+    low REAL NOT NULL,
+    close REAL NOT NULL,
+    volume REAL NOT NULL,
+    PRIMARY KEY (symbol, timeframe, open_time)
+) WITHOUT ROWID;
+CREATE TABLE IF NOT EXISTS ticks (
+    symbol TEXT NOT NULL,
+    time INTEGER NOT NULL,
+    price REAL NOT NULL,
+    PRIMARY KEY (symbol, time)
+) WITHOUT ROWID;
+";
+
+/// `$XDG_DATA_HOME/cryptowatcher/history.db` or the platform equivalent.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("cryptowatcher").join("history.db"))
+}
+
+/// Price history kept on disk: every candle seen per symbol and timeframe,
+/// plus each polled or streamed price.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("cannot create directory {}", dir.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("cannot open history database {}", path.display()))?;
+        // Ticks are written every few seconds; WAL keeps that cheap
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        Self::init(conn)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Inserts candles, replacing any stored with the same open time.
+    pub fn save_candles(
+        &mut self,
+        symbol: &str,
+        timeframe: Timeframe,
+        candles: &[Candle],
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT OR REPLACE INTO candles
+                 (symbol, timeframe, open_time, open, high, low, close, volume)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for c in candles {
+                insert.execute(params![
+                    symbol,
+                    timeframe.as_str(),
+                    c.open_time,
+                    c.open,
+                    c.high,
+                    c.low,
+                    c.close,
+                    c.volume
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn save_tick(&self, symbol: &str, time: i64, price: f64) -> Result<()> {
+        self.conn
+            .prepare_cached(
+                "INSERT OR REPLACE INTO ticks (symbol, time, price) VALUES (?1, ?2, ?3)",
+            )?
+            .execute(params![symbol, time, price])?;
+        Ok(())
+    }
+
+    /// The latest `limit` stored candles, oldest first.
+    pub fn candles(&self, symbol: &str, timeframe: Timeframe, limit: usize) -> Result<Vec<Candle>> {
+        let mut select = self.conn.prepare_cached(
+            "SELECT open_time, open, high, low, close, volume FROM candles
+             WHERE symbol = ?1 AND timeframe = ?2
+             ORDER BY open_time DESC LIMIT ?3",
+        )?;
+        let rows = select.query_map(params![symbol, timeframe.as_str(), limit as i64], |row| {
+            Ok(Candle {
+                open_time: row.get(0)?,
+                open: row.get(1)?,
+                high: row.get(2)?,
+                low: row.get(3)?,
+                close: row.get(4)?,
+                volume: row.get(5)?,
+            })
+        })?;
+        let mut candles = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        candles.reverse();
+        Ok(candles)
+    }
+
+    pub fn last_open_time(&self, symbol: &str, timeframe: Timeframe) -> Result<Option<i64>> {
+        let last = self
+            .conn
+            .prepare_cached(
+                "SELECT MAX(open_time) FROM candles WHERE symbol = ?1 AND timeframe = ?2",
+            )?
+            .query_row(params![symbol, timeframe.as_str()], |row| row.get(0))
+            .optional()?;
+        Ok(last.flatten())
+    }
+
+    /// Deletes candles older than their timeframe's retention and ticks
+    /// older than `tick_retention`; timeframes without a limit are kept.
+    /// Returns how many rows went.
+    pub fn prune(
+        &self,
+        retention: &HashMap<Timeframe, Duration>,
+        tick_retention: Duration,
+        now_ms: i64,
+    ) -> Result<usize> {
+        let cutoff = |keep: Duration| now_ms - keep.as_millis() as i64;
+        let mut removed = 0;
+        for (timeframe, keep) in retention {
+            removed += self.conn.execute(
+                "DELETE FROM candles WHERE timeframe = ?1 AND open_time < ?2",
+                params![timeframe.as_str(), cutoff(*keep)],
+            )?;
+        }
+        removed += self.conn.execute(
+            "DELETE FROM ticks WHERE time < ?1",
+            params![cutoff(tick_retention)],
+        )?;
+        Ok(removed)
+    }
+}
+
+/// Where fetching should resume: the last stored candle, which may have
+/// been saved while still open, or enough to fill a chart of `history`
+/// candles when nothing is stored. Never further back than `MAX_BACKFILL`.
+pub fn backfill_start(
+    last_open_time: Option<i64>,
+    timeframe: Timeframe,
+    history: usize,
+    now_ms: i64,
+) -> i64 {
+    let current = timeframe.bucket_start(now_ms);
+    let step = timeframe.duration_ms();
+    let earliest = current - (MAX_BACKFILL - 1) * step;
+    let start = last_open_time.unwrap_or(current - (history as i64 - 1) * step);
+    start.max(earliest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(open_time: i64, close: f64) -> Candle {
+        Candle {
+            open_time,
+            open: close - 1.0,
+            high: close + 1.0,
+            low: close - 2.0,
+            close,
+            volume: 3.0,
+        }
+    }
+
+    #[test]
+    fn test_candles_roundtrip_and_replace() {
+        let mut store = Store::open_in_memory().unwrap();
+        assert_eq!(
+            store.last_open_time("BTCUSDT", Timeframe::M1).unwrap(),
+            None
+        );
+
+        let candles: Vec<Candle> = (1..=5).map(|i| candle(i * 60_000, i as f64)).collect();
+        store
+            .save_candles("BTCUSDT", Timeframe::M1, &candles)
+            .unwrap();
+        store
+            .save_candles("BTCUSDT", Timeframe::M1, &[candle(300_000, 50.0)])
+            .unwrap();
+        store
+            .save_candles("ETHUSDT", Timeframe::M1, &[candle(360_000, 1.0)])
+            .unwrap();
+
+        let latest = store.candles("BTCUSDT", Timeframe::M1, 2).unwrap();
+        assert_eq!(latest, vec![candle(240_000, 4.0), candle(300_000, 50.0)]);
+        assert_eq!(
+            store.last_open_time("BTCUSDT", Timeframe::M1).unwrap(),
+            Some(300_000)
+        );
+        assert!(store
+            .candles("BTCUSDT", Timeframe::H1, 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_prune_by_timeframe() {
+        let day = Timeframe::D1.duration_ms();
+        let mut store = Store::open_in_memory().unwrap();
+        store
+            .save_candles(
+                "BTCUSDT",
+                Timeframe::M1,
+                &[candle(0, 1.0), candle(9 * day, 2.0)],
+            )
+            .unwrap();
+        store
+            .save_candles("BTCUSDT", Timeframe::D1, &[candle(0, 1.0)])
+            .unwrap();
+        store.save_tick("BTCUSDT", 0, 1.0).unwrap();
+        store.save_tick("BTCUSDT", 10 * day, 2.0).unwrap();
+
+        let retention = HashMap::from([(Timeframe::M1, Duration::from_secs(7 * 86_400))]);
+        let removed = store
+            .prune(&retention, Duration::from_secs(86_400), 10 * day)
+            .unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!(
+            store.candles("BTCUSDT", Timeframe::M1, 10).unwrap().len(),
+            1
+        );
+        assert_eq!(
+            store.candles("BTCUSDT", Timeframe::D1, 10).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_backfill_start() {
+        let tf = Timeframe::M1;
+        let now = 1_000 * tf.duration_ms() + 30_000;
+        // Nothing stored: one chart's worth, ending with the open candle
+        assert_eq!(backfill_start(None, tf, 60, now), 941 * tf.duration_ms());
+        assert_eq!(
+            backfill_start(Some(990 * tf.duration_ms()), tf, 60, now),
+            990 * tf.duration_ms()
+        );
+        // Long gaps only fill the most recent part
+        let far = 10_000 * tf.duration_ms() + 30_000;
+        assert_eq!(
+            backfill_start(Some(0), tf, 60, far),
+            (10_000 - MAX_BACKFILL + 1) * tf.duration_ms()
+        );
+    }
+}
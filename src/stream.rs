@@ -0,0 +1,485 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::sync::{mpsc, watch};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use crate::api::{deserialize_f64, deserialize_levels};
+use crate::book::DepthUpdate;
+use crate::event::AppEvent;
+use crate::source::{Candle, Level, Ticker, Timeframe, Trade};
+
+const STREAM_URL: &str = "wss://stream.binance.com:9443/stream";
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    Connected,
+    Disconnected(String),
+    Ticker(Ticker),
+    Kline {
+        symbol: String,
+        timeframe: Timeframe,
+        candle: Candle,
+    },
+    Depth(DepthUpdate),
+    Trade {
+        symbol: String,
+        trade: Trade,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    data: StreamData,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "e")]
+enum StreamData {
+    #[serde(rename = "24hrMiniTicker")]
+    MiniTicker {
+        #[serde(rename = "s")]
+        symbol: String,
+        #[serde(rename = "c", deserialize_with = "deserialize_f64")]
+        close: f64,
+        #[serde(rename = "o", deserialize_with = "deserialize_f64")]
+        open: f64,
+        #[serde(rename = "h", deserialize_with = "deserialize_f64")]
+        high: f64,
+        #[serde(rename = "l", deserialize_with = "deserialize_f64")]
+        low: f64,
+        #[serde(rename = "v", deserialize_with = "deserialize_f64")]
+        volume: f64,
+        #[serde(rename = "q", deserialize_with = "deserialize_f64")]
+        quote_volume: f64,
+    },
+    #[serde(rename = "kline")]
+    Kline {
+        #[serde(rename = "s")]
+        symbol: String,
+        #[serde(rename = "k")]
+        kline: KlineData,
+    },
+    #[serde(rename = "depthUpdate")]
+    DepthUpdate {
+        #[serde(rename = "s")]
+        symbol: String,
+        #[serde(rename = "U")]
+        first_update_id: u64,
+        #[serde(rename = "u")]
+        final_update_id: u64,
+        #[serde(rename = "b", deserialize_with = "deserialize_levels")]
+        bids: Vec<Level>,
+        #[serde(rename = "a", deserialize_with = "deserialize_levels")]
+        asks: Vec<Level>,
+    },
+    #[serde(rename = "aggTrade")]
+    AggTrade {
+        #[serde(rename = "s")]
+        symbol: String,
+        #[serde(rename = "a")]
+        id: u64,
+        #[serde(rename = "p", deserialize_with = "deserialize_f64")]
+        price: f64,
+        #[serde(rename = "q", deserialize_with = "deserialize_f64")]
+        quantity: f64,
+        #[serde(rename = "T")]
+        time: i64,
+        #[serde(rename = "m")]
+        buyer_maker: bool,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct KlineData {
+    #[serde(rename = "t")]
+    open_time: i64,
+    #[serde(rename = "i")]
+    interval: String,
+    #[serde(rename = "o", deserialize_with = "deserialize_f64")]
+    open: f64,
+    #[serde(rename = "h", deserialize_with = "deserialize_f64")]
+    high: f64,
+    #[serde(rename = "l", deserialize_with = "deserialize_f64")]
+    low: f64,
+    #[serde(rename = "c", deserialize_with = "deserialize_f64")]
+    close: f64,
+    #[serde(rename = "v", deserialize_with = "deserialize_f64")]
+    volume: f64,
+}
+
+/// What the stream is subscribed to; each part can change while connected.
+pub struct Subscriptions {
+    pub symbols: watch::Receiver<Vec<String>>,
+    pub timeframe: watch::Receiver<Timeframe>,
+    /// Symbol whose order book diffs are wanted, if any
+    pub depth: watch::Receiver<Option<String>>,
+    /// Symbols whose trades are wanted
+    pub trades: watch::Receiver<Vec<String>>,
+}
+
+impl Subscriptions {
+    fn streams(&mut self) -> Vec<String> {
+        streams(
+            &self.symbols.borrow_and_update(),
+            *self.timeframe.borrow_and_update(),
+            self.depth.borrow_and_update().as_deref(),
+            &self.trades.borrow_and_update(),
+        )
+    }
+
+    /// Waits for any part to change; errors once the sender is gone.
+    async fn changed(&mut self) -> Result<(), watch::error::RecvError> {
+        tokio::select! {
+            changed = self.symbols.changed() => changed,
+            changed = self.timeframe.changed() => changed,
+            changed = self.depth.changed() => changed,
+            changed = self.trades.changed() => changed,
+        }
+    }
+}
+
+/// Spawns the streaming task, forwarding updates as `AppEvent::Stream`
+/// until the receiving side goes away.
+pub fn spawn(subscriptions: Subscriptions, tx: mpsc::UnboundedSender<AppEvent>) {
+    tokio::spawn(async move {
+        let mut subscriptions = subscriptions;
+        let mut backoff = INITIAL_BACKOFF;
+
+        loop {
+            let mut connected = false;
+            let reason = match run_session(&mut subscriptions, &tx, &mut connected).await {
+                Ok(()) => "closed by server".to_string(),
+                Err(e) => e.to_string(),
+            };
+            if tx.is_closed() {
+                break;
+            }
+            if tx
+                .send(AppEvent::Stream(StreamEvent::Disconnected(reason)))
+                .is_err()
+            {
+                break;
+            }
+
+            if connected {
+                backoff = INITIAL_BACKOFF;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+}
+
+async fn run_session(
+    subscriptions: &mut Subscriptions,
+    tx: &mpsc::UnboundedSender<AppEvent>,
+    connected: &mut bool,
+) -> Result<()> {
+    let mut current = subscriptions.streams();
+    let (mut socket, _) = connect_async(stream_url(&current)).await?;
+    *connected = true;
+    if tx.send(AppEvent::Stream(StreamEvent::Connected)).is_err() {
+        return Ok(());
+    }
+
+    let mut request_id = 0u64;
+    loop {
+        tokio::select! {
+            msg = socket.next() => {
+                let Some(msg) = msg else {
+                    return Ok(());
+                };
+                match msg? {
+                    Message::Text(text) => {
+                        if let Some(event) = parse_message(&text) {
+                            if tx.send(AppEvent::Stream(event)).is_err() {
+                                return Ok(());
+                            }
+                        }
+                    }
+                    Message::Close(frame) => {
+                        return Err(anyhow!(
+                            "closed: {}",
+                            frame.map(|f| f.reason.to_string()).unwrap_or_default()
+                        ));
+                    }
+                    // tungstenite answers pings on its own
+                    _ => {}
+                }
+            }
+            changed = subscriptions.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+
+        // A watch changed: move the subscriptions over
+        let next = subscriptions.streams();
+        for (method, params) in [
+            ("UNSUBSCRIBE", missing_from(&current, &next)),
+            ("SUBSCRIBE", missing_from(&next, &current)),
+        ] {
+            if params.is_empty() {
+                continue;
+            }
+            request_id += 1;
+            let request = serde_json::json!({
+                "method": method,
+                "params": params,
+                "id": request_id,
+            });
+            socket.send(Message::Text(request.to_string())).await?;
+        }
+        current = next;
+    }
+}
+
+/// Ticker and kline stream names for every symbol, plus order book diffs
+/// for `depth` and aggregate trades for `trades`.
+fn streams(
+    symbols: &[String],
+    timeframe: Timeframe,
+    depth: Option<&str>,
+    trades: &[String],
+) -> Vec<String> {
+    let tickers = symbols
+        .iter()
+        .map(|s| format!("{}@miniTicker", s.to_lowercase()));
+    let klines = symbols
+        .iter()
+        .map(|s| format!("{}@kline_{}", s.to_lowercase(), timeframe));
+    let depth = depth.map(|s| format!("{}@depth@100ms", s.to_lowercase()));
+    let trades = trades
+        .iter()
+        .map(|s| format!("{}@aggTrade", s.to_lowercase()));
+    tickers.chain(klines).chain(depth).chain(trades).collect()
+}
+
+/// Streams in `streams` that `other` does not have.
+fn missing_from<'a>(streams: &'a [String], other: &[String]) -> Vec<&'a String> {
+    streams.iter().filter(|s| !other.contains(s)).collect()
+}
+
+fn stream_url(streams: &[String]) -> String {
+    format!("{}?streams={}", STREAM_URL, streams.join("/"))
+}
+
+fn parse_message(text: &str) -> Option<StreamEvent> {
+    let envelope: Envelope = serde_json::from_str(text).ok()?;
+    let event = match envelope.data {
+        StreamData::MiniTicker {
+            symbol,
+            close,
+            open,
+            high,
+            low,
+            volume,
+            quote_volume,
+        } => {
+            // miniTicker has no change field, derive it from the 24h open
+            let price_change_percent = if open > 0.0 {
+                (close - open) / open * 100.0
+            } else {
+                0.0
+            };
+            StreamEvent::Ticker(Ticker {
+                symbol,
+                last_price: close,
+                price_change_percent,
+                high_price: high,
+                low_price: low,
+                volume,
+                open_price: open,
+                quote_volume,
+                weighted_avg_price: None,
+                trade_count: None,
+            })
+        }
+        StreamData::Kline { symbol, kline } => StreamEvent::Kline {
+            symbol,
+            timeframe: kline.interval.parse().ok()?,
+            candle: Candle {
+                open_time: kline.open_time,
+                open: kline.open,
+                high: kline.high,
+                low: kline.low,
+                close: kline.close,
+                volume: kline.volume,
+            },
+        },
+        StreamData::DepthUpdate {
+            symbol,
+            first_update_id,
+            final_update_id,
+            bids,
+            asks,
+        } => StreamEvent::Depth(DepthUpdate {
+            symbol,
+            first_update_id,
+            final_update_id,
+            bids,
+            asks,
+        }),
+        StreamData::AggTrade {
+            symbol,
+            id,
+            price,
+            quantity,
+            time,
+            buyer_maker,
+        } => StreamEvent::Trade {
+            symbol,
+            trade: Trade {
+                id,
+                price,
+                quantity,
+                time,
+                buyer_maker,
+            },
+        },
+    };
+    Some(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_url() {
+        let url = stream_url(&streams(
+            &["BTCUSDT".to_string(), "ETHUSDT".to_string()],
+            Timeframe::H1,
+            None,
+            &[],
+        ));
+        assert_eq!(
+            url,
+            "wss://stream.binance.com:9443/stream?streams=btcusdt@miniTicker/ethusdt@miniTicker/btcusdt@kline_1h/ethusdt@kline_1h"
+        );
+    }
+
+    #[test]
+    fn test_resubscribe_only_changed_streams() {
+        let before = streams(
+            &["BTCUSDT".to_string(), "ETHUSDT".to_string()],
+            Timeframe::H1,
+            Some("BTCUSDT"),
+            &[],
+        );
+        let after = streams(
+            &["ETHUSDT".to_string(), "SOLUSDT".to_string()],
+            Timeframe::H1,
+            None,
+            &["SOLUSDT".to_string()],
+        );
+        assert_eq!(
+            missing_from(&before, &after),
+            vec![
+                "btcusdt@miniTicker",
+                "btcusdt@kline_1h",
+                "btcusdt@depth@100ms"
+            ]
+        );
+        assert_eq!(
+            missing_from(&after, &before),
+            vec!["solusdt@miniTicker", "solusdt@kline_1h", "solusdt@aggTrade"]
+        );
+    }
+
+    #[test]
+    fn test_parse_mini_ticker() {
+        let text = r#"{"stream":"btcusdt@miniTicker","data":{"e":"24hrMiniTicker","E":1700000000000,"s":"BTCUSDT","c":"110.00","o":"100.00","h":"120.00","l":"90.00","v":"1234.5","q":"135795.0"}}"#;
+        match parse_message(text) {
+            Some(StreamEvent::Ticker(t)) => {
+                assert_eq!(t.symbol, "BTCUSDT");
+                assert_eq!(t.last_price, 110.0);
+                assert!((t.price_change_percent - 10.0).abs() < 1e-9);
+                assert_eq!(t.high_price, 120.0);
+                assert_eq!(t.low_price, 90.0);
+                assert_eq!(t.volume, 1234.5);
+                assert_eq!(t.open_price, 100.0);
+                assert_eq!(t.quote_volume, 135795.0);
+                assert_eq!(t.weighted_avg_price, None);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_kline() {
+        let text = r#"{"stream":"ethusdt@kline_1m","data":{"e":"kline","E":1700000000000,"s":"ETHUSDT","k":{"t":1699999980000,"T":1700000039999,"s":"ETHUSDT","i":"1m","o":"2000.0","c":"2001.5","h":"2002.0","l":"1999.0","v":"12.0","x":false}}}"#;
+        match parse_message(text) {
+            Some(StreamEvent::Kline {
+                symbol,
+                timeframe,
+                candle,
+            }) => {
+                assert_eq!(symbol, "ETHUSDT");
+                assert_eq!(timeframe, Timeframe::M1);
+                assert_eq!(candle.open_time, 1699999980000);
+                assert_eq!(candle.open, 2000.0);
+                assert_eq!(candle.high, 2002.0);
+                assert_eq!(candle.low, 1999.0);
+                assert_eq!(candle.close, 2001.5);
+                assert_eq!(candle.volume, 12.0);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_depth_update() {
+        let text = r#"{"stream":"bnbbtc@depth@100ms","data":{"e":"depthUpdate","E":1672515782136,"s":"BNBBTC","U":157,"u":160,"b":[["0.0024","10"]],"a":[["0.0026","100"],["0.0027","0"]]}}"#;
+        match parse_message(text) {
+            Some(StreamEvent::Depth(update)) => {
+                assert_eq!(update.symbol, "BNBBTC");
+                assert_eq!(update.first_update_id, 157);
+                assert_eq!(update.final_update_id, 160);
+                assert_eq!(
+                    update.bids,
+                    vec![Level {
+                        price: 0.0024,
+                        quantity: 10.0
+                    }]
+                );
+                assert_eq!(update.asks.len(), 2);
+                assert_eq!(update.asks[1].quantity, 0.0);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_agg_trade() {
+        let text = r#"{"stream":"bnbbtc@aggTrade","data":{"e":"aggTrade","E":1672515782136,"s":"BNBBTC","a":12345,"p":"0.001","q":"100","f":100,"l":105,"T":1672515782136,"m":true,"M":true}}"#;
+        match parse_message(text) {
+            Some(StreamEvent::Trade { symbol, trade }) => {
+                assert_eq!(symbol, "BNBBTC");
+                assert_eq!(
+                    trade,
+                    Trade {
+                        id: 12345,
+                        price: 0.001,
+                        quantity: 100.0,
+                        time: 1672515782136,
+                        buyer_maker: true,
+                    }
+                );
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_ignores_unknown_messages() {
+        assert!(parse_message(r#"{"result":null,"id":1}"#).is_none());
+        assert!(parse_message("not json").is_none());
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::source::{MarketDataSource, Pair, SymbolInfo, DEFAULT_QUOTE};
+
+/// How long the cached exchange symbol list is trusted before refetching.
+pub const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    fetched_at_ms: i64,
+    symbols: Vec<SymbolInfo>,
+}
+
+/// `$XDG_CACHE_HOME/cryptowatcher/symbols.json` or the platform equivalent.
+pub fn cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("cryptowatcher").join("symbols.json"))
+}
+
+/// Symbols listed by the exchange, keyed by exchange symbol.
+#[derive(Debug, Default)]
+pub struct SymbolRegistry {
+    symbols: HashMap<String, SymbolInfo>,
+}
+
+impl SymbolRegistry {
+    pub fn new(symbols: Vec<SymbolInfo>) -> Self {
+        Self {
+            symbols: symbols
+                .into_iter()
+                .map(|info| (info.symbol.clone(), info))
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn get(&self, pair: &Pair) -> Option<&SymbolInfo> {
+        self.symbols.get(&pair.symbol())
+    }
+
+    /// Fetches the symbol list from `source` unless the cache at `path` is
+    /// younger than `CACHE_TTL` and knows every pair in `wanted`. A stale
+    /// cache is still used if the exchange cannot be reached.
+    pub async fn load(
+        source: &dyn MarketDataSource,
+        path: Option<&Path>,
+        wanted: &[Pair],
+    ) -> Result<Self> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let cached = path.and_then(read_cache);
+        if let Some(cache) = &cached {
+            let fresh = now_ms - cache.fetched_at_ms < CACHE_TTL.as_millis() as i64;
+            let registry = Self::new(cache.symbols.clone());
+            // A pair missing from a fresh cache may have been listed since
+            if fresh && wanted.iter().all(|p| registry.get(p).is_some()) {
+                return Ok(registry);
+            }
+        }
+
+        match source.symbols().await {
+            Ok(symbols) => {
+                if let Some(path) = path {
+                    let cache = CacheFile {
+                        fetched_at_ms: now_ms,
+                        symbols,
+                    };
+                    // Failing to cache only costs a refetch next time
+                    let _ = write_cache(path, &cache);
+                    return Ok(Self::new(cache.symbols));
+                }
+                Ok(Self::new(symbols))
+            }
+            Err(e) => match cached {
+                Some(cache) => Ok(Self::new(cache.symbols)),
+                None => Err(e.context("cannot fetch exchange symbols")),
+            },
+        }
+    }
+
+    /// Checks that `pair` is listed and trading, suggesting a close match
+    /// when it is not.
+    pub fn validate(&self, pair: &Pair) -> Result<&SymbolInfo, String> {
+        match self.get(pair) {
+            Some(info) if info.trading => Ok(info),
+            Some(_) => Err(format!("{} is not trading", pair)),
+            None => match self.suggest(pair) {
+                Some(suggestion) => Err(format!(
+                    "unknown pair {}, did you mean {}?",
+                    pair, suggestion
+                )),
+                None => Err(format!("unknown pair {}", pair)),
+            },
+        }
+    }
+
+    /// Trading pairs matching what was typed so far, best first: a `BASE/QUOTE`
+    /// query matches both halves by prefix, anything else is matched against
+    /// the base, then the whole symbol, then as a scattered subsequence.
+    /// Ties prefer `quote`, then the shorter symbol.
+    pub fn search(&self, query: &str, quote: &str, limit: usize) -> Vec<Pair> {
+        let query = query.trim().to_uppercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let (base_query, quote_query) = match query.split_once('/') {
+            Some((base, quote)) => (base.trim(), Some(quote.trim())),
+            None => (query.as_str(), None),
+        };
+
+        let mut matches: Vec<(u32, &SymbolInfo)> = self
+            .symbols
+            .values()
+            .filter(|info| info.trading)
+            .filter(|info| quote_query.is_none_or(|q| info.quote.starts_with(q)))
+            .filter_map(|info| {
+                let score = if info.base == base_query {
+                    4
+                } else if info.base.starts_with(base_query) {
+                    3
+                } else if quote_query.is_none() && info.symbol.starts_with(base_query) {
+                    2
+                } else if is_subsequence(base_query, &info.base) {
+                    1
+                } else {
+                    return None;
+                };
+                Some((score, info))
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then_with(|| (a.1.quote != quote).cmp(&(b.1.quote != quote)))
+                .then_with(|| a.1.symbol.len().cmp(&b.1.symbol.len()))
+                .then_with(|| a.1.symbol.cmp(&b.1.symbol))
+        });
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, info)| info.pair())
+            .collect()
+    }
+
+    fn suggest(&self, pair: &Pair) -> Option<Pair> {
+        let trading = || self.symbols.values().filter(|info| info.trading);
+
+        // A full symbol given as a coin, e.g. BTCUSDT/USDT
+        if let Some(info) = self.symbols.get(&pair.base).filter(|info| info.trading) {
+            return Some(info.pair());
+        }
+
+        // A typo in the base, e.g. BTCC/USDT
+        let max_distance = (pair.base.len() / 3).max(1);
+        let closest = trading()
+            .filter(|info| info.quote == pair.quote)
+            .map(|info| (strsim::damerau_levenshtein(&pair.base, &info.base), info))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.symbol.cmp(&b.1.symbol)));
+        if let Some((_, info)) = closest {
+            return Some(info.pair());
+        }
+
+        // The right coin with a quote it does not trade against
+        trading()
+            .filter(|info| info.base == pair.base)
+            .min_by_key(|info| (info.quote != DEFAULT_QUOTE, info.symbol.clone()))
+            .map(SymbolInfo::pair)
+    }
+}
+
+/// Whether every character of `needle` appears in `haystack` in order.
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut rest = haystack.chars();
+    needle.chars().all(|c| rest.any(|h| h == c))
+}
+
+fn read_cache(path: &Path) -> Option<CacheFile> {
+    let text = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn write_cache(path: &Path, cache: &CacheFile) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text = serde_json::to_string(cache)?;
+    fs::write(path, text).with_context(|| format!("cannot write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use anyhow::anyhow;
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::source::{Candle, Ticker, Timeframe};
+
+    fn info(base: &str, quote: &str, trading: bool) -> SymbolInfo {
+        SymbolInfo {
+            symbol: format!("{}{}", base, quote),
+            base: base.to_string(),
+            quote: quote.to_string(),
+            trading,
+            tick_size: 0.01,
+            quote_precision: 8,
+        }
+    }
+
+    fn registry() -> SymbolRegistry {
+        SymbolRegistry::new(vec![
+            info("BTC", "USDT", true),
+            info("ETH", "USDT", true),
+            info("ETH", "BTC", true),
+            info("SOL", "FDUSD", true),
+            info("LUNA", "USDT", false),
+        ])
+    }
+
+    /// Counts `symbols()` calls and can be switched off to simulate an outage.
+    struct ListingSource {
+        calls: AtomicUsize,
+        online: bool,
+    }
+
+    #[async_trait]
+    impl MarketDataSource for ListingSource {
+        async fn ticker(&self, _symbol: &str) -> Result<Ticker> {
+            Err(anyhow!("not used"))
+        }
+
+        async fn klines(
+            &self,
+            _symbol: &str,
+            _timeframe: Timeframe,
+            _limit: u32,
+        ) -> Result<Vec<Candle>> {
+            Err(anyhow!("not used"))
+        }
+
+        async fn symbols(&self) -> Result<Vec<SymbolInfo>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if !self.online {
+                return Err(anyhow!("offline"));
+            }
+            Ok(vec![info("BTC", "USDT", true), info("PEPE", "USDT", true)])
+        }
+    }
+
+    fn temp_cache(name: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("cryptowatcher-test-{}", std::process::id()))
+            .join(name);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_validate_pairs() {
+        let registry = registry();
+        assert!(registry.validate(&"BTC".parse().unwrap()).is_ok());
+        assert!(registry.validate(&"ETH/BTC".parse().unwrap()).is_ok());
+        assert_eq!(
+            registry.validate(&"LUNA".parse().unwrap()).unwrap_err(),
+            "LUNA/USDT is not trading"
+        );
+        assert_eq!(
+            registry.validate(&"DOGE".parse().unwrap()).unwrap_err(),
+            "unknown pair DOGE/USDT"
+        );
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let registry = registry();
+        let suggest = |s: &str| registry.suggest(&s.parse().unwrap()).map(|p| p.to_string());
+        assert_eq!(suggest("BTCC"), Some("BTC/USDT".to_string()));
+        assert_eq!(suggest("EHT"), Some("ETH/USDT".to_string()));
+        assert_eq!(suggest("BTCUSDT"), Some("BTC/USDT".to_string()));
+        assert_eq!(suggest("SOL"), Some("SOL/FDUSD".to_string()));
+        assert_eq!(suggest("ETH/EUR"), Some("ETH/USDT".to_string()));
+        assert_eq!(suggest("ZZZZZZ"), None);
+    }
+
+    #[test]
+    fn test_search() {
+        let registry = registry();
+        let search = |q: &str| -> Vec<String> {
+            registry
+                .search(q, "USDT", 5)
+                .iter()
+                .map(Pair::to_string)
+                .collect()
+        };
+        assert_eq!(search("eth"), vec!["ETH/USDT", "ETH/BTC"]);
+        assert_eq!(search("eth/b"), vec!["ETH/BTC"]);
+        assert_eq!(search("s"), vec!["SOL/FDUSD"]);
+        // Subsequence of the base
+        assert_eq!(search("bc"), vec!["BTC/USDT"]);
+        // Symbols typed whole still match
+        assert_eq!(search("ETHBTC"), vec!["ETH/BTC"]);
+        // Pairs that are not trading are never offered
+        assert!(search("luna").is_empty());
+        assert!(search("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_load_uses_fresh_cache() {
+        let path = temp_cache("fresh.json");
+        let source = ListingSource {
+            calls: AtomicUsize::new(0),
+            online: true,
+        };
+        let btc: Pair = "BTC".parse().unwrap();
+
+        SymbolRegistry::load(&source, Some(&path), std::slice::from_ref(&btc))
+            .await
+            .unwrap();
+        let registry = SymbolRegistry::load(&source, Some(&path), &[btc])
+            .await
+            .unwrap();
+        assert_eq!(source.calls.load(Ordering::SeqCst), 1);
+        assert!(registry.get(&"PEPE".parse().unwrap()).is_some());
+
+        // A pair the cache has never seen forces a refetch
+        SymbolRegistry::load(&source, Some(&path), &["NEW".parse().unwrap()])
+            .await
+            .unwrap();
+        assert_eq!(source.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_load_falls_back_to_stale_cache() {
+        let path = temp_cache("stale.json");
+        write_cache(
+            &path,
+            &CacheFile {
+                fetched_at_ms: 0,
+                symbols: vec![info("ETH", "USDT", true)],
+            },
+        )
+        .unwrap();
+        let source = ListingSource {
+            calls: AtomicUsize::new(0),
+            online: false,
+        };
+
+        let registry = SymbolRegistry::load(&source, Some(&path), &[])
+            .await
+            .unwrap();
+        assert_eq!(source.calls.load(Ordering::SeqCst), 1);
+        assert!(registry.get(&"ETH".parse().unwrap()).is_some());
+
+        assert!(SymbolRegistry::load(&source, None, &[]).await.is_err());
+    }
+}
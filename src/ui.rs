@@ -1,15 +1,26 @@
 use ratatui::{
-    layout::{Constraint, Layout, Rect},
+    layout::{Alignment, Constraint, Layout, Rect},
     style::{Color, Modifier, Style},
     symbols,
     text::{Line, Span},
-    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph},
+    widgets::{
+        Axis, Block, Borders, Cell, Chart, Dataset, GraphType, LegendPosition, List, ListItem,
+        Paragraph, Row, Table, TableState, Tabs,
+    },
     Frame,
 };
 
+use chrono::{Local, TimeZone};
 use Constraint::Ratio;
 
-use crate::app::{App, CoinData};
+use crate::app::{App, CoinData, SortColumn, View};
+use crate::book::{self, OrderBook};
+use crate::indicator::{
+    Band, Indicator, Oscillator, MACD_FAST, MACD_SIGNAL, MACD_SLOW, RSI_PERIOD,
+};
+use crate::keys::Action;
+use crate::portfolio::{Holding, Summary};
+use crate::source::{currency_sign, Candle, Level, Timeframe};
 
 // Synthwave color palette
 const PINK: Color = Color::Rgb(255, 46, 151); // #ff2e97
@@ -19,6 +30,33 @@ const BORDER: Color = Color::Rgb(61, 26, 120); // #3d1a78
 const MUTED: Color = Color::Rgb(107, 91, 149); // #6b5b95
 const TEXT: Color = Color::Rgb(240, 240, 240); // #f0f0f0
 
+// Panes narrower than this always fall back to the line chart
+const MIN_CANDLE_WIDTH: u16 = 40;
+const Y_LABEL_WIDTH: u16 = 9;
+/// Keeps tiny prices inside `Y_LABEL_WIDTH`
+const MAX_SHORT_DECIMALS: usize = 6;
+const ALERT_LOG_HEIGHT: u16 = 8;
+const SPARKLINE_WIDTH: usize = 30;
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+// The oscillator sub-pane only appears when the price chart keeps enough rows
+const OSCILLATOR_HEIGHT: u16 = 7;
+const MIN_OSCILLATOR_PANE_HEIGHT: u16 = 18;
+const RSI_OVERSOLD: f64 = 30.0;
+const RSI_OVERBOUGHT: f64 = 70.0;
+// Likewise the volume strip under the price plot
+const VOLUME_HEIGHT: u16 = 3;
+const MIN_VOLUME_PANE_HEIGHT: u16 = 12;
+
+const DETAIL_PANEL_WIDTH: u16 = 38;
+const TAPE_WIDTH: u16 = 34;
+/// Narrower panes keep the whole width for the chart.
+const MIN_TAPE_PANE_WIDTH: u16 = 70;
+const DEPTH_HEIGHT: u16 = 12;
+const MIN_DEPTH_PANE_HEIGHT: u16 = 30;
+const LADDER_WIDTH: u16 = 44;
+/// Levels per side drawn in the cumulative depth curves.
+const DEPTH_LEVELS: usize = 50;
+
 const CHART_COLORS: [Color; 6] = [
     Color::Rgb(255, 46, 151), // Hot pink
     Color::Rgb(0, 240, 255),  // Cyan
@@ -28,29 +66,584 @@ const CHART_COLORS: [Color; 6] = [
     Color::Rgb(114, 9, 183),  // Deep violet
 ];
 
+/// Colors by role, so the palette can be swapped from the config file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Decorations and page indicator
+    pub accent: Color,
+    /// Key hints, selection and status text
+    pub highlight: Color,
+    pub positive: Color,
+    pub negative: Color,
+    pub border: Color,
+    pub muted: Color,
+    pub text: Color,
+    /// Line colors, assigned to charts in page order
+    pub series: [Color; 6],
+}
+
+pub const SYNTHWAVE: Theme = Theme {
+    accent: PINK,
+    highlight: CYAN,
+    positive: POSITIVE,
+    negative: PINK,
+    border: BORDER,
+    muted: MUTED,
+    text: TEXT,
+    series: CHART_COLORS,
+};
+
+/// Plain ANSI colors for terminals without truecolor support.
+pub const CLASSIC: Theme = Theme {
+    accent: Color::Magenta,
+    highlight: Color::Cyan,
+    positive: Color::Green,
+    negative: Color::Red,
+    border: Color::DarkGray,
+    muted: Color::Gray,
+    text: Color::White,
+    series: [
+        Color::Yellow,
+        Color::Cyan,
+        Color::Magenta,
+        Color::Blue,
+        Color::Green,
+        Color::LightRed,
+    ],
+};
+
+impl Default for Theme {
+    fn default() -> Self {
+        SYNTHWAVE
+    }
+}
+
+impl Theme {
+    pub fn named(name: &str) -> Option<Theme> {
+        match name.to_lowercase().as_str() {
+            "synthwave" => Some(SYNTHWAVE),
+            "classic" => Some(CLASSIC),
+            _ => None,
+        }
+    }
+}
+
 pub fn render(frame: &mut Frame, app: &App) {
     let area = frame.area();
+    let theme = &app.theme;
+
+    // Split into main area + status bar, with a portfolio row when holding anything
+    let status_height = if app.portfolio.is_empty() { 3 } else { 4 };
+    let main_chunks =
+        Layout::vertical([Ratio(1, 1), Constraint::Length(status_height)]).split(area);
+    let mut main_area = main_chunks[0];
 
-    // Split into main area + status bar
-    let main_chunks = Layout::vertical([Ratio(1, 1), Constraint::Length(3)]).split(area);
-    let main_area = main_chunks[0];
+    if app.show_alert_log {
+        let [charts, log] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(ALERT_LOG_HEIGHT)])
+                .areas(main_area);
+        render_alert_log(frame, log, app);
+        main_area = charts;
+    }
+
+    match app.view {
+        View::Table => {
+            render_table(frame, main_area, app);
+            render_status_bar(frame, main_chunks[1], app);
+            return;
+        }
+        View::Detail => {
+            render_detail(frame, main_area, app);
+            render_status_bar(frame, main_chunks[1], app);
+            return;
+        }
+        View::Charts => {}
+    }
 
     // Get visible coins for current page
     let visible = app.visible_coins();
     let grid_areas = calculate_grid_layout(visible.len(), main_area);
 
-    for (i, (coin, chart_area)) in visible.iter().zip(grid_areas.iter()).enumerate() {
+    for (i, (coin, pane)) in visible.iter().zip(grid_areas.iter()).enumerate() {
+        let mut chart_area = *pane;
+        if coin.show_tape && pane.width >= MIN_TAPE_PANE_WIDTH {
+            let [chart, tape] =
+                Layout::horizontal([Constraint::Min(0), Constraint::Length(TAPE_WIDTH)])
+                    .areas(*pane);
+            render_tape(frame, tape, coin, app);
+            chart_area = chart;
+        }
         render_coin_chart(
             frame,
-            *chart_area,
+            chart_area,
             coin,
-            CHART_COLORS[i % CHART_COLORS.len()],
+            app.portfolio.get(&coin.pair),
+            coin_color(app, app.page_index * app.charts_per_page + i),
+            app.is_selected(i),
+            theme,
         );
     }
 
     render_status_bar(frame, main_chunks[1], app);
 }
 
+/// Every coin on one screen, ordered by `app.sort_column`.
+/// Series color of the coin at watchlist `index`: its position on its chart
+/// page, so a coin keeps its color across the grid, table and detail views.
+fn coin_color(app: &App, index: usize) -> Color {
+    let series = &app.theme.series;
+    series[(index % app.charts_per_page) % series.len()]
+}
+
+fn render_table(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let order = app.table_order();
+    let right = |text: String| Cell::from(Line::from(text).alignment(Alignment::Right));
+
+    let header = Row::new(
+        [
+            SortColumn::Watchlist,
+            SortColumn::Pair,
+            SortColumn::Price,
+            SortColumn::Change,
+            SortColumn::High,
+            SortColumn::Low,
+            SortColumn::Volume,
+        ]
+        .map(|column| {
+            let mut label = column.label().to_string();
+            if column == app.sort_column && column != SortColumn::Watchlist {
+                label.push_str(if app.sort_descending { " ▼" } else { " ▲" });
+            }
+            match column {
+                SortColumn::Watchlist | SortColumn::Pair => Cell::from(label),
+                _ => right(label),
+            }
+        })
+        .into_iter()
+        .chain([Cell::from("Trend")]),
+    )
+    .style(
+        Style::default()
+            .fg(theme.highlight)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let rows = order.iter().map(|&i| {
+        let coin = &app.coins[i];
+        let change_color = if coin.change_24h >= 0.0 {
+            theme.positive
+        } else {
+            theme.negative
+        };
+        let closes: Vec<f64> = coin.price_history.iter().map(|c| c.close).collect();
+        let trend_color = match (closes.first(), closes.last()) {
+            (Some(first), Some(last)) if last < first => theme.negative,
+            _ => theme.positive,
+        };
+        Row::new([
+            Cell::from(format!("{}", i + 1)).style(Style::default().fg(theme.muted)),
+            Cell::from(coin.pair.to_string()).style(
+                Style::default()
+                    .fg(coin_color(app, i))
+                    .add_modifier(Modifier::BOLD),
+            ),
+            right(format_price(coin.price, coin)).style(Style::default().fg(theme.text)),
+            right(format!("{:+.2}%", coin.change_24h)).style(Style::default().fg(change_color)),
+            right(format_price(coin.high_24h, coin)).style(Style::default().fg(theme.muted)),
+            right(format_price(coin.low_24h, coin)).style(Style::default().fg(theme.muted)),
+            right(format_volume(coin.volume_24h)).style(Style::default().fg(theme.muted)),
+            Cell::from(sparkline(&closes, SPARKLINE_WIDTH)).style(Style::default().fg(trend_color)),
+        ])
+    });
+
+    let title = Line::from(vec![
+        Span::styled("◈ ", Style::default().fg(theme.accent)),
+        Span::styled(
+            format!("Watchlist · {} coins · {} ", app.coins.len(), app.timeframe),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        ),
+    ]);
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(3),
+            Constraint::Length(12),
+            Constraint::Min(12),
+            Constraint::Length(9),
+            Constraint::Min(12),
+            Constraint::Min(12),
+            Constraint::Length(8),
+            Constraint::Length(SPARKLINE_WIDTH as u16),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border)),
+    )
+    .column_spacing(2)
+    .row_highlight_style(
+        Style::default()
+            .bg(theme.border)
+            .add_modifier(Modifier::BOLD),
+    )
+    .highlight_symbol("▶ ");
+
+    let mut state =
+        TableState::default().with_selected(order.iter().position(|&i| i == app.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+/// The selected coin full screen: timeframe tabs over a large chart, with
+/// its 24h stats and latest trades alongside.
+fn render_detail(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let Some(coin) = app.coins.get(app.selected) else {
+        return;
+    };
+    // Same color as the coin's pane in the grid
+    let color = coin_color(app, app.selected);
+
+    let [tabs_area, body] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+    let tabs = Tabs::new(Timeframe::ALL.map(|tf| tf.to_string()))
+        .select(Timeframe::ALL.iter().position(|tf| *tf == app.timeframe))
+        .style(Style::default().fg(theme.muted))
+        .highlight_style(
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        )
+        .divider(Span::styled("│", Style::default().fg(theme.border)));
+    frame.render_widget(tabs, tabs_area);
+
+    let [mut chart_area, side] =
+        Layout::horizontal([Constraint::Min(0), Constraint::Length(DETAIL_PANEL_WIDTH)])
+            .areas(body);
+    // The book takes room from the chart only when there is plenty
+    if chart_area.height >= MIN_DEPTH_PANE_HEIGHT {
+        let [chart, depth] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(DEPTH_HEIGHT)])
+                .areas(chart_area);
+        render_depth(frame, depth, coin, app.book.as_ref(), theme);
+        chart_area = chart;
+    }
+    render_coin_chart(
+        frame,
+        chart_area,
+        coin,
+        app.portfolio.get(&coin.pair),
+        color,
+        true,
+        theme,
+    );
+
+    let stats = detail_stats(coin, theme);
+    let [stats_area, trades_area] = Layout::vertical([
+        Constraint::Length(stats.len() as u16 + 2),
+        Constraint::Min(0),
+    ])
+    .areas(side);
+    let right = |text: String| Cell::from(Line::from(text).alignment(Alignment::Right));
+
+    let rows = stats.into_iter().map(|(label, value, color)| {
+        Row::new([
+            Cell::from(label).style(Style::default().fg(theme.muted)),
+            right(value).style(Style::default().fg(color)),
+        ])
+    });
+    let table = Table::new(rows, [Constraint::Length(12), Constraint::Min(0)])
+        .block(detail_block("24h stats", theme));
+    frame.render_widget(table, stats_area);
+
+    render_tape(frame, trades_area, coin, app);
+}
+
+/// Time and sales for one coin, newest first, colored by the taker's side.
+/// Trades worth at least the quote asset's large-trade threshold are
+/// emphasised, and the filter hides everything else; without a threshold
+/// the tape is never filtered.
+fn render_tape(frame: &mut Frame, area: Rect, coin: &CoinData, app: &App) {
+    let theme = &app.theme;
+    let threshold = app
+        .large_trade_threshold(&coin.pair)
+        .map(|threshold| format_amount(threshold, 0, &coin.pair.quote));
+    let filter = app.tape_filter && threshold.is_some();
+    let title = match &threshold {
+        Some(threshold) if filter => format!("Trades ≥ {}", threshold),
+        _ => "Trades".to_string(),
+    };
+    let right = |text: String| Cell::from(Line::from(text).alignment(Alignment::Right));
+
+    // Takers buying lift the price, takers selling hit it
+    let rows = coin
+        .trades
+        .iter()
+        .map(|trade| (trade, app.is_large_trade(&coin.pair, trade)))
+        .filter(|(_, large)| *large || !filter)
+        .map(|(trade, large)| {
+            let time = match Local.timestamp_millis_opt(trade.time).single() {
+                Some(dt) => dt.format("%H:%M:%S").to_string(),
+                None => "--:--:--".to_string(),
+            };
+            let side_color = if trade.buyer_maker {
+                theme.negative
+            } else {
+                theme.positive
+            };
+            let (size_color, modifier) = if large {
+                (side_color, Modifier::BOLD | Modifier::REVERSED)
+            } else {
+                (theme.text, Modifier::empty())
+            };
+            Row::new([
+                Cell::from(time).style(Style::default().fg(theme.muted)),
+                right(format_price(trade.price, coin)).style(Style::default().fg(side_color)),
+                right(format_quantity(trade.quantity))
+                    .style(Style::default().fg(size_color).add_modifier(modifier)),
+            ])
+        });
+    let header = Row::new([
+        Cell::from("Time"),
+        right("Price".to_string()),
+        right("Size".to_string()),
+    ])
+    .style(Style::default().fg(theme.muted));
+    let mut block = detail_block(&title, theme);
+    if let Some(threshold) = threshold.filter(|_| !filter) {
+        block = block.title_bottom(
+            Line::from(Span::styled(
+                format!(" big ≥ {} ", threshold),
+                Style::default().fg(theme.muted),
+            ))
+            .alignment(Alignment::Right),
+        );
+    }
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Min(0),
+            Constraint::Length(10),
+        ],
+    )
+    .header(header)
+    .block(block);
+    frame.render_widget(table, area);
+}
+
+fn detail_block<'a>(title: &'a str, theme: &Theme) -> Block<'a> {
+    Block::default()
+        .title(Line::from(vec![
+            Span::styled("◈ ", Style::default().fg(theme.accent)),
+            Span::styled(
+                title,
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.border))
+}
+
+/// Cumulative bid/ask curves beside a ladder of the best levels, with the
+/// spread and mid price in the title.
+fn render_depth(
+    frame: &mut Frame,
+    area: Rect,
+    coin: &CoinData,
+    book: Option<&OrderBook>,
+    theme: &Theme,
+) {
+    let muted = Style::default().fg(theme.muted);
+    let (Some(book), Some(spread), Some(mid)) = (
+        book,
+        book.and_then(OrderBook::spread),
+        book.and_then(OrderBook::mid_price),
+    ) else {
+        let message = if book.is_some() {
+            "No order book"
+        } else {
+            "Loading order book..."
+        };
+        let paragraph =
+            Paragraph::new(Span::styled(message, muted)).block(detail_block("Order book", theme));
+        frame.render_widget(paragraph, area);
+        return;
+    };
+
+    let block = detail_block("Order book", theme).title(Line::from(vec![
+        Span::styled(" spread ", muted),
+        Span::styled(format_price(spread, coin), Style::default().fg(theme.text)),
+        Span::styled(format!(" ({:.3}%)", spread / mid * 100.0), muted),
+        Span::styled("  mid ", muted),
+        Span::styled(format_price(mid, coin), Style::default().fg(theme.text)),
+        Span::raw(" "),
+    ]));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [curves_area, ladder_area] =
+        Layout::horizontal([Constraint::Min(0), Constraint::Length(LADDER_WIDTH)]).areas(inner);
+
+    // Bids step down from the best price, asks step up
+    let bids = book::cumulative(&book.bids, DEPTH_LEVELS);
+    let asks = book::cumulative(&book.asks, DEPTH_LEVELS);
+    let low = bids.last().map_or(mid, |l| l.0);
+    let high = asks.last().map_or(mid, |l| l.0);
+    let max_total = bids
+        .iter()
+        .chain(&asks)
+        .map(|l| l.1)
+        .fold(f64::MIN_POSITIVE, f64::max);
+    let datasets = vec![
+        Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(theme.positive))
+            .data(&bids),
+        Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(theme.negative))
+            .data(&asks),
+    ];
+    let x_labels = [low, mid, high].map(|p| Span::styled(format_price(p, coin), muted));
+    let y_labels = ["", &format_volume(max_total)]
+        .map(|text| Span::styled(format!("{:>1$}", text, Y_LABEL_WIDTH as usize), muted));
+    let chart = Chart::new(datasets)
+        .x_axis(
+            Axis::default()
+                .style(muted)
+                .bounds([low, high.max(low + f64::EPSILON)])
+                .labels(x_labels),
+        )
+        .y_axis(
+            Axis::default()
+                .style(muted)
+                .bounds([0.0, max_total])
+                .labels(y_labels),
+        );
+    frame.render_widget(chart, curves_area);
+
+    // Asks above the spread row, worst at the top, so the best levels meet
+    // in the middle
+    let per_side = (ladder_area.height.saturating_sub(1) / 2) as usize;
+    let asks: Vec<Level> = book.asks.iter().take(per_side).rev().copied().collect();
+    let bids: Vec<Level> = book.bids.iter().take(per_side).copied().collect();
+    let max_quantity = asks
+        .iter()
+        .chain(&bids)
+        .map(|l| l.quantity)
+        .fold(f64::MIN_POSITIVE, f64::max);
+    let bar_width = LADDER_WIDTH.saturating_sub(28) as f64;
+    let right = |text: String| Cell::from(Line::from(text).alignment(Alignment::Right));
+    let level_row = |level: &Level, color: Color| {
+        let bar = "█".repeat((level.quantity / max_quantity * bar_width).ceil() as usize);
+        Row::new([
+            right(format_price(level.price, coin)).style(Style::default().fg(color)),
+            right(format_quantity(level.quantity)).style(Style::default().fg(theme.text)),
+            Cell::from(bar).style(Style::default().fg(color)),
+        ])
+    };
+    let rows = asks
+        .iter()
+        .map(|level| level_row(level, theme.negative))
+        .chain([Row::new([
+            right(format_price(mid, coin)).style(Style::default().fg(theme.highlight)),
+            right(format_price(spread, coin)).style(muted),
+            Cell::from("spread").style(muted),
+        ])])
+        .chain(bids.iter().map(|level| level_row(level, theme.positive)));
+    let ladder = Table::new(
+        rows,
+        [
+            Constraint::Length(14),
+            Constraint::Length(10),
+            Constraint::Min(0),
+        ],
+    );
+    frame.render_widget(ladder, ladder_area);
+}
+
+/// Label, value and color for each row of the detail view's stats.
+fn detail_stats(coin: &CoinData, theme: &Theme) -> Vec<(&'static str, String, Color)> {
+    let change_color = if coin.change_24h >= 0.0 {
+        theme.positive
+    } else {
+        theme.negative
+    };
+    let unknown = || "–".to_string();
+    vec![
+        ("Price", format_price(coin.price, coin), theme.text),
+        ("Change", format!("{:+.2}%", coin.change_24h), change_color),
+        ("Open", format_price(coin.open_24h, coin), theme.text),
+        ("High", format_price(coin.high_24h, coin), theme.text),
+        ("Low", format_price(coin.low_24h, coin), theme.text),
+        (
+            "Avg price",
+            coin.weighted_avg_24h
+                .map_or_else(unknown, |avg| format_price(avg, coin)),
+            theme.text,
+        ),
+        (
+            "Volume",
+            format!("{} {}", format_volume(coin.volume_24h), coin.pair.base),
+            theme.text,
+        ),
+        (
+            "Quote volume",
+            format!(
+                "{} {}",
+                format_volume(coin.quote_volume_24h),
+                coin.pair.quote
+            ),
+            theme.text,
+        ),
+        (
+            "Trades",
+            coin.trades_24h
+                .map_or_else(unknown, |n| format_volume(n as f64)),
+            theme.text,
+        ),
+    ]
+}
+
+/// `values` squeezed into at most `width` block characters scaled between
+/// their own low and high.
+fn sparkline(values: &[f64], width: usize) -> String {
+    let n = values.len().min(width);
+    if n == 0 {
+        return String::new();
+    }
+    let sampled: Vec<f64> = (0..n)
+        .map(|i| {
+            values[if n == 1 {
+                0
+            } else {
+                i * (values.len() - 1) / (n - 1)
+            }]
+        })
+        .collect();
+    let lo = sampled.iter().copied().fold(f64::INFINITY, f64::min);
+    let hi = sampled.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let top = SPARK_LEVELS.len() - 1;
+    sampled
+        .iter()
+        .map(|v| {
+            let level = if hi > lo {
+                ((v - lo) / (hi - lo) * top as f64).round() as usize
+            } else {
+                top / 2
+            };
+            SPARK_LEVELS[level.min(top)]
+        })
+        .collect()
+}
+
 fn calculate_grid_layout(count: usize, area: Rect) -> Vec<Rect> {
     match count {
         0 => vec![],
@@ -74,127 +667,944 @@ fn calculate_grid_layout(count: usize, area: Rect) -> Vec<Rect> {
     }
 }
 
-fn render_coin_chart(frame: &mut Frame, area: Rect, coin: &CoinData, color: Color) {
+fn render_coin_chart(
+    frame: &mut Frame,
+    area: Rect,
+    coin: &CoinData,
+    holding: Option<&Holding>,
+    color: Color,
+    selected: bool,
+    theme: &Theme,
+) {
+    // Triggered alerts blink the border for a few seconds
+    let blink_on = chrono::Utc::now().timestamp_millis() / 500 % 2 == 0;
+    let border_color = if coin.is_flashing() && blink_on {
+        theme.accent
+    } else if selected {
+        theme.highlight
+    } else {
+        theme.border
+    };
+    let mut block = Block::default()
+        .title(chart_title(coin, color, theme))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border_color));
+    if let Some(holding) = holding.filter(|_| coin.price > 0.0) {
+        block = block.title_bottom(position_line(coin, holding, theme));
+    }
+
+    let overlays = overlay_lines(coin, color, theme);
+
+    let (area, oscillator_area) = match coin.oscillator {
+        Some(_) if area.height >= MIN_OSCILLATOR_PANE_HEIGHT => {
+            let [chart, oscillator] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(OSCILLATOR_HEIGHT)])
+                    .areas(area);
+            (chart, Some(oscillator))
+        }
+        _ => (area, None),
+    };
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let (plot_area, volume_area) = if coin.show_volume && inner.height >= MIN_VOLUME_PANE_HEIGHT {
+        let [plot, volume] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(VOLUME_HEIGHT)]).areas(inner);
+        (plot, Some(volume))
+    } else {
+        (inner, None)
+    };
+
+    let span = if coin.show_candles && area.width >= MIN_CANDLE_WIDTH {
+        render_candles(frame, plot_area, coin, &overlays, theme)
+    } else {
+        render_line_chart(frame, plot_area, coin, &overlays, color, theme)
+    };
+    if let Some(volume_area) = volume_area {
+        render_volume(frame, volume_area, coin, &span, theme);
+    }
+    if let Some(oscillator_area) = oscillator_area {
+        render_oscillator(
+            frame,
+            oscillator_area,
+            coin,
+            &span,
+            color,
+            border_color,
+            theme,
+        );
+    }
+}
+
+fn render_line_chart(
+    frame: &mut Frame,
+    area: Rect,
+    coin: &CoinData,
+    overlays: &[OverlayLine],
+    color: Color,
+    theme: &Theme,
+) -> PlotSpan {
     let data = coin.history_data();
     let (y_min, y_max) = coin.price_bounds();
 
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(&data);
+
+    let x_max = coin.price_history.len().max(60) as f64;
+    let time_labels = coin.time_labels();
+    let y_labels = [
+        format_price_short(y_min, coin),
+        format_price_short(y_max, coin),
+    ];
+    let x_labels: Vec<Span> = time_labels
+        .iter()
+        .map(|s| Span::styled(s.as_str(), Style::default().fg(theme.muted)))
+        .collect();
+
+    // Overlays first so the price line stays on top
+    let mut datasets: Vec<Dataset> = overlays
+        .iter()
+        .map(|line| {
+            let dataset = Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(line.color))
+                .data(&line.points);
+            match &line.name {
+                Some(name) => dataset.name(name.clone()),
+                None => dataset,
+            }
+        })
+        .collect();
+    datasets.push(dataset);
+
+    let chart = Chart::new(datasets)
+        .legend_position(Some(LegendPosition::TopLeft))
+        .hidden_legend_constraints((Ratio(1, 2), Ratio(1, 2)))
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(theme.muted))
+                .bounds([0.0, x_max])
+                .labels(x_labels),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(theme.muted))
+                .bounds([y_min, y_max])
+                .labels(
+                    y_labels
+                        .iter()
+                        .map(|label| Span::styled(label.as_str(), Style::default().fg(theme.muted)))
+                        .collect::<Vec<_>>(),
+                ),
+        );
+
+    frame.render_widget(chart, area);
+
+    // Mirrors how the chart sizes its label column, see `PlotSpan`
+    let first_x_label = time_labels.first().map_or(0, |l| l.chars().count());
+    let label_width = y_labels
+        .iter()
+        .map(|l| l.chars().count())
+        .chain([first_x_label.saturating_sub(1)])
+        .max()
+        .unwrap_or_default();
+    PlotSpan {
+        x_bounds: [0.0, x_max],
+        label_width,
+    }
+}
+
+/// Per-candle volume as bars, green or red by the candle's direction.
+fn render_volume(frame: &mut Frame, area: Rect, coin: &CoinData, span: &PlotSpan, theme: &Theme) {
+    let mut up = Vec::new();
+    let mut down = Vec::new();
+    for (i, candle) in coin.price_history.iter().enumerate() {
+        let bar = (i as f64, candle.volume);
+        if candle.is_up() {
+            up.push(bar);
+        } else {
+            down.push(bar);
+        }
+    }
+    let max_volume = coin
+        .price_history
+        .iter()
+        .map(|c| c.volume)
+        .fold(0.0, f64::max);
+
+    let bars = [(up, theme.positive), (down, theme.negative)];
+    let datasets = bars
+        .iter()
+        .map(|(bars, color)| {
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Bar)
+                .style(Style::default().fg(*color))
+                .data(bars)
+        })
+        .collect::<Vec<_>>();
+    let muted = Style::default().fg(theme.muted);
+    // Polled samples carry no volume; leave the scale blank until klines do
+    let top_label = if max_volume > 0.0 {
+        format_volume(max_volume)
+    } else {
+        String::new()
+    };
+    let y_labels =
+        ["", &top_label].map(|text| Span::styled(format!("{:>1$}", text, span.label_width), muted));
+
+    let chart = Chart::new(datasets)
+        .x_axis(Axis::default().bounds(span.x_bounds))
+        .y_axis(
+            Axis::default()
+                .style(muted)
+                .bounds([0.0, max_volume.max(f64::MIN_POSITIVE)])
+                .labels(y_labels),
+        );
+
+    frame.render_widget(chart, area);
+}
+
+/// Where the price plot sits horizontally, so the oscillator below can use
+/// the same x range and a label column of the same width.
+struct PlotSpan {
+    x_bounds: [f64; 2],
+    label_width: usize,
+}
+
+fn render_oscillator(
+    frame: &mut Frame,
+    area: Rect,
+    coin: &CoinData,
+    span: &PlotSpan,
+    color: Color,
+    border_color: Color,
+    theme: &Theme,
+) {
+    let Some(oscillator) = coin.oscillator else {
+        return;
+    };
+    let [x_lo, x_hi] = span.x_bounds;
+    let muted = Style::default().fg(theme.muted);
+
+    let (title, series, y_bounds, y_labels) = match oscillator {
+        Oscillator::Rsi => {
+            let rsi: Vec<(f64, f64)> = coin
+                .rsi
+                .values()
+                .iter()
+                .enumerate()
+                .filter_map(|(i, v)| v.map(|r| (i as f64, r)))
+                .collect();
+            let mut title = vec![Span::styled(format!(" RSI({}) ", RSI_PERIOD), muted)];
+            if let Some(value) = coin.rsi.last() {
+                let value_color = if value >= RSI_OVERBOUGHT {
+                    theme.negative
+                } else if value <= RSI_OVERSOLD {
+                    theme.positive
+                } else {
+                    theme.text
+                };
+                title.push(Span::styled(
+                    format!("{:.1} ", value),
+                    Style::default().fg(value_color),
+                ));
+            }
+            let guide = |y: f64| (vec![(x_lo, y), (x_hi, y)], theme.border, GraphType::Line);
+            let series = vec![
+                guide(RSI_OVERSOLD),
+                guide(RSI_OVERBOUGHT),
+                (rsi, color, GraphType::Line),
+            ];
+            (
+                title,
+                series,
+                [0.0, 100.0],
+                ["0".to_string(), "100".to_string()],
+            )
+        }
+        Oscillator::Macd => {
+            let mut macd = Vec::new();
+            let mut signal = Vec::new();
+            let mut rising = Vec::new();
+            let mut falling = Vec::new();
+            for (i, point) in coin.macd.values().iter().enumerate() {
+                let Some(point) = point else {
+                    continue;
+                };
+                let x = i as f64;
+                macd.push((x, point.macd));
+                if let (Some(s), Some(histogram)) = (point.signal, point.histogram()) {
+                    signal.push((x, s));
+                    if histogram >= 0.0 {
+                        rising.push((x, histogram));
+                    } else {
+                        falling.push((x, histogram));
+                    }
+                }
+            }
+
+            let mut title = vec![Span::styled(
+                format!(" MACD({},{},{}) ", MACD_FAST, MACD_SLOW, MACD_SIGNAL),
+                muted,
+            )];
+            if let Some(point) = coin.macd.last() {
+                title.push(Span::styled(
+                    format!("{} ", format_oscillator_value(point.macd, coin)),
+                    Style::default().fg(color),
+                ));
+                if let (Some(s), Some(histogram)) = (point.signal, point.histogram()) {
+                    let histogram_color = if histogram >= 0.0 {
+                        theme.positive
+                    } else {
+                        theme.negative
+                    };
+                    title.push(Span::styled(
+                        format!("{} ", format_oscillator_value(s, coin)),
+                        Style::default().fg(theme.highlight),
+                    ));
+                    title.push(Span::styled(
+                        format!("{} ", format_oscillator_value(histogram, coin)),
+                        Style::default().fg(histogram_color),
+                    ));
+                }
+            }
+
+            // Keep the zero line in range so the histogram has a baseline
+            let (lo, hi) = [&macd, &signal, &rising, &falling]
+                .into_iter()
+                .flatten()
+                .fold((0.0f64, 0.0f64), |(lo, hi), (_, y)| {
+                    (lo.min(*y), hi.max(*y))
+                });
+            let padding = ((hi - lo) * 0.1).max(f64::MIN_POSITIVE);
+            let (y_min, y_max) = (lo - padding, hi + padding);
+
+            // Histogram first so the lines stay on top
+            let series = vec![
+                (rising, theme.positive, GraphType::Bar),
+                (falling, theme.negative, GraphType::Bar),
+                (signal, theme.highlight, GraphType::Line),
+                (macd, color, GraphType::Line),
+            ];
+            (
+                title,
+                series,
+                [y_min, y_max],
+                [
+                    format_oscillator_value(y_min, coin),
+                    format_oscillator_value(y_max, coin),
+                ],
+            )
+        }
+    };
+
+    let datasets = series
+        .iter()
+        .map(|(points, color, graph_type)| {
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(*graph_type)
+                .style(Style::default().fg(*color))
+                .data(points)
+        })
+        .collect();
+    let y_labels: Vec<Span> = y_labels
+        .into_iter()
+        .map(|text| Span::styled(format!("{:>1$}", text, span.label_width), muted))
+        .collect();
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(Line::from(title))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(border_color)),
+        )
+        .x_axis(Axis::default().bounds([x_lo, x_hi]))
+        .y_axis(
+            Axis::default()
+                .style(muted)
+                .bounds(y_bounds)
+                .labels(y_labels),
+        );
+
+    frame.render_widget(chart, area);
+}
+
+/// One drawable line of an indicator overlay, in history-index coordinates.
+/// Bollinger Bands give three lines; only the first of each overlay is named
+/// so the legend lists it once.
+struct OverlayLine {
+    name: Option<String>,
+    color: Color,
+    points: Vec<(f64, f64)>,
+}
+
+fn overlay_lines(coin: &CoinData, chart_color: Color, theme: &Theme) -> Vec<OverlayLine> {
+    if !coin.show_indicators {
+        return Vec::new();
+    }
+    let mut colors = theme
+        .series
+        .iter()
+        .copied()
+        .filter(|c| *c != chart_color)
+        .cycle();
+    let mut lines = Vec::new();
+    for series in &coin.indicators {
+        let color = colors.next().unwrap_or(theme.muted);
+        let bands: Vec<(f64, Band)> = series
+            .values()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, v)| v.map(|band| (i as f64, band)))
+            .collect();
+        let mid = bands.iter().map(|(x, b)| (*x, b.mid)).collect();
+        lines.push(OverlayLine {
+            name: Some(series.indicator.to_string()),
+            color,
+            points: mid,
+        });
+        if let Indicator::Bollinger { .. } = series.indicator {
+            for edge in [|b: &Band| b.upper, |b: &Band| b.lower] {
+                lines.push(OverlayLine {
+                    name: None,
+                    color,
+                    points: bands.iter().map(|(x, b)| (*x, edge(b))).collect(),
+                });
+            }
+        }
+    }
+    lines
+}
+
+/// Size, value and unrealized P&L of the position in `coin`.
+fn position_line(coin: &CoinData, holding: &Holding, theme: &Theme) -> Line<'static> {
+    let position = holding.position(coin);
+    let pnl_color = if position.pnl >= 0.0 {
+        theme.positive
+    } else {
+        theme.negative
+    };
+    let plus = if position.pnl > 0.0 { "+" } else { "" };
+    let pnl_percent = position
+        .pnl_percent
+        .map(|p| format!(" ({}{:.2}%)", plus, p))
+        .unwrap_or_default();
+
+    Line::from(vec![
+        Span::styled(
+            format!(" {} {}", holding.quantity(), coin.pair.base),
+            Style::default().fg(theme.muted),
+        ),
+        Span::styled(" │ ", Style::default().fg(theme.border)),
+        Span::styled(
+            format_value(position.value, coin),
+            Style::default().fg(theme.text),
+        ),
+        Span::styled(" │ ", Style::default().fg(theme.border)),
+        Span::styled(
+            format!(
+                "P&L {}{}{} ",
+                plus,
+                format_value(position.pnl, coin),
+                pnl_percent
+            ),
+            Style::default().fg(pnl_color),
+        ),
+    ])
+}
+
+fn chart_title<'a>(coin: &'a CoinData, color: Color, theme: &Theme) -> Line<'a> {
     let change_color = if coin.change_24h >= 0.0 {
-        POSITIVE
+        theme.positive
     } else {
-        PINK
+        theme.negative
     };
 
     let change_arrow = if coin.change_24h >= 0.0 { "▲" } else { "▼" };
 
-    let title = Line::from(vec![
-        Span::styled("◈ ", Style::default().fg(PINK)),
+    Line::from(vec![
+        Span::styled("◈ ", Style::default().fg(theme.accent)),
         Span::styled(
-            coin.display_name.as_str(),
+            coin.pair.to_string(),
             Style::default().fg(color).add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" │ ", Style::default().fg(BORDER)),
         Span::styled(
-            format_price(coin.price),
-            Style::default().fg(TEXT).add_modifier(Modifier::BOLD),
+            format!(" {}", coin.timeframe),
+            Style::default().fg(theme.muted),
         ),
-        Span::styled(" │ ", Style::default().fg(BORDER)),
+        Span::styled(" │ ", Style::default().fg(theme.border)),
+        Span::styled(
+            format_price(coin.price, coin),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" │ ", Style::default().fg(theme.border)),
         Span::styled(
             format!("{} {:.2}%", change_arrow, coin.change_24h.abs()),
             Style::default().fg(change_color),
         ),
-        Span::styled(" │ ", Style::default().fg(BORDER)),
+        Span::styled(" │ ", Style::default().fg(theme.border)),
         Span::styled(
             format!(
                 "H:{} L:{}",
-                format_price_short(coin.high_24h),
-                format_price_short(coin.low_24h)
+                format_price_short(coin.high_24h, coin),
+                format_price_short(coin.low_24h, coin)
             ),
-            Style::default().fg(MUTED),
+            Style::default().fg(theme.muted),
         ),
-        Span::styled(" │ ", Style::default().fg(BORDER)),
+        Span::styled(" │ ", Style::default().fg(theme.border)),
         Span::styled(
             format!("Vol:{}", format_volume(coin.volume_24h)),
-            Style::default().fg(MUTED),
+            Style::default().fg(theme.muted),
         ),
-        Span::styled(" ◈", Style::default().fg(PINK)),
-    ]);
+        Span::styled(" ◈", Style::default().fg(theme.accent)),
+    ])
+}
 
-    let dataset = Dataset::default()
-        .marker(symbols::Marker::Braille)
-        .graph_type(GraphType::Line)
-        .style(Style::default().fg(color))
-        .data(&data);
+/// Which part of a candle covers a slice of the price axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CandleFill {
+    Empty,
+    Wick,
+    Body,
+}
 
-    let x_max = coin.price_history.len().max(60) as f64;
-    let time_labels = coin.time_labels();
-    let x_labels: Vec<Span> = time_labels
+fn candle_fill(candle: &Candle, lo: f64, hi: f64) -> CandleFill {
+    let body_lo = candle.open.min(candle.close);
+    let body_hi = candle.open.max(candle.close);
+    if body_hi >= lo && body_lo < hi {
+        CandleFill::Body
+    } else if candle.high >= lo && candle.low < hi {
+        CandleFill::Wick
+    } else {
+        CandleFill::Empty
+    }
+}
+
+/// Picks a box-drawing glyph for one cell from the fill of its upper and
+/// lower halves, giving candles half-cell vertical resolution.
+fn candle_glyph(top: CandleFill, bottom: CandleFill) -> char {
+    use CandleFill::*;
+    match (top, bottom) {
+        (Body, Body) => '┃',
+        (Body, Wick) => '╿',
+        (Body, Empty) => '╹',
+        (Wick, Body) => '╽',
+        (Wick, Wick) => '│',
+        (Wick, Empty) => '╵',
+        (Empty, Body) => '╻',
+        (Empty, Wick) => '╷',
+        (Empty, Empty) => ' ',
+    }
+}
+
+fn render_candles(
+    frame: &mut Frame,
+    area: Rect,
+    coin: &CoinData,
+    overlays: &[OverlayLine],
+    theme: &Theme,
+) -> PlotSpan {
+    // The oscillator's chart draws its axis line in the last label column
+    let label_width = Y_LABEL_WIDTH as usize - 1;
+    if area.width <= Y_LABEL_WIDTH + 1 || area.height < 3 {
+        return PlotSpan {
+            x_bounds: [0.0, coin.price_history.len().max(60) as f64],
+            label_width,
+        };
+    }
+
+    let [plot_row, axis_row] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(area);
+    let [y_axis, plot] =
+        Layout::horizontal([Constraint::Length(Y_LABEL_WIDTH), Constraint::Min(1)]).areas(plot_row);
+
+    // Leave a gap between candles when there is room for it
+    let total = coin.price_history.len();
+    let step = if plot.width as usize >= total * 2 {
+        2
+    } else {
+        1
+    };
+    let count = total.min(plot.width as usize / step);
+    let skip = total - count;
+    let candles: Vec<&Candle> = coin.price_history.iter().skip(skip).collect();
+
+    let overlay_values = overlays
         .iter()
-        .map(|s| Span::styled(s.as_str(), Style::default().fg(MUTED)))
+        .flat_map(|line| line.points.iter())
+        .filter(|(x, _)| *x as usize >= skip)
+        .map(|(_, y)| *y);
+    let lo = candles
+        .iter()
+        .map(|c| c.low)
+        .chain(overlay_values.clone())
+        .fold(f64::INFINITY, f64::min);
+    let hi = candles
+        .iter()
+        .map(|c| c.high)
+        .chain(overlay_values)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let (y_min, y_max) = if candles.is_empty() {
+        (0.0, 100.0)
+    } else {
+        let padding = ((hi - lo) * 0.1).max(hi.abs() * 1e-6);
+        (lo - padding, hi + padding)
+    };
+
+    let row_span = (y_max - y_min) / plot.height as f64;
+    let buf = frame.buffer_mut();
+    for (i, candle) in candles.iter().enumerate() {
+        let x = plot.x + (i * step) as u16;
+        let color = if candle.is_up() {
+            theme.positive
+        } else {
+            theme.negative
+        };
+        for row in 0..plot.height {
+            // Row 0 is the top of the plot
+            let row_hi = y_max - row_span * row as f64;
+            let row_mid = row_hi - row_span / 2.0;
+            let row_lo = row_hi - row_span;
+            let glyph = candle_glyph(
+                candle_fill(candle, row_mid, row_hi),
+                candle_fill(candle, row_lo, row_mid),
+            );
+            if glyph != ' ' {
+                buf[(x, plot.y + row)].set_char(glyph).set_fg(color);
+            }
+        }
+    }
+
+    // Overlays are dotted into free cells so they never hide a candle
+    for line in overlays {
+        for &(x, y) in &line.points {
+            let Some(i) = (x as usize).checked_sub(skip) else {
+                continue;
+            };
+            let row = (((y_max - y) / row_span) as u16).min(plot.height - 1);
+            let col = plot.x + (i * step) as u16;
+            for col in col..(col + step as u16).min(plot.right()) {
+                let cell = &mut buf[(col, plot.y + row)];
+                if cell.symbol() == " " {
+                    cell.set_char('·').set_fg(line.color);
+                }
+            }
+        }
+    }
+    let legend: Vec<Span> = overlays
+        .iter()
+        .filter_map(|line| {
+            Some(Span::styled(
+                format!("{} ", line.name.as_ref()?),
+                line.color,
+            ))
+        })
         .collect();
+    if !legend.is_empty() {
+        frame.render_widget(Line::from(legend), Rect { height: 1, ..plot });
+    }
 
-    let chart = Chart::new(vec![dataset])
-        .block(
-            Block::default()
-                .title(title)
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(BORDER)),
-        )
-        .x_axis(
-            Axis::default()
-                .style(Style::default().fg(MUTED))
-                .bounds([0.0, x_max])
-                .labels(x_labels),
-        )
-        .y_axis(
-            Axis::default()
-                .style(Style::default().fg(MUTED))
-                .bounds([y_min, y_max])
-                .labels(vec![
-                    Span::styled(format_price_short(y_min), Style::default().fg(MUTED)),
-                    Span::styled(format_price_short(y_max), Style::default().fg(MUTED)),
-                ]),
+    let muted = Style::default().fg(theme.muted);
+    let y_labels = Paragraph::new(vec![
+        Line::from(format_price_short(y_max, coin)),
+        Line::from(""),
+    ])
+    .style(muted);
+    frame.render_widget(y_labels, y_axis);
+    let bottom_label = Rect {
+        y: y_axis.bottom().saturating_sub(1),
+        height: 1,
+        ..y_axis
+    };
+    frame.render_widget(
+        Paragraph::new(format_price_short(y_min, coin)).style(muted),
+        bottom_label,
+    );
+
+    let time_labels = coin.time_labels_from(skip);
+    let axis_area = Rect {
+        x: plot.x,
+        width: plot.width,
+        ..axis_row
+    };
+    for (label, alignment) in
+        time_labels
+            .iter()
+            .zip([Alignment::Left, Alignment::Center, Alignment::Right])
+    {
+        frame.render_widget(
+            Paragraph::new(label.as_str())
+                .style(muted)
+                .alignment(alignment),
+            axis_area,
         );
+    }
 
-    frame.render_widget(chart, area);
+    PlotSpan {
+        x_bounds: [
+            skip as f64,
+            (skip + (plot.width as usize - 1) / step) as f64,
+        ],
+        label_width,
+    }
+}
+
+fn render_alert_log(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let items: Vec<ListItem> = app
+        .alert_log
+        .iter()
+        .skip(app.alert_scroll)
+        .map(|event| {
+            let time = match Local.timestamp_millis_opt(event.at_ms).single() {
+                Some(dt) => dt.format("%H:%M:%S").to_string(),
+                None => "--:--:--".to_string(),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", time), Style::default().fg(theme.muted)),
+                Span::styled(event.message.as_str(), Style::default().fg(theme.text)),
+            ]))
+        })
+        .collect();
+
+    let title = Line::from(vec![
+        Span::styled("◈ ", Style::default().fg(theme.accent)),
+        Span::styled(
+            format!("Alerts ({})", app.alert_log.len()),
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" ◈", Style::default().fg(theme.accent)),
+    ]);
+    let list = List::new(items).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border)),
+    );
+    frame.render_widget(list, area);
 }
 
 fn render_status_bar(frame: &mut Frame, area: Rect, app: &App) {
-    let total_pages = app.total_pages();
+    // The table shows every coin, so pages only matter for charts
+    let total_pages = match app.view {
+        View::Charts => app.total_pages(),
+        View::Table | View::Detail => 1,
+    };
     let page_indicator = if total_pages > 1 {
         format!("Page {}/{}  ", app.page_index + 1, total_pages)
     } else {
         String::new()
     };
-    let nav_label = if total_pages > 1 { "·Page" } else { "" };
 
-    let status = Line::from(vec![
-        Span::raw(" "),
-        Span::styled("Q", Style::default().fg(CYAN).add_modifier(Modifier::BOLD)),
-        Span::styled("·Quit  ", Style::default().fg(MUTED)),
-        Span::styled("R", Style::default().fg(CYAN).add_modifier(Modifier::BOLD)),
-        Span::styled("·Refresh  ", Style::default().fg(MUTED)),
-        Span::styled("←→", Style::default().fg(CYAN).add_modifier(Modifier::BOLD)),
-        Span::styled(nav_label, Style::default().fg(MUTED)),
+    let theme = &app.theme;
+    let key_style = Style::default()
+        .fg(theme.highlight)
+        .add_modifier(Modifier::BOLD);
+    let hint_style = Style::default().fg(theme.muted);
+
+    let mut hints = vec![
+        (app.keymap.label(Action::Quit), "·Quit  "),
+        (app.keymap.label(Action::Refresh), "·Refresh  "),
+        (
+            format!(
+                "{}{}",
+                app.keymap.label(Action::SelectPrev),
+                app.keymap.label(Action::SelectNext)
+            ),
+            "·Select  ",
+        ),
+    ];
+    match app.view {
+        View::Charts => hints.extend([
+            (app.keymap.label(Action::ToggleCandles), "·Candles  "),
+            (app.keymap.label(Action::ToggleIndicators), "·Indicators  "),
+            (app.keymap.label(Action::CycleOscillator), "·RSI/MACD  "),
+            (app.keymap.label(Action::EditHolding), "·Holding  "),
+            (app.keymap.label(Action::AddCoin), "·Add  "),
+            (app.keymap.label(Action::RemoveCoin), "·Remove  "),
+            (app.keymap.label(Action::OpenSelected), "·Detail  "),
+            (app.keymap.label(Action::ToggleTape), "·Tape  "),
+            (app.keymap.label(Action::ToggleTapeFilter), "·Big trades  "),
+            (app.keymap.label(Action::ToggleTable), "·Table  "),
+        ]),
+        View::Detail => hints.extend([
+            (app.keymap.label(Action::ToggleCandles), "·Candles  "),
+            (app.keymap.label(Action::ToggleIndicators), "·Indicators  "),
+            (app.keymap.label(Action::CycleOscillator), "·RSI/MACD  "),
+            (app.keymap.label(Action::ToggleTapeFilter), "·Big trades  "),
+            (app.keymap.label(Action::Back), "·Back  "),
+        ]),
+        View::Table => hints.extend([
+            (app.keymap.label(Action::CycleSort), "·Sort  "),
+            (app.keymap.label(Action::OpenSelected), "·Chart  "),
+            (app.keymap.label(Action::ToggleTable), "·Charts  "),
+        ]),
+    }
+    hints.extend([
+        (app.keymap.label(Action::NextTimeframe), "·Timeframe  "),
+        (app.keymap.label(Action::ToggleAlertLog), "·Alerts  "),
+    ]);
+    if total_pages > 1 {
+        hints.push((
+            format!(
+                "{}{}",
+                app.keymap.label(Action::PrevPage),
+                app.keymap.label(Action::NextPage)
+            ),
+            "·Page",
+        ));
+    }
+
+    let mut spans = vec![Span::raw(" ")];
+    for (key, label) in hints {
+        spans.push(Span::styled(key, key_style));
+        spans.push(Span::styled(label, hint_style));
+    }
+    spans.extend([
         Span::raw("          "),
-        Span::styled(&page_indicator, Style::default().fg(PINK)),
+        Span::styled(&page_indicator, Style::default().fg(theme.accent)),
+        Span::styled(
+            format!("TF {}  ", app.timeframe),
+            Style::default().fg(theme.highlight),
+        ),
+        Span::styled(
+            if app.streaming { "● LIVE  " } else { "" },
+            Style::default().fg(theme.positive),
+        ),
         Span::styled(
             format!("Updated {}", app.last_update_str()),
-            Style::default().fg(MUTED),
+            Style::default().fg(theme.muted),
         ),
         Span::raw("  "),
-        Span::styled(&app.status_message, Style::default().fg(CYAN)),
     ]);
+    if let Some(limit) = app.rate_limit.filter(|l| l.used_weight > 0) {
+        // Binance resets the budget every minute; warn well before it runs out
+        let busy = limit.used_weight * 5 >= limit.weight_limit * 4;
+        spans.push(Span::styled(
+            format!("W {}/{}  ", limit.used_weight, limit.weight_limit),
+            Style::default().fg(if busy { theme.negative } else { theme.muted }),
+        ));
+    }
+    match app.rate_limited_for() {
+        Some(secs) => spans.push(Span::styled(
+            format!("Rate limited, resuming in {}s", secs),
+            Style::default().fg(theme.negative),
+        )),
+        None => spans.push(Span::styled(
+            &app.status_message,
+            Style::default().fg(theme.highlight),
+        )),
+    }
+    let status = match &app.prompt {
+        Some(prompt) => {
+            let mut spans = vec![
+                Span::raw(" "),
+                Span::styled(prompt.label(), hint_style),
+                Span::styled(format!("{}█", prompt.input), key_style),
+                Span::raw("  "),
+            ];
+            for (i, pair) in app.suggestions().iter().enumerate() {
+                let style = if i == prompt.choice {
+                    key_style.add_modifier(Modifier::REVERSED)
+                } else {
+                    hint_style
+                };
+                spans.push(Span::styled(pair.to_string(), style));
+                spans.push(Span::raw(" "));
+            }
+            spans.push(Span::styled(
+                prompt.error.clone().unwrap_or_default(),
+                Style::default().fg(theme.negative),
+            ));
+            Line::from(spans)
+        }
+        None => Line::from(spans),
+    };
+    let mut lines = vec![status];
+    if !app.portfolio.is_empty() {
+        lines.push(portfolio_line(&app.portfolio.summaries(&app.coins), theme));
+    }
 
-    let paragraph = Paragraph::new(status).block(
+    let paragraph = Paragraph::new(lines).block(
         Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(BORDER)),
+            .border_style(Style::default().fg(theme.border)),
     );
 
     frame.render_widget(paragraph, area);
 }
 
-fn format_volume(vol: f64) -> String {
+/// Total value, day change and allocation for each quote held.
+fn portfolio_line(summaries: &[Summary], theme: &Theme) -> Line<'static> {
+    let mut spans = vec![Span::styled(
+        " Portfolio ",
+        Style::default().fg(theme.accent),
+    )];
+    if summaries.is_empty() {
+        spans.push(Span::styled(
+            "waiting for prices",
+            Style::default().fg(theme.muted),
+        ));
+    }
+    for (i, summary) in summaries.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled("  ║  ", Style::default().fg(theme.border)));
+        }
+        let decimals = value_decimals(&summary.quote, MAX_SHORT_DECIMALS);
+        let change = summary.day_change_percent();
+        let (arrow, change_color) = if change >= 0.0 {
+            ("▲", theme.positive)
+        } else {
+            ("▼", theme.negative)
+        };
+        let plus = if summary.day_change > 0.0 { "+" } else { "" };
+        let allocations: Vec<String> = summary
+            .allocations
+            .iter()
+            .map(|(base, share)| format!("{} {:.0}%", base, share))
+            .collect();
+        spans.extend([
+            Span::styled(
+                format_amount(summary.value, decimals, &summary.quote),
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(
+                    " {} {:.2}% ({}{})",
+                    arrow,
+                    change.abs(),
+                    plus,
+                    format_amount(summary.day_change, decimals, &summary.quote)
+                ),
+                Style::default().fg(change_color),
+            ),
+            Span::styled(" │ ", Style::default().fg(theme.border)),
+            Span::styled(allocations.join(" · "), Style::default().fg(theme.muted)),
+        ]);
+    }
+    Line::from(spans)
+}
+
+/// Trade size: four decimals for small amounts, compact above a thousand.
+fn format_quantity(quantity: f64) -> String {
+    if quantity >= 1_000.0 {
+        format_volume(quantity)
+    } else {
+        format!("{:.4}", quantity)
+    }
+}
+
+/// Compact amount, e.g. `950`, `12.3K` or `4.5B`.
+pub(crate) fn format_volume(vol: f64) -> String {
     if vol >= 1_000_000_000.0 {
         format!("{:.1}B", vol / 1_000_000_000.0)
     } else if vol >= 1_000_000.0 {
@@ -206,39 +1616,116 @@ fn format_volume(vol: f64) -> String {
     }
 }
 
-fn format_price(price: f64) -> String {
-    if price >= 1000.0 {
-        // Round to cents first to handle edge cases like 99.999 → 100.00
-        let rounded = (price * 100.0).round() / 100.0;
-        let whole = rounded as i64;
-        let frac = ((rounded - whole as f64) * 100.0).round().clamp(0.0, 99.0) as u8;
-        let s = whole.to_string();
-        let mut result = String::with_capacity(s.len() + s.len() / 3);
-        for (i, c) in s.chars().enumerate() {
-            if i > 0 && (s.len() - i).is_multiple_of(3) {
-                result.push(',');
-            }
-            result.push(c);
+/// Full price to the pair's tick size with thousands separators, e.g.
+/// `$42,069.42` or `0.05234 BTC`.
+pub(crate) fn format_price(price: f64, coin: &CoinData) -> String {
+    format_amount(price, coin.price_decimals, &coin.pair.quote)
+}
+
+/// Position value in the coin's quote: cents for fiat-like quotes, the
+/// pair's price precision otherwise.
+fn format_value(value: f64, coin: &CoinData) -> String {
+    format_amount(
+        value,
+        value_decimals(&coin.pair.quote, coin.price_decimals),
+        &coin.pair.quote,
+    )
+}
+
+fn value_decimals(quote: &str, price_decimals: usize) -> usize {
+    if currency_sign(quote).is_some() {
+        2
+    } else {
+        price_decimals
+    }
+}
+
+/// `amount` of `quote` with thousands separators and the quote's sign.
+fn format_amount(amount: f64, decimals: usize, quote: &str) -> String {
+    // Formatting first rounds correctly, e.g. 99.999 → 100.00
+    let fixed = format!("{:.*}", decimals, amount.abs());
+    let (whole, frac) = match fixed.split_once('.') {
+        Some((whole, frac)) => (whole, Some(frac)),
+        None => (fixed.as_str(), None),
+    };
+    let mut digits = String::with_capacity(fixed.len() + whole.len() / 3);
+    for (i, c) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i).is_multiple_of(3) {
+            digits.push(',');
         }
-        format!("${}.{:02}", result, frac)
+        digits.push(c);
+    }
+    if let Some(frac) = frac {
+        digits.push('.');
+        digits.push_str(frac);
+    }
+    // No "-$0.00" for amounts that round to zero
+    let minus = if amount < 0.0 && fixed.bytes().any(|b| (b'1'..=b'9').contains(&b)) {
+        "-"
     } else {
-        format!("${:.2}", price)
+        ""
+    };
+    match currency_sign(quote) {
+        Some(sign) => format!("{}{}{}", minus, sign, digits),
+        None => format!("{}{} {}", minus, digits, quote),
     }
 }
 
-fn format_price_short(price: f64) -> String {
-    if price >= 1_000_000.0 {
-        format!("${:.1}M", price / 1_000_000.0)
+/// Compact price for axis labels; quotes without a currency sign are left
+/// bare to fit the label column.
+pub(crate) fn format_price_short(price: f64, coin: &CoinData) -> String {
+    let amount = if price >= 1_000_000.0 {
+        format!("{:.1}M", price / 1_000_000.0)
     } else if price >= 1_000.0 {
-        format!("${:.1}k", price / 1_000.0)
+        format!("{:.1}k", price / 1_000.0)
     } else {
-        format!("${:.2}", price)
+        format!("{:.*}", coin.price_decimals.min(MAX_SHORT_DECIMALS), price)
+    };
+    format!(
+        "{}{}",
+        coin.pair.currency_sign().unwrap_or_default(),
+        amount
+    )
+}
+
+/// Compact oscillator reading in quote units, without a currency sign.
+fn format_oscillator_value(value: f64, coin: &CoinData) -> String {
+    if value.abs() >= 1_000.0 {
+        format!("{:.1}k", value / 1_000.0)
+    } else {
+        format!("{:.*}", coin.price_decimals.min(MAX_SHORT_DECIMALS), value)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::source::Pair;
+
+    #[test]
+    fn test_candle_fill() {
+        let candle = Candle {
+            open_time: 0,
+            open: 100.0,
+            high: 120.0,
+            low: 90.0,
+            close: 110.0,
+            volume: 1.0,
+        };
+        assert_eq!(candle_fill(&candle, 104.0, 106.0), CandleFill::Body);
+        assert_eq!(candle_fill(&candle, 114.0, 116.0), CandleFill::Wick);
+        assert_eq!(candle_fill(&candle, 92.0, 94.0), CandleFill::Wick);
+        assert_eq!(candle_fill(&candle, 130.0, 140.0), CandleFill::Empty);
+    }
+
+    #[test]
+    fn test_candle_glyph() {
+        use CandleFill::*;
+        assert_eq!(candle_glyph(Body, Body), '┃');
+        assert_eq!(candle_glyph(Wick, Body), '╽');
+        assert_eq!(candle_glyph(Empty, Wick), '╷');
+        assert_eq!(candle_glyph(Empty, Empty), ' ');
+    }
 
     #[test]
     fn test_format_volume() {
@@ -250,18 +1737,71 @@ mod tests {
 
     #[test]
     fn test_format_price() {
-        assert_eq!(format_price(0.50), "$0.50");
-        assert_eq!(format_price(99.99), "$99.99");
-        assert_eq!(format_price(1000.00), "$1,000.00");
-        assert_eq!(format_price(42069.42), "$42,069.42");
-        assert_eq!(format_price(100000.00), "$100,000.00");
+        let usdt = CoinData::new(Pair::new("BTC", "USDT"));
+        assert_eq!(format_price(0.50, &usdt), "$0.50");
+        assert_eq!(format_price(99.99, &usdt), "$99.99");
+        assert_eq!(format_price(99.999, &usdt), "$100.00");
+        assert_eq!(format_price(1000.00, &usdt), "$1,000.00");
+        assert_eq!(format_price(42069.42, &usdt), "$42,069.42");
+        assert_eq!(format_price(100000.00, &usdt), "$100,000.00");
+        assert_eq!(
+            format_price(1234.5, &CoinData::new(Pair::new("BTC", "EUR"))),
+            "€1,234.50"
+        );
+
+        let mut eth_btc = CoinData::new(Pair::new("ETH", "BTC"));
+        eth_btc.price_decimals = 5;
+        assert_eq!(format_price(0.05234, &eth_btc), "0.05234 BTC");
+
+        let mut shib = CoinData::new(Pair::new("SHIB", "USDT"));
+        shib.price_decimals = 8;
+        assert_eq!(format_price(0.00001234, &shib), "$0.00001234");
+        shib.price_decimals = 0;
+        assert_eq!(format_price(1234.0, &shib), "$1,234");
+    }
+
+    #[test]
+    fn test_format_value() {
+        let mut btc = CoinData::new(Pair::new("BTC", "USDT"));
+        btc.price_decimals = 1;
+        assert_eq!(format_value(-2500.0, &btc), "-$2,500.00");
+        assert_eq!(format_value(-0.001, &btc), "$0.00");
+
+        let mut eth_btc = CoinData::new(Pair::new("ETH", "BTC"));
+        eth_btc.price_decimals = 5;
+        assert_eq!(format_value(1.5, &eth_btc), "1.50000 BTC");
     }
 
     #[test]
     fn test_format_price_short() {
-        assert_eq!(format_price_short(0.50), "$0.50");
-        assert_eq!(format_price_short(999.99), "$999.99");
-        assert_eq!(format_price_short(1500.0), "$1.5k");
-        assert_eq!(format_price_short(1_500_000.0), "$1.5M");
+        let usdt = CoinData::new(Pair::new("BTC", "USDT"));
+        assert_eq!(format_price_short(0.50, &usdt), "$0.50");
+        assert_eq!(format_price_short(999.99, &usdt), "$999.99");
+        assert_eq!(format_price_short(1500.0, &usdt), "$1.5k");
+        assert_eq!(format_price_short(1_500_000.0, &usdt), "$1.5M");
+
+        let mut shib = CoinData::new(Pair::new("SHIB", "USDT"));
+        shib.price_decimals = 8;
+        assert_eq!(format_price_short(0.00001234, &shib), "$0.000012");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[], 10), "");
+        assert_eq!(sparkline(&[5.0, 5.0], 10), "▄▄");
+        assert_eq!(
+            sparkline(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0], 10),
+            "▁▂▃▄▅▆▇█"
+        );
+        // Squeezed to the width, keeping both ends
+        assert_eq!(sparkline(&[0.0, 9.0, 1.0, 9.0, 7.0], 3), "▁▂█");
+    }
+
+    #[test]
+    fn test_format_oscillator_value() {
+        let usdt = CoinData::new(Pair::new("BTC", "USDT"));
+        assert_eq!(format_oscillator_value(-0.5, &usdt), "-0.50");
+        assert_eq!(format_oscillator_value(-1520.0, &usdt), "-1.5k");
+        assert_eq!(format_oscillator_value(42.0, &usdt), "42.00");
     }
 }